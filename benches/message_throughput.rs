@@ -0,0 +1,84 @@
+//! Measures how fast messages go through earning by chatting when most members are on cooldown,
+//! like on a busy guild. The currencies come from the guild's cached currency list, as they do
+//! for real messages.
+//!
+//! It needs a database, the same as the bot itself, so run it with `cargo bench` only where one
+//! is configured.
+
+use chrono::{ Duration, Utc };
+use conebot_rust::{
+    db::{ models::{ currency::builder::Builder, earn_cooldown::EarnCooldown, Currency }, CLIENT },
+    event_handler::message::{ earn, Chatter },
+    init_env,
+    mechanics::{ anti_farming::MessageFacts, pending_earnings },
+};
+use criterion::{ criterion_group, criterion_main, Criterion, Throughput };
+use futures::StreamExt;
+use mongodb::{ bson::doc, Collection };
+use serenity::model::prelude::{ ChannelId, GuildId, RoleId, UserId };
+
+const BENCH_GUILD_ID: u64 = 987_654_321;
+const CURR_NAME: &str = "earn benchmark";
+/// How many messages make up one iteration.
+const MESSAGES: u64 = 1_000;
+const USERS: u64 = 500;
+/// How many messages are handled at once, like events from the gateway would be.
+const CONCURRENCY: usize = 64;
+
+fn message_throughput(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let guild_id = GuildId::new(BENCH_GUILD_ID);
+    let cooldowns: Collection<EarnCooldown> = runtime.block_on(async {
+        init_env().await;
+        let cooldowns = CLIENT.get().await.database("conebot").collection("earnCooldowns");
+        cooldowns.delete_many(doc! { "CurrName": CURR_NAME }, None).await.unwrap();
+        let existing = Currency::try_from_name(guild_id.into(), CURR_NAME.to_owned()).await;
+        if let Some(existing) = existing.unwrap() {
+            Currency::delete_currency(existing).await.unwrap();
+        }
+        let mut builder = Builder::new(guild_id.into(), CURR_NAME.to_owned(), "B".to_owned());
+        builder.earn_by_chat(true).earn_timeout(Duration::seconds(60));
+        builder.build().await.unwrap();
+        cooldowns
+    });
+
+    let roles: &[RoleId] = &[RoleId::new(1)];
+    let channel_lineage: &[ChannelId] = &[ChannelId::new(2), ChannelId::new(3)];
+    let mut group = c.benchmark_group("message");
+    group.throughput(Throughput::Elements(MESSAGES));
+    group.bench_function("earn", |b| {
+        b.to_async(&runtime).iter(|| {
+            let now = Utc::now();
+            futures::stream::iter(0..MESSAGES).for_each_concurrent(CONCURRENCY, move |i| {
+                async move {
+                    let content = format!("message number {i} about nothing in particular");
+                    let chatter = Chatter {
+                        guild_id,
+                        user_id: UserId::new(1 + (i % USERS)),
+                        roles,
+                        channel_lineage,
+                        facts: MessageFacts {
+                            content: &content,
+                            repeated: false,
+                            account_created: now - Duration::days(365),
+                            joined_at: Some(now - Duration::days(30)),
+                        },
+                    };
+                    let currencies = Currency::try_from_guild(guild_id.into()).await.unwrap();
+                    earn(&chatter, &currencies, now).await.unwrap();
+                }
+            })
+        });
+    });
+    group.finish();
+
+    runtime.block_on(async {
+        pending_earnings::flush().await;
+        cooldowns.delete_many(doc! { "CurrName": CURR_NAME }, None).await.unwrap();
+        let currency = Currency::try_from_name(guild_id.into(), CURR_NAME.to_owned()).await;
+        Currency::delete_currency(currency.unwrap().unwrap()).await.unwrap();
+    });
+}
+
+criterion_group!(benches, message_throughput);
+criterion_main!(benches);
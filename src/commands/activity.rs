@@ -0,0 +1,71 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::{ CreateCommand, CreateEmbed, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    db::models::activity::Activity,
+    mechanics::activity::{ do_activity, ActivityOutcome },
+    ACCENT_COLOUR,
+};
+
+/// Runs one of the activity commands staff set up in the guild, like `/work`.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - There is no activity with the name of the command.
+/// - Any error from doing the activity.
+pub async fn run(command: &CommandInteraction, http: &Context) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+    let member = command.member
+        .as_ref()
+        .ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+    let activity = Activity::try_from_name(guild_id.into(), &command.data.name).await?.ok_or_else(
+        || anyhow!("Unknown command: {}", command.data.name)
+    )?;
+
+    let outcome = do_activity(guild_id, command.user.id, &member.roles, &activity, http).await?;
+    let embed = match outcome {
+        ActivityOutcome::OnCooldown(available) =>
+            CreateEmbed::default()
+                .title("Not yet")
+                .description(
+                    format!("You can {} again <t:{}:R>.", activity.name(), available.timestamp())
+                ),
+        ActivityOutcome::Done(drops) => {
+            let mut description = activity
+                .message()
+                .map(|m| format!("{m}\n"))
+                .unwrap_or_default();
+            for (name, amount) in &drops {
+                description.push_str(&format!("**{amount}x** {name}\n"));
+            }
+            if drops.is_empty() {
+                description.push_str("Nothing this time.\n");
+            }
+            CreateEmbed::default().title(activity.name()).description(description)
+        }
+        ActivityOutcome::Failed(lost) => {
+            let mut description = activity
+                .fail_message()
+                .map_or_else(|| "It did not work out.\n".to_owned(), |m| format!("{m}\n"));
+            if let Some((curr_name, amount)) = lost {
+                description.push_str(&format!("You lost **{amount}** {curr_name}.\n"));
+            }
+            CreateEmbed::default().title(activity.name()).description(description)
+        }
+    };
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().embed(embed.colour(ACCENT_COLOUR))
+    ).await?;
+    Ok(())
+}
+
+/// The guild command of an activity.
+pub fn command(activity: &Activity) -> CreateCommand {
+    CreateCommand::new(activity.name()).description(activity.description()).dm_permission(false)
+}
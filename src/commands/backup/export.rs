@@ -0,0 +1,54 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateAttachment, CreateCommandOption, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{ db::backup::GuildBackup, event_handler::command_handler::CommandOptions };
+
+/// Sends a backup of the economy of the guild as a JSON file.
+///
+/// # Errors
+///
+/// This function can return an error if any `MongoDB` error occurs or the response fails.
+pub async fn run(
+    _options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command can't be performed in DMs."))?;
+
+    let backup = GuildBackup::export(guild_id.into()).await?;
+    let json = serde_json::to_vec_pretty(&backup)?;
+    let file_name = format!(
+        "economy-{}-{}.json",
+        guild_id.get(),
+        backup.created_at.format("%Y-%m-%d-%H%M%S")
+    );
+    let content = format!(
+        "Backed up {} currencies, {} items, {} drop table parts, {} store entries, {} balances \
+        and {} inventory entries.",
+        backup.currencies.len(),
+        backup.items.len(),
+        backup.drop_tables.len(),
+        backup.store_entries.len(),
+        backup.balances.len(),
+        backup.inventories.len()
+    );
+    command.edit_response(
+        &http,
+        EditInteractionResponse::new()
+            .content(content)
+            .new_attachment(CreateAttachment::bytes(json, file_name))
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "export",
+        "Get a file with the currencies, items, store, balances and inventories of the server."
+    )
+}
@@ -0,0 +1,38 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::CreateCommand,
+    client::Context,
+    model::Permissions,
+};
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod export;
+pub mod restore;
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+
+    match cmd_name.as_str() {
+        "export" => export::run(cmd_options, command, http).await?,
+        "restore" => restore::run(cmd_options, command, http).await?,
+        &_ => anyhow::bail!("Unknown backup subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("backup")
+        .description("Back up the economy of the server or restore a backup of it.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(export::option())
+        .add_option(restore::option())
+}
@@ -0,0 +1,96 @@
+use anyhow::{ anyhow, bail, Result };
+use core::str::FromStr;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    db::backup::{ GuildBackup, RestoreMode },
+    event_handler::command_handler::CommandOptions,
+};
+
+/// Backups bigger than this are not downloaded.
+const MAX_BACKUP_BYTES: u32 = 25 * 1024 * 1024;
+
+/// Restores a backup file made by the export subcommand into the guild.
+///
+/// # Errors
+///
+/// This function can return an error if the file is not a backup, the backup can not be
+/// restored, or any `MongoDB` error occurs.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let attachment_id = options
+        .get_attachment_value(FILE_OPTION_NAME)
+        .ok_or_else(|| anyhow!("Backup file not found."))??;
+    let mode = RestoreMode::from_str(
+        &options
+            .get_string_value(MODE_OPTION_NAME)
+            .ok_or_else(|| anyhow!("Restore mode not found."))??
+    )?;
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command can't be performed in DMs."))?;
+
+    let attachment = command.data.resolved.attachments
+        .get(&attachment_id)
+        .ok_or_else(|| anyhow!("Backup file not found."))?;
+    if attachment.size > MAX_BACKUP_BYTES {
+        bail!("Backup file is too big.");
+    }
+    let bytes = attachment.download().await?;
+    let backup: GuildBackup = serde_json
+        ::from_slice(&bytes)
+        .map_err(|e| anyhow!("File is not a backup: {e}"))?;
+
+    let restored = backup.restore(guild_id.into(), mode).await?;
+
+    let mut content = format!(
+        "Restored the backup taken <t:{}:f>.\n",
+        backup.created_at.timestamp()
+    );
+    for part in restored {
+        content.push_str(
+            &format!(
+                "- {}: {} added, {} kept, {} removed\n",
+                part.collection,
+                part.inserted,
+                part.skipped,
+                part.deleted
+            )
+        );
+    }
+    command.edit_response(&http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
+
+const FILE_OPTION_NAME: &str = "file";
+const MODE_OPTION_NAME: &str = "mode";
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "restore",
+        "Restore a backup of the economy. Either all of it is restored or none of it."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Attachment,
+                FILE_OPTION_NAME,
+                "The backup file made with /backup export."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                MODE_OPTION_NAME,
+                "Whether to keep what the server has and only add what is missing, or replace it."
+            )
+                .required(true)
+                .add_string_choice("keep", "keep")
+                .add_string_choice("replace", "replace")
+        )
+}
@@ -16,6 +16,7 @@ use serenity::{
 use crate::{
     db::{ models::{ Balance, Balances, Currency }, uniques::DbGuildId, ArcTokioRwLockOption },
     event_handler::command_handler::CommandOptions,
+    util::money::Money,
     ACCENT_COLOUR,
 };
 
@@ -132,7 +133,7 @@ fn single_currency_embed<'a>(
                 currency.curr_name().as_str()
             )
         )
-        .description(format!("{}{}", currency.symbol(), balance.amount()))
+        .description(describe(currency.symbol(), balance.amount(), balance.bank(), currency.bank()))
         .colour(Colour::DARK_GREEN)
         .thumbnail(target.face())
         .timestamp(chrono::Utc::now())
@@ -149,17 +150,17 @@ async fn multi_currency_embed(
     let t = try_join_all(
         balances
             .iter()
-            .map(|b| async {
-                b.currency().await.map(|c| anyhow::Ok((c, b.curr_name(), b.amount())))
+            .map(|b| async move {
+                b.currency().await.map(|c| anyhow::Ok((c, b)))
             })
     ).await?
         .into_iter()
         .collect::<Result<Vec<_>>>()?
         .into_iter()
-        .filter(|(c, _, _)| c.is_some())
-        .map(|(a, b, c)| (a.unwrap(), b, c))
+        .filter(|(c, _)| c.is_some())
+        .map(|(c, b)| (c.unwrap(), b))
         .collect::<Vec<_>>();
-    for (curr, n, a) in t {
+    for (curr, b) in t {
         let currency = curr.read().await;
         let Some(currency_) = currency.as_ref() else {
             continue;
@@ -168,8 +169,8 @@ async fn multi_currency_embed(
             continue;
         }
         let symbol = currency_.symbol();
-        let title = format!("{symbol}{n}");
-        let description = format!("{symbol}{a}");
+        let title = format!("{symbol}{}", b.curr_name());
+        let description = describe(symbol, b.amount(), b.bank(), currency_.bank());
         field_data.push((title, description, true));
         drop(currency);
     }
@@ -186,6 +187,16 @@ async fn multi_currency_embed(
     )
 }
 
+/// What a member has of a currency. The bank is only shown if the currency has one, or if there is
+/// still something left in it from when it did.
+fn describe(symbol: &str, wallet: Money, bank: Money, has_bank: bool) -> String {
+    if has_bank || !bank.is_zero() {
+        format!("Wallet: {symbol}{wallet}\nBank: {symbol}{bank}")
+    } else {
+        format!("{symbol}{wallet}")
+    }
+}
+
 struct Options {
     user: Option<(User, Box<Member>)>,
     currency: Option<ArcTokioRwLockOption<Currency>>,
@@ -0,0 +1,114 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    db::models::Currency,
+    event_handler::command_handler::CommandOptions,
+    mechanics::bank::{ move_money, BankAction },
+};
+
+/// Runs the `/deposit` or `/withdraw` command, depending on the action.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - An option is missing or invalid.
+/// - The currency does not exist.
+/// - Any error from moving the currency.
+pub async fn run(
+    action: BankAction,
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let curr_name = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No currency was found"))?;
+    let amount = options
+        .get_int_or_number_value(AMOUNT_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No amount was found"))?
+        .cast_to_f64();
+    let member = command.member
+        .as_ref()
+        .ok_or_else(|| anyhow!("Command can't be performed in DMs"))?;
+
+    let currency = Currency::try_from_name(member.guild_id.into(), curr_name).await?.ok_or_else(||
+        anyhow!("Currency not found")
+    )?;
+    let currency = currency.read().await;
+    let currency_ = currency
+        .as_ref()
+        .ok_or_else(|| anyhow!("Currency is being used in a breaking operation"))?;
+    let moved = move_money(currency_, action, amount, member).await?;
+    let (symbol, curr_name, capacity) = (
+        currency_.symbol().to_owned(),
+        currency_.curr_name().as_str().to_owned(),
+        currency_.bank_capacity(),
+    );
+    drop(currency);
+
+    let done = match action {
+        BankAction::Deposit => "deposited",
+        BankAction::Withdraw => "withdrew",
+    };
+    let capacity = capacity.map(|c| format!(" out of {symbol}{c}")).unwrap_or_default();
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!(
+                "You {done} {symbol}{} {curr_name}. You now have {symbol}{} in your wallet and \
+                {symbol}{}{capacity} in the bank.",
+                moved.amount,
+                moved.wallet,
+                moved.bank
+            )
+        )
+    ).await?;
+
+    Ok(())
+}
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+const AMOUNT_OPTION_NAME: &str = "amount";
+
+fn bank_options(command: CreateCommand, action: BankAction) -> CreateCommand {
+    command
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                format!("The currency to {}.", action.as_str())
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                AMOUNT_OPTION_NAME,
+                format!("How much to {}.", action.as_str())
+            ).required(true)
+        )
+}
+
+pub fn deposit_command() -> CreateCommand {
+    bank_options(
+        CreateCommand::new("deposit").description(
+            "Put some of your currency in the bank, where it can't be spent but earns any interest."
+        ),
+        BankAction::Deposit
+    )
+}
+
+pub fn withdraw_command() -> CreateCommand {
+    bank_options(
+        CreateCommand::new("withdraw").description(
+            "Take some of your currency out of the bank so you can spend it."
+        ),
+        BankAction::Withdraw
+    )
+}
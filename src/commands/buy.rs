@@ -8,11 +8,13 @@ use serenity::{
 };
 
 use crate::{
-    db::{ models::{ store::Store, Balances, Inventory, Item }, CLIENT },
+    db::{
+        models::{ store::Store, Balances, Inventory, Item, TransactionKind, TransactionReason },
+        CLIENT,
+    },
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
 };
 
-#[allow(clippy::cast_precision_loss)]
 pub async fn run(
     options: CommandOptions,
     command: &CommandInteraction,
@@ -43,7 +45,10 @@ pub async fn run(
 
     let item = Item::try_from_name(guild_id.into(), entry.item_name().to_owned()).await?;
 
-    let to_take = entry.value() * (amount as f64);
+    let to_take = entry
+        .value()
+        .checked_mul(amount)
+        .ok_or_else(|| anyhow!("That is too many to buy at once."))?;
 
     let to_give = entry.amount() * amount;
 
@@ -69,9 +74,10 @@ pub async fn run(
         anyhow::bail!("Not enough currency to buy the item.");
     }
 
+    let reason = TransactionReason::new(TransactionKind::Buy, user_id.into());
     let res = {
-        balance.sub_amount(to_take, Some(&mut session)).await?;
-        inventory_.give_item(item, to_give, Some(&mut session), 0, http).await?;
+        balance.sub_amount(to_take, reason, Some(&mut session)).await?;
+        inventory_.give_item(item, to_give, reason, Some(&mut session), 0, http).await?;
         anyhow::Ok(())
     };
 
@@ -0,0 +1,68 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::{ CreateCommand, CreateEmbed, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    db::models::claim::ClaimKind,
+    mechanics::claim::{ claim, ClaimOutcome, Reward },
+    ACCENT_COLOUR,
+};
+
+/// Runs the `/daily` or `/weekly` command, depending on the kind.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - There is no reward set up for the kind of claim.
+/// - Any error from claiming the reward.
+pub async fn run(kind: ClaimKind, command: &CommandInteraction, http: &Context) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+
+    let embed = match claim(guild_id, command.user.id, kind, http).await? {
+        ClaimOutcome::OnCooldown(available) =>
+            CreateEmbed::default()
+                .title("Not yet")
+                .description(
+                    format!(
+                        "You can claim your {} reward again <t:{}:R>.",
+                        kind.as_str(),
+                        available.timestamp()
+                    )
+                )
+                .colour(ACCENT_COLOUR),
+        ClaimOutcome::Claimed { streak, multiplier, rewards } => {
+            let mut description = String::new();
+            for reward in rewards {
+                match reward {
+                    Reward::Currency(curr_name, amount) => {
+                        description.push_str(&format!("**{amount}** {curr_name}\n"));
+                    }
+                    Reward::Item(item_name, amount) => {
+                        description.push_str(&format!("**{amount}x** {item_name}\n"));
+                    }
+                }
+            }
+            if description.is_empty() {
+                description.push_str("Nothing this time.\n");
+            }
+            description.push_str(&format!("\nStreak: **{streak}** (x{multiplier:.2})"));
+            CreateEmbed::default()
+                .title(format!("Claimed your {} reward", kind.as_str()))
+                .description(description)
+                .colour(ACCENT_COLOUR)
+        }
+    };
+
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn daily_command() -> CreateCommand {
+    CreateCommand::new("daily").description("Claim your daily reward.").dm_permission(false)
+}
+
+pub fn weekly_command() -> CreateCommand {
+    CreateCommand::new("weekly").description("Claim your weekly reward.").dm_permission(false)
+}
@@ -0,0 +1,58 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{ db::models::activity::Activity, event_handler::command_handler::CommandOptions };
+
+use super::NAME_OPTION_NAME;
+
+/// Runs the delete activity subcommand, which also removes its command from the guild.
+///
+/// # Errors
+///
+/// Returns an error if there is no activity with the name, or if there is an issue deleting it or
+/// its command.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let name = options
+        .get_string_value(NAME_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No name was provided."))??
+        .trim()
+        .to_lowercase();
+    let activity = Activity::try_from_name(guild_id.into(), &name).await?.ok_or_else(||
+        anyhow!("There is no activity called {name}.")
+    )?;
+
+    let guild_commands = guild_id.get_commands(&http.http).await?;
+    if let Some(guild_command) = guild_commands.iter().find(|c| c.name == name) {
+        guild_id.delete_command(&http.http, guild_command.id).await?;
+    }
+    activity.delete().await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(format!("/{name} has been removed."))
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "delete",
+        "Remove an activity along with its command."
+    ).add_sub_option(
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            NAME_OPTION_NAME,
+            "The name of the activity."
+        ).required(true)
+    )
+}
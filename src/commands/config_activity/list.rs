@@ -0,0 +1,66 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::activity::Activity,
+    event_handler::command_handler::CommandOptions,
+    ACCENT_COLOUR,
+};
+
+/// Runs the list activities subcommand.
+///
+/// # Errors
+///
+/// Returns an error if there are no activities or if any `MongoDB` error occurs.
+pub async fn run(
+    _: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let activities = Activity::from_guild(guild_id.into()).await?;
+    if activities.is_empty() {
+        bail!("No activities have been set up.");
+    }
+
+    let mut embed = CreateEmbed::default().title("Activities").colour(ACCENT_COLOUR);
+    for activity in &activities {
+        let mut details = format!(
+            "{}x {}, every {} seconds",
+            activity.count(),
+            activity.drop_table_name(),
+            activity.cooldown().num_seconds()
+        );
+        if let Some(role) = activity.required_role() {
+            details.push_str(&format!("\nNeeds <@&{}>", role.as_u64()));
+        }
+        if activity.fail_chance() > 0.0 {
+            details.push_str(&format!("\n{}% chance to fail", activity.fail_chance() * 100.0));
+            if let Some(penalty) = activity.penalty() {
+                details.push_str(
+                    &format!(
+                        ", losing {} to {} {}",
+                        penalty.min,
+                        penalty.max,
+                        penalty.curr_name
+                    )
+                );
+            }
+        }
+        embed = embed.field(format!("/{}", activity.name()), details, false);
+    }
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "list",
+        "List the activities of this server."
+    )
+}
@@ -0,0 +1,53 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::CreateCommand,
+    client::Context,
+    model::Permissions,
+};
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod delete;
+pub mod list;
+pub mod set;
+
+const NAME_OPTION_NAME: &str = "name";
+const DESCRIPTION_OPTION_NAME: &str = "description";
+const DROP_TABLE_OPTION_NAME: &str = "drop_table";
+const COUNT_OPTION_NAME: &str = "count";
+const COOLDOWN_OPTION_NAME: &str = "cooldown";
+const ROLE_OPTION_NAME: &str = "required_role";
+const FAIL_CHANCE_OPTION_NAME: &str = "fail_chance";
+const PENALTY_CURRENCY_OPTION_NAME: &str = "penalty_currency";
+const PENALTY_MIN_OPTION_NAME: &str = "penalty_min";
+const PENALTY_MAX_OPTION_NAME: &str = "penalty_max";
+const MESSAGE_OPTION_NAME: &str = "message";
+const FAIL_MESSAGE_OPTION_NAME: &str = "fail_message";
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+    match cmd_name.as_str() {
+        "list" => list::run(cmd_options, command, http).await?,
+        "set" => set::run(cmd_options, command, http).await?,
+        "delete" => delete::run(cmd_options, command, http).await?,
+        &_ => bail!("Unknown activity config subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("config_activity")
+        .description("Make commands like /work or /crime that roll a drop table.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(list::option())
+        .add_option(set::option())
+        .add_option(delete::option())
+}
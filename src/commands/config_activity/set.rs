@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::Duration;
+use serenity::{
+    all::{ Command, CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    commands,
+    db::models::{ activity::{ Activity, ActivityPenalty }, Currency, DropTable },
+    event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    util::money::Money,
+};
+
+use super::{
+    COOLDOWN_OPTION_NAME,
+    COUNT_OPTION_NAME,
+    DESCRIPTION_OPTION_NAME,
+    DROP_TABLE_OPTION_NAME,
+    FAIL_CHANCE_OPTION_NAME,
+    FAIL_MESSAGE_OPTION_NAME,
+    MESSAGE_OPTION_NAME,
+    NAME_OPTION_NAME,
+    PENALTY_CURRENCY_OPTION_NAME,
+    PENALTY_MAX_OPTION_NAME,
+    PENALTY_MIN_OPTION_NAME,
+    ROLE_OPTION_NAME,
+};
+
+/// How long a member has to wait between doing an activity if no cooldown is given.
+const DEFAULT_COOLDOWN: Duration = Duration::hours(1);
+
+/// Runs the set activity subcommand, which makes an activity or replaces the one with the same
+/// name, and registers its command in the guild.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Any of the options could not be resolved
+/// - The name is taken by one of the bot's own commands
+/// - The drop table or the penalty currency does not exist
+/// - The settings are invalid
+/// - The command could not be registered
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let name = options
+        .get_string_value(NAME_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No name was provided."))??
+        .trim()
+        .to_lowercase();
+    let drop_table_name = options
+        .get_string_value(DROP_TABLE_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No drop table was provided."))??;
+    let description = options
+        .get_string_value(DESCRIPTION_OPTION_NAME)
+        .transpose()?
+        .unwrap_or_else(|| format!("Do {name} for a roll on {drop_table_name}."));
+    let count = options
+        .get_int_or_number_value(COUNT_OPTION_NAME)
+        .transpose()?
+        .map_or(1, IntOrNumber::cast_to_i64);
+    let cooldown = options
+        .get_int_or_number_value(COOLDOWN_OPTION_NAME)
+        .transpose()?
+        .map_or(DEFAULT_COOLDOWN, |n| Duration::seconds(n.cast_to_i64()));
+    let required_role = options.get_role_value(ROLE_OPTION_NAME).transpose()?;
+    let fail_chance = options
+        .get_int_or_number_value(FAIL_CHANCE_OPTION_NAME)
+        .transpose()?
+        .map_or(0.0, IntOrNumber::cast_to_f64);
+    let penalty_currency = options.get_string_value(PENALTY_CURRENCY_OPTION_NAME).transpose()?;
+    let penalty_min = options
+        .get_int_or_number_value(PENALTY_MIN_OPTION_NAME)
+        .transpose()?
+        .map(IntOrNumber::cast_to_f64);
+    let penalty_max = options
+        .get_int_or_number_value(PENALTY_MAX_OPTION_NAME)
+        .transpose()?
+        .map(IntOrNumber::cast_to_f64);
+    let message = options.get_string_value(MESSAGE_OPTION_NAME).transpose()?;
+    let fail_message = options.get_string_value(FAIL_MESSAGE_OPTION_NAME).transpose()?;
+
+    // The bot's own command would always be the one that runs.
+    let global_commands = Command::get_global_commands(&http.http).await?;
+    if global_commands.iter().any(|c| c.name == name) {
+        bail!("/{name} is already one of the bot's commands.");
+    }
+    let drop_table = DropTable::try_from_name(
+        guild_id.into(),
+        Cow::from(drop_table_name.as_str()),
+        None
+    ).await?;
+    if drop_table.read().await.is_none() {
+        bail!("Drop table is being used in a breaking operation.");
+    }
+    let penalty = match penalty_currency {
+        Some(curr_name) => {
+            let precision = Currency::precision_from_name(
+                guild_id.into(),
+                curr_name.clone()
+            ).await?;
+            let min = penalty_min.ok_or_else(|| anyhow!("A penalty needs a minimum amount."))?;
+            let min = Money::from_f64(min, precision)?;
+            let max = penalty_max
+                .map(|max| Money::from_f64(max, precision))
+                .transpose()?
+                .unwrap_or(min);
+            Some(ActivityPenalty { curr_name, min, max })
+        }
+        None if penalty_min.is_some() || penalty_max.is_some() => {
+            bail!("A penalty needs a currency.");
+        }
+        None => None,
+    };
+
+    let activity = Activity::set(
+        guild_id.into(),
+        name,
+        description,
+        drop_table_name,
+        count,
+        cooldown,
+        required_role.map(Into::into),
+        fail_chance / 100.0,
+        penalty,
+        message,
+        fail_message
+    ).await?;
+    guild_id.create_command(&http.http, commands::activity::command(&activity)).await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!("/{} has been set up. It may take a moment to show up.", activity.name())
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "set",
+        "Make an activity command, or replace the one with the same name."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                NAME_OPTION_NAME,
+                "The name of the command, like work or crime."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                DROP_TABLE_OPTION_NAME,
+                "The drop table to roll."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                DESCRIPTION_OPTION_NAME,
+                "The description of the command."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                COUNT_OPTION_NAME,
+                "How many times to roll the drop table. 1 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                COOLDOWN_OPTION_NAME,
+                "Seconds a member has to wait between doing it. One hour by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Role,
+                ROLE_OPTION_NAME,
+                "The role a member needs to do it."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                FAIL_CHANCE_OPTION_NAME,
+                "Percent chance it fails instead of rolling the drop table. 0 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                PENALTY_CURRENCY_OPTION_NAME,
+                "The currency a member loses some of when it fails."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                PENALTY_MIN_OPTION_NAME,
+                "The least lost when it fails."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                PENALTY_MAX_OPTION_NAME,
+                "The most lost when it fails, the minimum by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                MESSAGE_OPTION_NAME,
+                "Shown above what the member got."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                FAIL_MESSAGE_OPTION_NAME,
+                "Shown when it fails."
+            ).required(false)
+        )
+}
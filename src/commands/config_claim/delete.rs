@@ -0,0 +1,51 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::claim::{ ClaimConfig, ClaimKind },
+    event_handler::command_handler::CommandOptions,
+};
+
+use super::{ kind_option, KIND_OPTION_NAME };
+
+/// Runs the delete claim reward subcommand.
+///
+/// # Errors
+///
+/// Returns an error if there is no reward of that kind or if there is an issue deleting it.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let kind: ClaimKind = options
+        .get_string_value(KIND_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No kind was provided."))??
+        .parse()?;
+
+    let config = ClaimConfig::try_from_kind(guild_id.into(), kind).await?.ok_or_else(||
+        anyhow!("There is no {} reward.", kind.as_str())
+    )?;
+    config.delete().await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!("The {} reward has been removed.", kind.as_str())
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "delete",
+        "Remove a reward so that it can not be claimed anymore."
+    ).add_sub_option(kind_option())
+}
@@ -0,0 +1,64 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::claim::{ ClaimConfig, ClaimPayout },
+    event_handler::command_handler::CommandOptions,
+    ACCENT_COLOUR,
+};
+
+/// Runs the list claim rewards subcommand.
+///
+/// # Errors
+///
+/// Returns an error if there are no rewards or if any `MongoDB` error occurs.
+pub async fn run(
+    _: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+
+    let configs = ClaimConfig::from_guild(guild_id.into()).await?;
+    if configs.is_empty() {
+        bail!("No daily or weekly rewards have been set up.");
+    }
+
+    let mut embed = CreateEmbed::default().title("Claim rewards").colour(ACCENT_COLOUR);
+    for config in &configs {
+        let payout = match config.payout() {
+            ClaimPayout::Currency { curr_name, min, max } => {
+                format!("Between {min} and {max} {curr_name}")
+            }
+            ClaimPayout::Item { item_name, amount } => format!("{amount}x {item_name}"),
+            ClaimPayout::DropTable { drop_table_name, count } => {
+                format!("{count} rolls on {drop_table_name}")
+            }
+        };
+        embed = embed.field(
+            config.kind().as_str(),
+            format!(
+                "{payout}\n+{}% per claim in a row, up to {} in a row\n{} seconds of grace",
+                config.streak_bonus() * 100.0,
+                config.max_streak(),
+                config.grace().num_seconds()
+            ),
+            false
+        );
+    }
+
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "list",
+        "List the daily and weekly rewards of this server."
+    )
+}
@@ -0,0 +1,63 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption },
+    http::CacheHttp,
+    model::Permissions,
+};
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod delete;
+pub mod list;
+pub mod set;
+
+const KIND_OPTION_NAME: &str = "kind";
+const CURRENCY_OPTION_NAME: &str = "currency";
+const MIN_OPTION_NAME: &str = "min";
+const MAX_OPTION_NAME: &str = "max";
+const ITEM_OPTION_NAME: &str = "item";
+const DROP_TABLE_OPTION_NAME: &str = "drop_table";
+const AMOUNT_OPTION_NAME: &str = "amount";
+const STREAK_BONUS_OPTION_NAME: &str = "streak_bonus";
+const MAX_STREAK_OPTION_NAME: &str = "max_streak";
+const GRACE_OPTION_NAME: &str = "grace";
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp + Clone
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+    match cmd_name.as_str() {
+        "list" => list::run(cmd_options, command, http).await?,
+        "set" => set::run(cmd_options, command, http).await?,
+        "delete" => delete::run(cmd_options, command, http).await?,
+        &_ => bail!("Unknown claim config subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("config_claim")
+        .description("Configure the rewards of the daily and weekly commands.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(list::option())
+        .add_option(set::option())
+        .add_option(delete::option())
+}
+
+/// The option to pick between daily and weekly, shared by the subcommands.
+fn kind_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        KIND_OPTION_NAME,
+        "Which reward to configure."
+    )
+        .required(true)
+        .add_string_choice("Daily", "daily")
+        .add_string_choice("Weekly", "weekly")
+}
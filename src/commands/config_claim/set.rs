@@ -0,0 +1,192 @@
+use anyhow::{ anyhow, bail, Result };
+use chrono::Duration;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::{ claim::{ ClaimConfig, ClaimKind, ClaimPayout }, Currency, DropTable, Item },
+    event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    util::money::Money,
+};
+
+use super::{
+    kind_option,
+    AMOUNT_OPTION_NAME,
+    CURRENCY_OPTION_NAME,
+    DROP_TABLE_OPTION_NAME,
+    GRACE_OPTION_NAME,
+    ITEM_OPTION_NAME,
+    KIND_OPTION_NAME,
+    MAX_OPTION_NAME,
+    MAX_STREAK_OPTION_NAME,
+    MIN_OPTION_NAME,
+    STREAK_BONUS_OPTION_NAME,
+};
+
+/// The streak after which the multiplier stops going up if none is given.
+const DEFAULT_MAX_STREAK: i64 = 7;
+
+/// Runs the set claim reward subcommand.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Any of the options could not be resolved
+/// - Not exactly one of a currency, an item or a drop table was given
+/// - The currency, item or drop table does not exist
+/// - The amounts are invalid
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let kind: ClaimKind = options
+        .get_string_value(KIND_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No kind was provided."))??
+        .parse()?;
+    let currency = options.get_string_value(CURRENCY_OPTION_NAME).transpose()?;
+    let item = options.get_string_value(ITEM_OPTION_NAME).transpose()?;
+    let drop_table = options.get_string_value(DROP_TABLE_OPTION_NAME).transpose()?;
+    let min = options
+        .get_int_or_number_value(MIN_OPTION_NAME)
+        .transpose()?
+        .map(IntOrNumber::cast_to_f64);
+    let max = options
+        .get_int_or_number_value(MAX_OPTION_NAME)
+        .transpose()?
+        .map(IntOrNumber::cast_to_f64);
+    let amount = options
+        .get_int_or_number_value(AMOUNT_OPTION_NAME)
+        .transpose()?
+        .map_or(1, IntOrNumber::cast_to_i64);
+    let streak_bonus = options
+        .get_int_or_number_value(STREAK_BONUS_OPTION_NAME)
+        .transpose()?
+        .map_or(0.0, IntOrNumber::cast_to_f64);
+    let max_streak = options
+        .get_int_or_number_value(MAX_STREAK_OPTION_NAME)
+        .transpose()?
+        .map_or(DEFAULT_MAX_STREAK, IntOrNumber::cast_to_i64);
+    let grace = options
+        .get_int_or_number_value(GRACE_OPTION_NAME)
+        .transpose()?
+        .map_or_else(|| kind.cooldown(), |n| Duration::seconds(n.cast_to_i64()));
+
+    let payout = match (currency, item, drop_table) {
+        (Some(curr_name), None, None) => {
+            let precision = Currency::precision_from_name(
+                guild_id.into(),
+                curr_name.clone()
+            ).await?;
+            let min = min.ok_or_else(|| anyhow!("A currency reward needs a minimum amount."))?;
+            let min = Money::from_f64(min, precision)?;
+            let max = max.map(|max| Money::from_f64(max, precision)).transpose()?.unwrap_or(min);
+            ClaimPayout::Currency { curr_name, min, max }
+        }
+        (None, Some(item_name), None) => {
+            // Make sure it exists.
+            Item::try_from_name(guild_id.into(), item_name.clone()).await?;
+            ClaimPayout::Item { item_name, amount }
+        }
+        (None, None, Some(drop_table_name)) => {
+            // Make sure it exists.
+            DropTable::try_from_name(guild_id.into(), drop_table_name.as_str().into(), None).await?;
+            ClaimPayout::DropTable { drop_table_name, count: amount }
+        }
+        _ => bail!("Give exactly one of a currency, an item or a drop table."),
+    };
+
+    ClaimConfig::set(
+        guild_id.into(),
+        kind,
+        payout,
+        streak_bonus / 100.0,
+        max_streak,
+        grace
+    ).await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!("The {} reward has been set.", kind.as_str())
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "set",
+        "Set what the daily or weekly command gives."
+    )
+        .add_sub_option(kind_option())
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "Give a random amount of this currency."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MIN_OPTION_NAME,
+                "The least amount of the currency to give."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MAX_OPTION_NAME,
+                "The most amount of the currency to give, the minimum by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                ITEM_OPTION_NAME,
+                "Give this item."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                DROP_TABLE_OPTION_NAME,
+                "Give a roll on this drop table."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                AMOUNT_OPTION_NAME,
+                "How many of the item, or rolls on the drop table, to give. 1 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                STREAK_BONUS_OPTION_NAME,
+                "Percent more given for every claim in a row after the first. 0 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                MAX_STREAK_OPTION_NAME,
+                "The streak after which the bonus stops going up. 7 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                GRACE_OPTION_NAME,
+                "Seconds past the cooldown a claim keeps the streak. One cooldown by default."
+            ).required(false)
+        )
+}
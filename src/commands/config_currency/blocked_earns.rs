@@ -0,0 +1,62 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::{ blocked_earns::{ BlockedEarns, FarmingRule }, Currency },
+    event_handler::command_handler::CommandOptions,
+};
+
+/// Shows how many times each anti-farming rule of a currency stopped a message from earning it.
+///
+/// # Errors
+///
+/// This function can return an error if the currency is not found or any `MongoDB` error occurs.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let currency_name = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .ok_or_else(|| anyhow!("Currency name not found."))??;
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command can't be performed in DMs."))?;
+
+    if Currency::try_from_name(guild_id.into(), currency_name.clone()).await?.is_none() {
+        return Err(anyhow!("Currency not found."));
+    }
+    let counts = BlockedEarns::from_currency(guild_id.into(), &currency_name).await?;
+
+    let embed = CreateEmbed::new()
+        .title(format!("Blocked earns of {currency_name}"))
+        .fields(
+            FarmingRule::ALL.iter().map(|rule| {
+                let count = counts
+                    .iter()
+                    .find(|c| c.rule() == *rule)
+                    .map_or(0, BlockedEarns::count);
+                (rule.as_str(), count.to_string(), true)
+            })
+        );
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "blocked_earns",
+        "See how many times each anti-farming rule stopped a message from earning a currency."
+    ).add_sub_option(
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            CURRENCY_OPTION_NAME,
+            "The currency to see the counts for."
+        ).required(true)
+    )
+}
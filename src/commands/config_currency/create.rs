@@ -7,6 +7,7 @@ use serenity::{ builder::CreateCommandOption, http::Http };
 
 use crate::db::{ models::currency::builder::Builder, uniques::DbGuildId };
 use crate::event_handler::command_handler::{ CommandOptions, IntOrNumber };
+use crate::util::money::{ Money, MONEY_SCALE };
 
 /// Runs the create currency subcommand.
 ///
@@ -45,16 +46,33 @@ pub async fn run(
         options.get_int_or_number_value("base_value").transpose()?.map(IntOrNumber::cast_to_f64)
     );
     currency_builder.pay(options.get_bool_value("pay").transpose()?);
+    currency_builder.gamble(options.get_bool_value("gamble").transpose()?);
     currency_builder.earn_by_chat(options.get_bool_value("earn_by_chat").transpose()?);
     currency_builder.channels_is_whitelist(
         options.get_bool_value("channels_is_whitelist").transpose()?
     );
     currency_builder.roles_is_whitelist(options.get_bool_value("roles_is_whitelist").transpose()?);
     currency_builder.earn_min(
-        options.get_int_or_number_value("earn_min").transpose()?.map(IntOrNumber::cast_to_f64)
+        options
+            .get_int_or_number_value("earn_min")
+            .transpose()?
+            .map(|n| Money::from_f64(n.cast_to_f64(), MONEY_SCALE))
+            .transpose()?
     );
     currency_builder.earn_max(
-        options.get_int_or_number_value("earn_max").transpose()?.map(IntOrNumber::cast_to_f64)
+        options
+            .get_int_or_number_value("earn_max")
+            .transpose()?
+            .map(|n| Money::from_f64(n.cast_to_f64(), MONEY_SCALE))
+            .transpose()?
+    );
+    currency_builder.precision(
+        options
+            .get_int_or_number_value("precision")
+            .transpose()?
+            .map(|n| u8::try_from(n.cast_to_i64()))
+            .transpose()
+            .map_err(|_| anyhow!("Precision must be between 0 and {MONEY_SCALE}"))?
     );
     currency_builder.earn_timeout(
         options
@@ -62,6 +80,41 @@ pub async fn run(
             .transpose()?
             .map(|n| Duration::seconds(n.cast_to_i64()))
     );
+    currency_builder.interest_rate(
+        options.get_int_or_number_value("interest_rate").transpose()?.map(IntOrNumber::cast_to_f64)
+    );
+    currency_builder.interest_interval(
+        options
+            .get_int_or_number_value("interest_interval")
+            .transpose()?
+            .map(|n| Duration::seconds(n.cast_to_i64()))
+    );
+    currency_builder.interest_threshold(
+        options
+            .get_int_or_number_value("interest_threshold")
+            .transpose()?
+            .map(|n| Money::from_f64(n.cast_to_f64(), MONEY_SCALE))
+            .transpose()?
+    );
+    currency_builder.bank(options.get_bool_value("bank").transpose()?);
+    currency_builder.earn_by_voice(options.get_bool_value("earn_by_voice").transpose()?);
+    currency_builder.voice_rate(
+        options
+            .get_int_or_number_value("voice_rate")
+            .transpose()?
+            .map(|n| Money::from_f64(n.cast_to_f64(), MONEY_SCALE))
+            .transpose()?
+    );
+    currency_builder.voice_exclude_muted(
+        options.get_bool_value("voice_exclude_muted").transpose()?
+    );
+    currency_builder.voice_exclude_deafened(
+        options.get_bool_value("voice_exclude_deafened").transpose()?
+    );
+    currency_builder.voice_exclude_alone(
+        options.get_bool_value("voice_exclude_alone").transpose()?
+    );
+    currency_builder.voice_exclude_afk(options.get_bool_value("voice_exclude_afk").transpose()?);
     currency_builder.build().await?;
     command.edit_response(
         http,
@@ -71,6 +124,7 @@ pub async fn run(
 }
 // There might be a more efficient and compact way to do this but I cannot think of it right now.
 
+#[allow(clippy::too_many_lines)]
 pub fn option() -> CreateCommandOption {
     CreateCommandOption::new(CommandOptionType::SubCommand, "create", "Create a new currency")
         .add_sub_option(
@@ -115,6 +169,13 @@ pub fn option() -> CreateCommandOption {
                 "If members can pay each other this"
             ).required(false)
         )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "gamble",
+                "If members can bet this in the gambling games, true by default"
+            ).required(false)
+        )
         .add_sub_option(
             CreateCommandOption::new(
                 CommandOptionType::Boolean,
@@ -157,4 +218,81 @@ pub fn option() -> CreateCommandOption {
                 "Cooldown in seconds between earning currency"
             ).required(false)
         )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "precision",
+                "How many decimal places amounts of this currency have, 2 by default"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                "interest_rate",
+                "Percent of each balance added every interval, negative for a wealth tax"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "interest_interval",
+                "Seconds between applying interest, 1 day by default"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                "interest_threshold",
+                "Balances below this do not earn interest or pay the tax"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "bank",
+                "If members can keep this in a bank, which is the only part that earns interest"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "earn_by_voice",
+                "If members can earn this by being in voice channels"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                "voice_rate",
+                "Amount of currency earned per minute in a voice channel"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "voice_exclude_muted",
+                "If muted members do not earn by voice, true by default"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "voice_exclude_deafened",
+                "If deafened members do not earn by voice, true by default"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "voice_exclude_alone",
+                "If members alone in a voice channel do not earn by voice, true by default"
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "voice_exclude_afk",
+                "If members in the AFK channel do not earn by voice, true by default"
+            ).required(false)
+        )
 }
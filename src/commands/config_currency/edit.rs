@@ -7,8 +7,13 @@ use serenity::{
     http::Http,
 };
 
-use crate::{ db::models::Currency, event_handler::command_handler::CommandOptions };
+use crate::{
+    db::models::Currency,
+    event_handler::command_handler::CommandOptions,
+    util::money::Money,
+};
 
+#[allow(clippy::too_many_lines)]
 pub async fn run(
     options: CommandOptions,
     command: &CommandInteraction,
@@ -58,6 +63,7 @@ pub async fn run(
             currency__.update_base_value(value.parse().ok(), None).await?;
         }
         "pay" => currency__.update_pay(value.parse()?, None).await?,
+        "gamble" => currency__.update_gamble(value.parse()?, None).await?,
         "earn_by_chat" => currency__.update_earn_by_chat(value.parse()?, None).await?,
         "channels_is_whitelist" => {
             currency__.update_channels_is_whitelist(value.parse()?, None).await?;
@@ -65,11 +71,75 @@ pub async fn run(
         "roles_is_whitelist" => {
             currency__.update_roles_is_whitelist(value.parse()?, None).await?;
         }
-        "earn_min" => currency__.update_earn_min(value.parse()?, None).await?,
-        "earn_max" => currency__.update_earn_max(value.parse()?, None).await?,
+        "earn_min" => {
+            let precision = currency__.precision();
+            currency__.update_earn_min(value.parse::<Money>()?.truncate(precision), None).await?;
+        }
+        "earn_max" => {
+            let precision = currency__.precision();
+            currency__.update_earn_max(value.parse::<Money>()?.truncate(precision), None).await?;
+        }
         "earn_timeout" => {
             currency__.update_earn_timeout(Duration::seconds(value.parse::<i64>()?), None).await?;
         }
+        "precision" => currency__.update_precision(value.parse()?, None).await?,
+        "interest_rate" => {
+            // Anything that is not a number turns interest off, like base_value.
+            currency__.update_interest_rate(value.parse().ok(), None).await?;
+        }
+        "interest_interval" => {
+            currency__.update_interest_interval(
+                Duration::seconds(value.parse::<i64>()?),
+                None
+            ).await?;
+        }
+        "interest_threshold" => {
+            let precision = currency__.precision();
+            currency__.update_interest_threshold(
+                value.parse::<Money>()?.truncate(precision),
+                None
+            ).await?;
+        }
+        "bank" => currency__.update_bank(value.parse()?, None).await?,
+        "bank_capacity" => {
+            // "none" removes the limit. Anything else has to be a number, so a typo does not.
+            let capacity = if value.trim().eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(value.parse::<Money>()?.truncate(currency__.precision()))
+            };
+            currency__.update_bank_capacity(capacity, None).await?;
+        }
+        "earn_by_voice" => currency__.update_earn_by_voice(value.parse()?, None).await?,
+        "voice_rate" => currency__.update_voice_rate(value.parse()?, None).await?,
+        "voice_exclude_muted" => currency__.update_voice_exclude_muted(value.parse()?, None).await?,
+        "voice_exclude_deafened" => {
+            currency__.update_voice_exclude_deafened(value.parse()?, None).await?;
+        }
+        "voice_exclude_alone" => currency__.update_voice_exclude_alone(value.parse()?, None).await?,
+        "voice_exclude_afk" => currency__.update_voice_exclude_afk(value.parse()?, None).await?,
+        "multiplier_stacking" => {
+            currency__.update_multiplier_stacking(value.parse()?, None).await?;
+        }
+        "min_message_length" => currency__.update_min_message_length(value.parse()?, None).await?,
+        "ignore_repeated_messages" => {
+            currency__.update_ignore_repeated_messages(value.parse()?, None).await?;
+        }
+        "min_account_age" => {
+            currency__.update_min_account_age(
+                Duration::seconds(value.parse::<i64>()?),
+                None
+            ).await?;
+        }
+        "min_member_age" => {
+            currency__.update_min_member_age(Duration::seconds(value.parse::<i64>()?), None).await?;
+        }
+        "ignore_emoji_and_link_only" => {
+            currency__.update_ignore_emoji_and_link_only(value.parse()?, None).await?;
+        }
+        "earn_multipliers" => {
+            anyhow::bail!("Multipliers are edited with the edit_multipliers subcommand");
+        }
         "channels_whitelist" | "channels_blacklist" | "roles_blacklist" | "roles_whitelist" => {
             anyhow::bail!("List field is not editable with this command");
         }
@@ -1,13 +1,18 @@
 use anyhow::{ anyhow, bail, Result };
 use core::str::FromStr;
 use serenity::{
-    all::{ CommandInteraction, CommandOptionType },
+    all::{ ChannelId, CommandInteraction, CommandOptionType, GuildId, RoleId },
     builder::{ CreateCommandOption, EditInteractionResponse },
+    cache::Cache,
     http::{ CacheHttp, Http },
     model::prelude::Mention,
 };
 
-use crate::{ db::models::Currency, event_handler::command_handler::CommandOptions };
+use crate::{
+    db::models::Currency,
+    event_handler::command_handler::CommandOptions,
+    util::channel,
+};
 
 /// Runs the command with the given options, command interaction, and HTTP client.
 ///
@@ -55,22 +60,60 @@ pub async fn run(
         ).await?;
         return Ok(());
     }
-    let value = Mention::from_str(&value)?;
+    let value = parse_value(&field_name, &value)?;
     match operation.as_str() {
         "add" => add(currency_, &field_name, value).await?,
         "remove" => remove(currency_, &field_name, value).await?,
         _ => bail!("Invalid operation."),
     }
+    let cache = http.cache().map(AsRef::as_ref);
+    let entries = match field_name.as_str() {
+        "channels_whitelist" => currency_.channels_whitelist(),
+        "channels_blacklist" => currency_.channels_blacklist(),
+        _ => &[],
+    }
+        .iter()
+        .map(|id| describe(Mention::Channel((*id).into()), guild_id, cache))
+        .collect::<Vec<_>>();
     drop(currency);
-    command.edit_response(
-        &http,
-        EditInteractionResponse::new().content(
-            format!("{operation}ed {value} from/to {field_name}")
-        )
-    ).await?;
+    let mut content = format!(
+        "{operation}ed {} from/to {field_name}",
+        describe(value, guild_id, cache)
+    );
+    if !entries.is_empty() {
+        content.push_str(&format!("\nNow: {}", entries.join(", ")));
+    }
+    command.edit_response(&http, EditInteractionResponse::new().content(content)).await?;
     Ok(())
 }
 
+/// Parses the value to add or remove, which is either a mention or a bare ID. Bare IDs are how
+/// categories are given, since they can not be mentioned.
+fn parse_value(field_name: &str, value: &str) -> Result<Mention> {
+    let Ok(id) = value.trim().parse::<u64>() else {
+        return Ok(Mention::from_str(value)?);
+    };
+    if id == 0 {
+        bail!("Invalid ID.");
+    }
+    if field_name.starts_with("roles") {
+        Ok(Mention::Role(RoleId::new(id)))
+    } else {
+        Ok(Mention::Channel(ChannelId::new(id)))
+    }
+}
+
+/// Shows a mention along with what kind of channel it is if it is one, since channel lists can
+/// hold categories, channels and threads alike.
+fn describe(value: Mention, guild_id: GuildId, cache: Option<&Cache>) -> String {
+    match (value, cache) {
+        (Mention::Channel(c), Some(cache)) => {
+            format!("{value} ({})", channel::kind_name(cache, guild_id, c))
+        }
+        _ => value.to_string(),
+    }
+}
+
 async fn add(currency: &mut Currency, field_name: &str, value: Mention) -> Result<()> {
     match field_name {
         "roles_whitelist" => {
@@ -196,7 +239,7 @@ pub fn option() -> CreateCommandOption {
             CreateCommandOption::new(
                 CommandOptionType::String,
                 VALUE_OPTION_NAME,
-                "The value to add/remove to the list. Type whatever if clearing."
+                "The mention or ID to add/remove, IDs for categories. Type whatever if clearing."
             ).required(true)
         )
 }
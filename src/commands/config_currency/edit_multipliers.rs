@@ -0,0 +1,131 @@
+use anyhow::{ anyhow, bail, Result };
+use core::str::FromStr;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+    model::prelude::Mention,
+};
+
+use crate::{
+    db::models::{ currency::multiplier::EarnMultiplier, Currency },
+    event_handler::command_handler::{ CommandOptions, IntOrNumber },
+};
+
+/// Runs the subcommand that sets, removes or clears the earn multipliers of a currency.
+///
+/// # Errors
+///
+/// This function can return an error if there is a problem executing the command.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let currency_name = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .ok_or_else(|| anyhow!("Currency name not found."))??;
+    let operation = options
+        .get_string_value(OPERATION_OPTION_NAME)
+        .ok_or_else(|| anyhow!("Operation not found."))??
+        .trim()
+        .to_lowercase();
+    let target = options.get_string_value(TARGET_OPTION_NAME).transpose()?;
+    let multiplier = options
+        .get_int_or_number_value(MULTIPLIER_OPTION_NAME)
+        .transpose()?
+        .map(IntOrNumber::cast_to_f64);
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command can't be performed in DMs."))?;
+
+    let currency = Currency::try_from_name(guild_id.into(), currency_name).await?.ok_or_else(||
+        anyhow!("Currency not found.")
+    )?;
+
+    let mut currency = currency.write().await;
+
+    let currency_ = currency
+        .as_mut()
+        .ok_or_else(|| anyhow!("Currency is being used in breaking operation."))?;
+    if operation.as_str() == "clear" {
+        currency_.overwrite_earn_multipliers(vec![], None).await?;
+        drop(currency);
+        command.edit_response(
+            &http,
+            EditInteractionResponse::new().content("Cleared earn multipliers")
+        ).await?;
+        return Ok(());
+    }
+    let target = target.ok_or_else(|| anyhow!("A role or channel must be given."))?;
+    let mention = Mention::from_str(&target)?;
+    let earn_multiplier = to_earn_multiplier(mention, multiplier.unwrap_or(1.0))?;
+    let content = match operation.as_str() {
+        "set" => {
+            let multiplier = multiplier.ok_or_else(|| anyhow!("A multiplier must be given."))?;
+            currency_.set_earn_multiplier(earn_multiplier, None).await?;
+            format!("Set the earn multiplier of {mention} to {multiplier}")
+        }
+        "remove" => {
+            currency_.remove_earn_multiplier(earn_multiplier, None).await?;
+            format!("Removed the earn multiplier of {mention}")
+        }
+        _ => bail!("Invalid operation."),
+    };
+    drop(currency);
+    command.edit_response(&http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
+
+fn to_earn_multiplier(mention: Mention, multiplier: f64) -> Result<EarnMultiplier> {
+    match mention {
+        Mention::Role(r) => Ok(EarnMultiplier::Role { role_id: r.into(), multiplier }),
+        Mention::Channel(c) => Ok(EarnMultiplier::Channel { channel_id: c.into(), multiplier }),
+        Mention::User(_) => bail!("Multipliers can only be given to roles and channels."),
+    }
+}
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+const OPERATION_OPTION_NAME: &str = "operation";
+const TARGET_OPTION_NAME: &str = "target";
+const MULTIPLIER_OPTION_NAME: &str = "multiplier";
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "edit_multipliers",
+        "Edit the multipliers on what members with a role or in a channel earn by chatting."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency to edit."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                OPERATION_OPTION_NAME,
+                "The operation to perform on the multipliers."
+            )
+                .required(true)
+                .add_string_choice("set", "set")
+                .add_string_choice("remove", "remove")
+                .add_string_choice("clear", "clear")
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                TARGET_OPTION_NAME,
+                "The role or channel mention to set or remove the multiplier of."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MULTIPLIER_OPTION_NAME,
+                "The multiplier to set, like 1.5 for 50% more."
+            )
+                .required(false)
+                .min_number_value(0.0)
+        )
+}
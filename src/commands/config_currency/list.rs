@@ -16,8 +16,14 @@ impl<'a> CurrencyConfigPrettifier<'a> {
         Self { options }
     }
 
-    pub fn pretty(self) -> Result<CreateEmbed> {
-        fn embed_field_default(k: &str, v: &str, mut embed: CreateEmbed) -> CreateEmbed {
+    /// Makes the embeds that show the config. There are more fields than fit in one embed, so
+    /// they are spread over as many as needed, and only the first one has the title.
+    pub fn pretty(self) -> Result<Vec<CreateEmbed>> {
+        fn embed_field_default(
+            k: &str,
+            v: &str,
+            mut embed: Vec<(String, String)>
+        ) -> Vec<(String, String)> {
             if k.is_empty() {
                 return embed;
             }
@@ -30,10 +36,10 @@ impl<'a> CurrencyConfigPrettifier<'a> {
                 }
                 k_.push(c);
             }
-            embed = embed.field(k_, v, true);
+            embed.push((k_, v.to_owned()));
             embed
         }
-        let mut embed = CreateEmbed::default();
+        let mut embed = Vec::new();
         let kvs = self.options.try_to_kvs()?.into_iter();
         let mut channels_is_whitelist: bool = false;
         let mut roles_is_whitelist: bool = false;
@@ -74,32 +80,44 @@ impl<'a> CurrencyConfigPrettifier<'a> {
                 "GuildId" => (),
                 "channelsWhitelist" => {
                     if channels_is_whitelist {
-                        embed = embed.field("Channels Whitelist", v, true);
+                        embed.push(("Channels Whitelist".to_owned(), v));
                     }
                 }
                 "ChannelsBlacklist" => {
                     if !channels_is_whitelist {
-                        embed = embed.field("Channels Blacklist", v, true);
+                        embed.push(("Channels Blacklist".to_owned(), v));
                     }
                 }
                 "RolesWhitelist" => {
                     if roles_is_whitelist {
-                        embed = embed.field("Roles Whitelist", v, true);
+                        embed.push(("Roles Whitelist".to_owned(), v));
                     }
                 }
                 "RolesBlacklist" => {
                     if !roles_is_whitelist {
-                        embed = embed.field("Roles Blacklist", v, true);
+                        embed.push(("Roles Blacklist".to_owned(), v));
                     }
                 }
                 &_ => {}
             }
         }
-        embed = embed.title(embed_title);
-        Ok(embed)
+        let embeds = embed
+            .chunks(MAX_EMBED_FIELDS)
+            .enumerate()
+            .map(|(i, fields)| {
+                let embed = CreateEmbed::default().fields(
+                    fields.iter().map(|(k, v)| (k, v, true))
+                );
+                if i == 0 { embed.title(embed_title.clone()) } else { embed }
+            })
+            .collect();
+        Ok(embeds)
     }
 }
 
+/// The most fields Discord allows in one embed.
+const MAX_EMBED_FIELDS: usize = 25;
+
 const COMMAND_OPTION_CURRENCY: &str = "currency";
 
 pub async fn run(
@@ -122,11 +140,11 @@ pub async fn run(
         .as_ref()
         .ok_or_else(|| anyhow!("Currency is being used in breaking operation."))?;
 
-    let embed = CurrencyConfigPrettifier::new(currency_).pretty()?;
+    let embeds = CurrencyConfigPrettifier::new(currency_).pretty()?;
 
     drop(currency);
 
-    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    command.edit_response(http, EditInteractionResponse::new().embeds(embeds)).await?;
 
     Ok(())
 }
@@ -3,10 +3,12 @@ use serenity::{ all::CommandInteraction, builder::CreateCommand, http::{ CacheHt
 
 use crate::event_handler::command_handler::CommandOptions;
 
+pub mod blocked_earns;
 pub mod create;
 pub mod delete;
 pub mod edit;
 pub mod edit_list;
+pub mod edit_multipliers;
 pub mod list;
 
 pub async fn run(
@@ -21,8 +23,10 @@ pub async fn run(
         "list" => list::run(cmd_options, command, http).await?,
         "edit" => edit::run(cmd_options, command, http).await?,
         "edit_list" => edit_list::run(cmd_options, command, http).await?,
+        "edit_multipliers" => edit_multipliers::run(cmd_options, command, http).await?,
         "create" => create::run(cmd_options, command, http).await?,
         "delete" => delete::run(cmd_options, command, http).await?,
+        "blocked_earns" => blocked_earns::run(cmd_options, command, http).await?,
         &_ => bail!("Unknown currency config subcommand."),
     }
     Ok(())
@@ -35,6 +39,8 @@ pub fn command() -> CreateCommand {
         .add_option(list::option())
         .add_option(edit::option())
         .add_option(edit_list::option())
+        .add_option(edit_multipliers::option())
         .add_option(create::option())
         .add_option(delete::option())
+        .add_option(blocked_earns::option())
 }
@@ -0,0 +1,52 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::gambling::GameConfig,
+    event_handler::command_handler::CommandOptions,
+    ACCENT_COLOUR,
+};
+
+/// Runs the list gambling config subcommand.
+///
+/// # Errors
+///
+/// Returns an error if any `MongoDB` error occurs.
+pub async fn run(
+    _: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let configs = GameConfig::from_guild(guild_id.into()).await?;
+
+    let mut embed = CreateEmbed::default().title("Gambling games").colour(ACCENT_COLOUR);
+    for config in &configs {
+        embed = embed.field(
+            config.game().as_str(),
+            format!(
+                "{}\nBets from {} to {}\n{}% house edge\n{} seconds of cooldown",
+                if config.enabled() { "On" } else { "Off" },
+                config.min_bet(),
+                config.max_bet().map_or_else(|| "any amount".to_owned(), |max| max.to_string()),
+                config.house_edge() * 100.0,
+                config.cooldown().num_seconds()
+            ),
+            false
+        );
+    }
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "list",
+        "List how the gambling games of this server are set up."
+    )
+}
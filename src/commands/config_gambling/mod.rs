@@ -0,0 +1,61 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption },
+    http::CacheHttp,
+    model::Permissions,
+};
+
+use crate::{ db::models::gambling::Game, event_handler::command_handler::CommandOptions };
+
+pub mod list;
+pub mod records;
+pub mod set;
+
+const GAME_OPTION_NAME: &str = "game";
+const ENABLED_OPTION_NAME: &str = "enabled";
+const MIN_BET_OPTION_NAME: &str = "min_bet";
+const MAX_BET_OPTION_NAME: &str = "max_bet";
+const NO_MAX_BET_OPTION_NAME: &str = "no_max_bet";
+const HOUSE_EDGE_OPTION_NAME: &str = "house_edge";
+const COOLDOWN_OPTION_NAME: &str = "cooldown";
+const MEMBER_OPTION_NAME: &str = "member";
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp + Clone
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+    match cmd_name.as_str() {
+        "list" => list::run(cmd_options, command, http).await?,
+        "set" => set::run(cmd_options, command, http).await?,
+        "records" => records::run(cmd_options, command, http).await?,
+        &_ => bail!("Unknown gambling config subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("config_gambling")
+        .description("Configure the coinflip, dice and slots games, and look at how they went.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(list::option())
+        .add_option(set::option())
+        .add_option(records::option())
+}
+
+/// The option to pick a game, shared by the subcommands.
+fn game_option() -> CreateCommandOption {
+    Game::ALL.iter().fold(
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            GAME_OPTION_NAME,
+            "Which game."
+        ).required(true),
+        |option, game| option.add_string_choice(game.as_str(), game.as_str())
+    )
+}
@@ -0,0 +1,77 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::gambling::GameRecord,
+    event_handler::command_handler::CommandOptions,
+    ACCENT_COLOUR,
+};
+
+use super::MEMBER_OPTION_NAME;
+
+/// How many of the most recent games are shown.
+const RECORDS_SHOWN: i64 = 20;
+
+/// Runs the records gambling config subcommand, which shows the most recent games so that staff can
+/// check how they went.
+///
+/// # Errors
+///
+/// Returns an error if no games were played or if any `MongoDB` error occurs.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let member = options.get_user_value(MEMBER_OPTION_NAME).transpose()?;
+
+    let records = GameRecord::recent(
+        guild_id.into(),
+        member.map(Into::into),
+        RECORDS_SHOWN
+    ).await?;
+    if records.is_empty() {
+        bail!("No games have been played yet.");
+    }
+
+    let mut description = String::new();
+    for record in &records {
+        description.push_str(
+            &format!(
+                "<t:{}:f> <@{}> {}: **{}**, bet {} and got back {} {}\n",
+                record.timestamp().timestamp(),
+                record.user_id().as_i64(),
+                record.game().as_str(),
+                record.outcome(),
+                record.bet(),
+                record.payout(),
+                record.curr_name()
+            )
+        );
+    }
+    let embed = CreateEmbed::default()
+        .title("Most recent games")
+        .description(description)
+        .colour(ACCENT_COLOUR);
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "records",
+        "Show the most recent games, to check how they went."
+    ).add_sub_option(
+        CreateCommandOption::new(
+            CommandOptionType::User,
+            MEMBER_OPTION_NAME,
+            "Only show the games of this member."
+        ).required(false)
+    )
+}
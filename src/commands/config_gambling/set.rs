@@ -0,0 +1,148 @@
+use anyhow::{ anyhow, bail, Result };
+use chrono::Duration;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::gambling::{ Game, GameConfig },
+    event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    util::money::{ Money, MONEY_SCALE },
+};
+
+use super::{
+    game_option,
+    COOLDOWN_OPTION_NAME,
+    ENABLED_OPTION_NAME,
+    GAME_OPTION_NAME,
+    HOUSE_EDGE_OPTION_NAME,
+    MAX_BET_OPTION_NAME,
+    MIN_BET_OPTION_NAME,
+    NO_MAX_BET_OPTION_NAME,
+};
+
+/// Runs the set gambling config subcommand. Anything not given is kept as it was.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Any of the options could not be resolved
+/// - Both a maximum bet and no maximum bet were given
+/// - The new settings are invalid
+/// - Any `MongoDB` error occurs
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let game: Game = options
+        .get_string_value(GAME_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No game was provided."))??
+        .parse()?;
+    let old = GameConfig::from_game(guild_id.into(), game).await?;
+
+    let enabled = options.get_bool_value(ENABLED_OPTION_NAME).transpose()?;
+    let min_bet = options
+        .get_int_or_number_value(MIN_BET_OPTION_NAME)
+        .transpose()?
+        .map(|n| Money::from_f64(n.cast_to_f64(), MONEY_SCALE))
+        .transpose()?;
+    let max_bet = options
+        .get_int_or_number_value(MAX_BET_OPTION_NAME)
+        .transpose()?
+        .map(|n| Money::from_f64(n.cast_to_f64(), MONEY_SCALE))
+        .transpose()?;
+    let no_max_bet = options.get_bool_value(NO_MAX_BET_OPTION_NAME).transpose()?.unwrap_or(false);
+    let house_edge = options
+        .get_int_or_number_value(HOUSE_EDGE_OPTION_NAME)
+        .transpose()?
+        .map(IntOrNumber::cast_to_f64);
+    let cooldown = options
+        .get_int_or_number_value(COOLDOWN_OPTION_NAME)
+        .transpose()?
+        .map(|n| Duration::seconds(n.cast_to_i64()));
+
+    let max_bet = match (max_bet, no_max_bet) {
+        (Some(_), true) => bail!("Give either a maximum bet or no maximum bet, not both."),
+        (Some(max_bet), false) => Some(max_bet),
+        (None, true) => None,
+        (None, false) => old.max_bet(),
+    };
+
+    let config = GameConfig::set(
+        guild_id.into(),
+        game,
+        enabled.unwrap_or_else(|| old.enabled()),
+        min_bet.unwrap_or_else(|| old.min_bet()),
+        max_bet,
+        house_edge.map_or_else(|| old.house_edge(), |edge| edge / 100.0),
+        cooldown.unwrap_or_else(|| old.cooldown())
+    ).await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!(
+                "{} has been set up and is {}.",
+                game.as_str(),
+                if config.enabled() { "on" } else { "off" }
+            )
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "set",
+        "Set up a gambling game. Anything left out stays as it was."
+    )
+        .add_sub_option(game_option())
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                ENABLED_OPTION_NAME,
+                "If members can play the game. Games are off until turned on."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MIN_BET_OPTION_NAME,
+                "The least that can be bet."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MAX_BET_OPTION_NAME,
+                "The most that can be bet."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                NO_MAX_BET_OPTION_NAME,
+                "Remove the maximum bet."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                HOUSE_EDGE_OPTION_NAME,
+                "Percent of every bet the house keeps on average. 2 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                COOLDOWN_OPTION_NAME,
+                "Seconds a member has to wait between games. 5 by default."
+            ).required(false)
+        )
+}
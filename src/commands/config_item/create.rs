@@ -7,7 +7,9 @@ use serenity::{
 
 use crate::{
     db::models::item::{ self, fieldless::{ ItemActionTypeFieldless, ItemTypeFieldless } },
+    db::models::Currency,
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    util::money::{ Money, MONEY_SCALE },
 };
 
 pub async fn run(
@@ -26,6 +28,15 @@ pub async fn run(
         .get_int_or_number_value(VALUE_OPTION_NAME)
         .transpose()?
         .map(IntOrNumber::cast_to_f64);
+    let precision = if let Some(currency) = currency.as_ref() {
+        Currency::precision_from_name(
+            command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?.into(),
+            currency.clone()
+        ).await?
+    } else {
+        MONEY_SCALE
+    };
+    let value = value.map(|v| Money::from_f64(v, precision)).transpose()?;
     let item_type = options.get_string_value(TYPE_OPTION_NAME).transpose()?;
     let message = options.get_string_value(MESSAGE_OPTION_NAME).transpose()?;
     let action_type = options.get_string_value(ACTION_TYPE_OPTION_NAME).transpose()?;
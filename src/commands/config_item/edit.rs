@@ -4,8 +4,12 @@ use serenity::{
     model::prelude::Mention,
 };
 
-use crate::db::{ models::{ item::ItemTypeUpdateType, Item }, uniques::DropTableName };
+use crate::{
+    db::{ models::{ item::ItemTypeUpdateType, Currency, Item }, uniques::DropTableName },
+    util::money::Money,
+};
 
+#[allow(clippy::too_many_lines)]
 pub async fn run(
     options: crate::event_handler::command_handler::CommandOptions,
     command: &CommandInteraction,
@@ -44,7 +48,17 @@ pub async fn run(
         "sellable" | "sell" => item__.update_sellable(value.parse()?, None).await?,
         "tradeable" | "trade" => item__.update_tradeable(value.parse()?, None).await?,
         "currency" | "currency_value" => item__.update_currency_value(value, None).await?,
-        "value" => item__.update_value(value.parse()?, None).await?,
+        "value" => {
+            let mut new_value = value.parse::<Money>()?;
+            if !item__.currency_value().is_empty() {
+                let precision = Currency::precision_from_name(
+                    guild_id.into(),
+                    item__.currency_value().to_owned()
+                ).await?;
+                new_value = new_value.truncate(precision);
+            }
+            item__.update_value(new_value, None).await?;
+        }
         "type" => {
             item__.update_item_type(
                 item__
@@ -0,0 +1,62 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::rob::{ FineTarget, RobConfig },
+    event_handler::command_handler::CommandOptions,
+    ACCENT_COLOUR,
+};
+
+/// Runs the list rob config subcommand.
+///
+/// # Errors
+///
+/// Returns an error if robbing was never set up or if any `MongoDB` error occurs.
+pub async fn run(
+    _: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let configs = RobConfig::from_guild(guild_id.into()).await?;
+    if configs.is_empty() {
+        bail!("Robbing has not been set up for any currency.");
+    }
+
+    let mut embed = CreateEmbed::default().title("Robbing").colour(ACCENT_COLOUR);
+    for config in &configs {
+        embed = embed.field(
+            config.curr_name(),
+            format!(
+                "{}\n{}% to {}% chance, up to {}% stolen\n{}% fine {}\n\
+                {} seconds between robberies, {} seconds before being robbed again",
+                if config.enabled() { "On" } else { "Off" },
+                config.min_chance() * 100.0,
+                config.max_chance() * 100.0,
+                config.max_steal() * 100.0,
+                config.fine() * 100.0,
+                match config.fine_target() {
+                    FineTarget::Victim => "paid to the victim",
+                    FineTarget::Burn => "burned",
+                },
+                config.robber_cooldown().num_seconds(),
+                config.victim_cooldown().num_seconds()
+            ),
+            false
+        );
+    }
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "list",
+        "List the currencies robbing was set up for."
+    )
+}
@@ -0,0 +1,47 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::CreateCommand,
+    http::CacheHttp,
+    model::Permissions,
+};
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod list;
+pub mod set;
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+const ENABLED_OPTION_NAME: &str = "enabled";
+const MIN_CHANCE_OPTION_NAME: &str = "min_chance";
+const MAX_CHANCE_OPTION_NAME: &str = "max_chance";
+const MAX_STEAL_OPTION_NAME: &str = "max_steal";
+const FINE_OPTION_NAME: &str = "fine";
+const FINE_TARGET_OPTION_NAME: &str = "fine_target";
+const ROBBER_COOLDOWN_OPTION_NAME: &str = "robber_cooldown";
+const VICTIM_COOLDOWN_OPTION_NAME: &str = "victim_cooldown";
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp + Clone
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+    match cmd_name.as_str() {
+        "list" => list::run(cmd_options, command, http).await?,
+        "set" => set::run(cmd_options, command, http).await?,
+        &_ => bail!("Unknown rob config subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("config_rob")
+        .description("Configure which currencies can be robbed and how.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(list::option())
+        .add_option(set::option())
+}
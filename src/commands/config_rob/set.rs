@@ -0,0 +1,171 @@
+use anyhow::{ anyhow, Result };
+use chrono::Duration;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::{ rob::{ FineTarget, RobConfig }, Currency },
+    event_handler::command_handler::{ CommandOptions, IntOrNumber },
+};
+
+use super::{
+    CURRENCY_OPTION_NAME,
+    ENABLED_OPTION_NAME,
+    FINE_OPTION_NAME,
+    FINE_TARGET_OPTION_NAME,
+    MAX_CHANCE_OPTION_NAME,
+    MAX_STEAL_OPTION_NAME,
+    MIN_CHANCE_OPTION_NAME,
+    ROBBER_COOLDOWN_OPTION_NAME,
+    VICTIM_COOLDOWN_OPTION_NAME,
+};
+
+/// Runs the set rob config subcommand. Anything not given is kept as it was.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Any of the options could not be resolved
+/// - The currency does not exist
+/// - The new settings are invalid
+/// - Any `MongoDB` error occurs
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let curr_name = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No currency was provided."))??;
+    // Make sure it exists.
+    Currency::precision_from_name(guild_id.into(), curr_name.clone()).await?;
+    let old = RobConfig::from_currency(guild_id.into(), &curr_name).await?;
+
+    let percent = |name: &str| -> Result<Option<f64>> {
+        Ok(
+            options
+                .get_int_or_number_value(name)
+                .transpose()?
+                .map(|n| n.cast_to_f64() / 100.0)
+        )
+    };
+    let seconds = |name: &str| -> Result<Option<Duration>> {
+        Ok(
+            options
+                .get_int_or_number_value(name)
+                .transpose()?
+                .map(IntOrNumber::cast_to_i64)
+                .map(Duration::seconds)
+        )
+    };
+    let enabled = options.get_bool_value(ENABLED_OPTION_NAME).transpose()?;
+    let fine_target = options
+        .get_string_value(FINE_TARGET_OPTION_NAME)
+        .transpose()?
+        .map(|t| t.parse::<FineTarget>())
+        .transpose()?;
+
+    let config = RobConfig::set(
+        guild_id.into(),
+        curr_name,
+        enabled.unwrap_or_else(|| old.enabled()),
+        percent(MIN_CHANCE_OPTION_NAME)?.unwrap_or_else(|| old.min_chance()),
+        percent(MAX_CHANCE_OPTION_NAME)?.unwrap_or_else(|| old.max_chance()),
+        percent(MAX_STEAL_OPTION_NAME)?.unwrap_or_else(|| old.max_steal()),
+        percent(FINE_OPTION_NAME)?.unwrap_or_else(|| old.fine()),
+        fine_target.unwrap_or_else(|| old.fine_target()),
+        seconds(ROBBER_COOLDOWN_OPTION_NAME)?.unwrap_or_else(|| old.robber_cooldown()),
+        seconds(VICTIM_COOLDOWN_OPTION_NAME)?.unwrap_or_else(|| old.victim_cooldown())
+    ).await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!(
+                "Robbing {} has been set up and is {}.",
+                config.curr_name(),
+                if config.enabled() { "on" } else { "off" }
+            )
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "set",
+        "Set up robbing for a currency. Anything left out stays as it was."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency to set up."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                ENABLED_OPTION_NAME,
+                "If members can rob this currency. Off until turned on."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MIN_CHANCE_OPTION_NAME,
+                "Percent chance a robbery works at least, however rich the robber. 20 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MAX_CHANCE_OPTION_NAME,
+                "Percent chance a robbery works at most, however rich the victim. 80 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                MAX_STEAL_OPTION_NAME,
+                "The most percent of the victim's balance that can be stolen. 25 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                FINE_OPTION_NAME,
+                "Percent of the robber's balance they are fined when caught. 10 by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                FINE_TARGET_OPTION_NAME,
+                "Where fines go. To the victim by default."
+            )
+                .required(false)
+                .add_string_choice("To the victim", FineTarget::Victim.as_str())
+                .add_string_choice("Burned", FineTarget::Burn.as_str())
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                ROBBER_COOLDOWN_OPTION_NAME,
+                "Seconds a member has to wait between robberies. One hour by default."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                VICTIM_COOLDOWN_OPTION_NAME,
+                "Seconds a member can not be robbed after being robbed. 30 minutes by default."
+            ).required(false)
+        )
+}
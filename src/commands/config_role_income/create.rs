@@ -0,0 +1,108 @@
+use anyhow::{ anyhow, Result };
+use chrono::Duration;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType, Mention },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::{ role_income::RoleIncome, Currency },
+    event_handler::command_handler::CommandOptions,
+    util::money::Money,
+};
+
+use super::{ AMOUNT_OPTION_NAME, CURRENCY_OPTION_NAME, INTERVAL_OPTION_NAME, ROLE_OPTION_NAME };
+
+/// Runs the create role income subcommand.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Any of the options could not be resolved
+/// - The currency does not exist
+/// - The role already earns the currency
+/// - The amount or interval are invalid
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let role = options
+        .get_role_value(ROLE_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No role was provided."))??;
+    let currency = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No currency was provided."))??;
+    let amount = options
+        .get_int_or_number_value(AMOUNT_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No amount was provided."))??
+        .cast_to_f64();
+    let interval = options
+        .get_int_or_number_value(INTERVAL_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No interval was provided."))??
+        .cast_to_i64();
+
+    let precision = Currency::precision_from_name(guild_id.into(), currency.clone()).await?;
+    let amount = Money::from_f64(amount, precision)?;
+
+    let income = RoleIncome::new(
+        guild_id.into(),
+        role.into(),
+        currency,
+        amount,
+        Duration::seconds(interval)
+    ).await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!(
+                "Members with {} will now earn {} {} every {} seconds.",
+                Mention::from(role),
+                income.amount(),
+                income.curr_name(),
+                income.interval().num_seconds()
+            )
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "create",
+        "Make a role earn an amount of a currency regularly."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Role,
+                ROLE_OPTION_NAME,
+                "The role that earns the currency."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency that is earned."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                AMOUNT_OPTION_NAME,
+                "How much every member with the role earns each time."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                INTERVAL_OPTION_NAME,
+                "How many seconds between each payout."
+            ).required(true)
+        )
+}
@@ -0,0 +1,66 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType, Mention },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{ db::models::role_income::RoleIncome, event_handler::command_handler::CommandOptions };
+
+use super::{ CURRENCY_OPTION_NAME, ROLE_OPTION_NAME };
+
+/// Runs the delete role income subcommand.
+///
+/// # Errors
+///
+/// Returns an error if the role does not earn the currency or if there is an issue deleting it.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let role = options
+        .get_role_value(ROLE_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No role was provided."))??;
+    let currency = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No currency was provided."))??;
+
+    let income = RoleIncome::try_from_role(
+        guild_id.into(),
+        role.into(),
+        &currency
+    ).await?.ok_or_else(|| anyhow!("That role does not earn {currency}."))?;
+    income.delete().await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!("Members with {} no longer earn {currency}.", Mention::from(role))
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "delete",
+        "Stop a role from earning a currency."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Role,
+                ROLE_OPTION_NAME,
+                "The role that earns the currency."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency that is earned."
+            ).required(true)
+        )
+}
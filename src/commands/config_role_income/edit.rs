@@ -0,0 +1,111 @@
+use anyhow::{ anyhow, bail, Result };
+use chrono::Duration;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType, Mention },
+    builder::{ CreateCommandOption, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::{ role_income::RoleIncome, Currency },
+    event_handler::command_handler::CommandOptions,
+    util::money::Money,
+};
+
+use super::{ AMOUNT_OPTION_NAME, CURRENCY_OPTION_NAME, INTERVAL_OPTION_NAME, ROLE_OPTION_NAME };
+
+/// Runs the edit role income subcommand.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Any of the options could not be resolved
+/// - Neither the amount nor the interval were given
+/// - The role does not earn the currency
+/// - The amount or interval are invalid
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+    let role = options
+        .get_role_value(ROLE_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No role was provided."))??;
+    let currency = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .ok_or_else(|| anyhow!("No currency was provided."))??;
+    let amount = options.get_int_or_number_value(AMOUNT_OPTION_NAME).transpose()?;
+    let interval = options.get_int_or_number_value(INTERVAL_OPTION_NAME).transpose()?;
+
+    if amount.is_none() && interval.is_none() {
+        bail!("Nothing to edit.");
+    }
+
+    let mut income = RoleIncome::try_from_role(
+        guild_id.into(),
+        role.into(),
+        &currency
+    ).await?.ok_or_else(|| anyhow!("That role does not earn {currency}."))?;
+
+    let amount = if let Some(amount) = amount {
+        let precision = Currency::precision_from_name(guild_id.into(), currency).await?;
+        Some(Money::from_f64(amount.cast_to_f64(), precision)?)
+    } else {
+        None
+    };
+    let interval = interval.map(|i| Duration::seconds(i.cast_to_i64()));
+
+    income.update(amount, interval).await?;
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!(
+                "Members with {} now earn {} {} every {} seconds.",
+                Mention::from(role),
+                income.amount(),
+                income.curr_name(),
+                income.interval().num_seconds()
+            )
+        )
+    ).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "edit",
+        "Change how much or how often a role earns a currency."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Role,
+                ROLE_OPTION_NAME,
+                "The role that earns the currency."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency that is earned."
+            ).required(true)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                AMOUNT_OPTION_NAME,
+                "How much every member with the role earns each time."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                INTERVAL_OPTION_NAME,
+                "How many seconds between each payout."
+            ).required(false)
+        )
+}
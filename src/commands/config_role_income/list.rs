@@ -0,0 +1,58 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType, Mention, RoleId },
+    builder::{ CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    http::CacheHttp,
+};
+
+use crate::{
+    db::models::role_income::RoleIncome,
+    event_handler::command_handler::CommandOptions,
+    ACCENT_COLOUR,
+};
+
+/// Runs the list role incomes subcommand.
+///
+/// # Errors
+///
+/// Returns an error if there are no role incomes or if any `MongoDB` error occurs.
+pub async fn run(
+    _: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
+
+    let incomes = RoleIncome::from_guild(guild_id.into()).await?;
+    if incomes.is_empty() {
+        bail!("No role incomes have been set up.");
+    }
+
+    let mut description = String::new();
+    for income in &incomes {
+        description.push_str(
+            &format!(
+                "{} earns *{} {}* every {} seconds\n",
+                Mention::from(RoleId::from(income.role_id())),
+                income.amount(),
+                income.curr_name(),
+                income.interval().num_seconds()
+            )
+        );
+    }
+    let embed = CreateEmbed::default()
+        .title("Role incomes")
+        .description(description)
+        .colour(ACCENT_COLOUR);
+
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "list",
+        "List every role income in this server."
+    )
+}
@@ -0,0 +1,48 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::CreateCommand,
+    http::CacheHttp,
+    model::Permissions,
+};
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod create;
+pub mod delete;
+pub mod edit;
+pub mod list;
+
+const ROLE_OPTION_NAME: &str = "role";
+const CURRENCY_OPTION_NAME: &str = "currency";
+const AMOUNT_OPTION_NAME: &str = "amount";
+const INTERVAL_OPTION_NAME: &str = "interval";
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl CacheHttp + Clone
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+    match cmd_name.as_str() {
+        "list" => list::run(cmd_options, command, http).await?,
+        "create" => create::run(cmd_options, command, http).await?,
+        "edit" => edit::run(cmd_options, command, http).await?,
+        "delete" => delete::run(cmd_options, command, http).await?,
+        &_ => bail!("Unknown role income config subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("config_role_income")
+        .description("Configure currency paid out regularly to members with certain roles.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(list::option())
+        .add_option(create::option())
+        .add_option(edit::option())
+        .add_option(delete::option())
+}
@@ -8,6 +8,7 @@ use serenity::{
 use crate::{
     db::models::{ store::Store, Currency, Item },
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    util::money::Money,
 };
 
 const ITEM_NAME_OPTION_NAME: &str = "item_name";
@@ -27,7 +28,7 @@ pub async fn run(
     let currency_name: String = options
         .get_string_value(CURRENCY_NAME_OPTION_NAME)
         .ok_or_else(|| anyhow!("No currency name provided."))??;
-    let value: f64 = options
+    let value = options
         .get_int_or_number_value(VALUE_OPTION_NAME)
         .ok_or_else(|| anyhow!("No value provided."))?
         .map(IntOrNumber::cast_to_f64)?;
@@ -36,7 +37,8 @@ pub async fn run(
         .transpose()?
         .map_or(1, IntOrNumber::cast_to_i64);
 
-    Currency::try_from_name(guild_id.into(), currency_name.clone()).await?;
+    let precision = Currency::precision_from_name(guild_id.into(), currency_name.clone()).await?;
+    let value = Money::from_f64(value, precision)?;
     Item::try_from_name(guild_id.into(), item_name.clone()).await?;
 
     let store = Store::try_from_guild(guild_id.into()).await?;
@@ -6,8 +6,9 @@ use serenity::{
 };
 
 use crate::{
-    db::models::store::Store,
+    db::models::{ store::Store, Currency },
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    util::money::Money,
 };
 
 /// Runs the command to edit an entry in the store.
@@ -31,6 +32,15 @@ pub async fn run(
         .get_int_or_number_value(VALUE_OPTION_NAME)
         .transpose()?
         .map(IntOrNumber::cast_to_f64);
+    let value = if let Some(value) = value {
+        let precision = Currency::precision_from_name(
+            guild_id.into(),
+            currency_name.clone()
+        ).await?;
+        Some(Money::from_f64(value, precision)?)
+    } else {
+        None
+    };
     let amount: Option<i64> = options
         .get_int_or_number_value(AMOUNT_OPTION_NAME)
         .transpose()?
@@ -50,7 +60,7 @@ pub async fn run(
         .ok_or_else(|| anyhow!("Store is being used in a breaking operation."))?;
 
     if let Some(value) = value {
-        if value < 0.0 {
+        if value.is_negative() {
             anyhow::bail!("Value cannot be negative.");
         }
         store_.edit_entry_value(&item_name, &currency_name, value, None).await?;
@@ -33,8 +33,6 @@ pub async fn run(
         .as_ref()
         .ok_or_else(|| anyhow!("Command can't be performed in DMs"))?;
 
-    let amount = amount.cast_to_f64();
-
     let input = Currency::try_from_name(command.guild_id.unwrap().into(), input).await?.ok_or_else(
         || anyhow!("Input currency not found")
     )?;
@@ -52,6 +50,8 @@ pub async fn run(
         .as_ref()
         .ok_or_else(|| anyhow!("Output currency is being used in a breaking operation"))?;
 
+    let amount = input.amount_from_f64(amount.cast_to_f64())?;
+
     let given = exchange(input, output, amount, member).await?;
 
     command.edit_response(
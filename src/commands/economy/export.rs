@@ -0,0 +1,181 @@
+use std::collections::{ HashMap, HashSet };
+
+use anyhow::{ anyhow, Result };
+use futures::TryStreamExt;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateAttachment, CreateCommandOption, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    db::{ models::{ Balances, Currency, InventoryEntry, Item }, uniques::DbGuildId },
+    event_handler::command_handler::CommandOptions,
+    util::csv,
+};
+
+const HEADER: [&str; 5] = ["user_id", "type", "name", "symbol", "amount"];
+
+/// Sends the balances and inventories of the guild as a CSV file, with one row per balance or
+/// inventory entry. If a currency or an item is given, only that one is exported.
+///
+/// # Errors
+///
+/// This function can return an error if the currency or item is not found or any `MongoDB` error
+/// occurs.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let currency = options.get_string_value(CURRENCY_OPTION_NAME).transpose()?;
+    let item = options.get_string_value(ITEM_OPTION_NAME).transpose()?;
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command can't be performed in DMs."))?;
+    let db_guild_id = DbGuildId::from(guild_id);
+
+    // Without a filter everything is exported, otherwise only what was asked for.
+    let everything = currency.is_none() && item.is_none();
+    let mut out = String::new();
+    csv::write_record(&mut out, &HEADER);
+    let mut rows = 0;
+    if everything || currency.is_some() {
+        rows += write_balances(&mut out, db_guild_id, currency.as_deref()).await?;
+    }
+    if everything || item.is_some() {
+        rows += write_inventories(&mut out, db_guild_id, item.as_deref()).await?;
+    }
+
+    let file_name = format!(
+        "economy-{}-{}.csv",
+        guild_id.get(),
+        chrono::Utc::now().format("%Y-%m-%d-%H%M%S")
+    );
+    command.edit_response(
+        &http,
+        EditInteractionResponse::new()
+            .content(format!("Exported {rows} rows."))
+            .new_attachment(CreateAttachment::bytes(out.into_bytes(), file_name))
+    ).await?;
+    Ok(())
+}
+
+/// Writes a row for every balance of the currency, or of every currency, going through the
+/// balances as they come in, and a `bank` row after it if there is something in the bank.
+/// Balances of currencies that no longer exist are left out.
+async fn write_balances(
+    out: &mut String,
+    guild_id: DbGuildId,
+    currency: Option<&str>
+) -> Result<usize> {
+    let mut symbols = HashMap::new();
+    for curr in Currency::try_from_guild(guild_id).await?.iter() {
+        let curr = curr.read().await;
+        if let Some(curr) = curr.as_ref() {
+            symbols.insert(curr.curr_name().as_str().to_owned(), curr.symbol().to_owned());
+        }
+    }
+    if let Some(currency) = currency {
+        if !symbols.contains_key(currency) {
+            return Err(anyhow!("Currency not found."));
+        }
+    }
+
+    let mut rows = 0;
+    let mut cursor = Balances::cursor_of_guild(guild_id, currency).await?;
+    while let Some(balance) = cursor.try_next().await? {
+        let Some(symbol) = symbols.get(balance.curr_name()) else {
+            continue;
+        };
+        csv::write_record(
+            out,
+            &[
+                u64::from(balance.user_id()).to_string(),
+                "currency".to_owned(),
+                balance.curr_name().to_owned(),
+                symbol.clone(),
+                balance.amount().to_string(),
+            ]
+        );
+        rows += 1;
+        if !balance.bank().is_zero() {
+            csv::write_record(
+                out,
+                &[
+                    u64::from(balance.user_id()).to_string(),
+                    "bank".to_owned(),
+                    balance.curr_name().to_owned(),
+                    symbol.clone(),
+                    balance.bank().to_string(),
+                ]
+            );
+            rows += 1;
+        }
+    }
+    Ok(rows)
+}
+
+/// Writes a row for every inventory entry of the item, or of every item, the same way as
+/// `write_balances`.
+async fn write_inventories(
+    out: &mut String,
+    guild_id: DbGuildId,
+    item: Option<&str>
+) -> Result<usize> {
+    let mut names = HashSet::new();
+    for it in Item::try_from_guild(guild_id).await? {
+        let it = it.read().await;
+        if let Some(it) = it.as_ref() {
+            names.insert(it.name().to_owned());
+        }
+    }
+    if let Some(item) = item {
+        if !names.contains(item) {
+            return Err(anyhow!("Item not found."));
+        }
+    }
+
+    let mut rows = 0;
+    let mut cursor = InventoryEntry::cursor_of_guild(guild_id, item).await?;
+    while let Some(entry) = cursor.try_next().await? {
+        if !names.contains(entry.item_name()) {
+            continue;
+        }
+        csv::write_record(
+            out,
+            &[
+                u64::from(entry.user_id()).to_string(),
+                "item".to_owned(),
+                entry.item_name().to_owned(),
+                String::new(),
+                entry.amount().to_string(),
+            ]
+        );
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+const ITEM_OPTION_NAME: &str = "item";
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "export",
+        "Get the balances and inventories of every member as a CSV file for spreadsheets."
+    )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "Only export the balances of this currency."
+            ).required(false)
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                ITEM_OPTION_NAME,
+                "Only export how many members have of this item."
+            ).required(false)
+        )
+}
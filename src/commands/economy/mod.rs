@@ -0,0 +1,35 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::CommandInteraction,
+    builder::CreateCommand,
+    client::Context,
+    model::Permissions,
+};
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod export;
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+
+    match cmd_name.as_str() {
+        "export" => export::run(cmd_options, command, http).await?,
+        &_ => anyhow::bail!("Unknown economy subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("economy")
+        .description("Look at the economy of the server as a whole.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(export::option())
+}
@@ -0,0 +1,153 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    db::models::gambling::Game,
+    event_handler::command_handler::CommandOptions,
+    mechanics::gambling::{ play, Bet, PlayOutcome, DICE_SIDES },
+    ACCENT_COLOUR,
+};
+
+/// Runs the `/coinflip`, `/dice` or `/slots` command, depending on the game.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - An option is missing or invalid.
+/// - Any error from playing the game.
+pub async fn run(
+    game: Game,
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+    let curr_name = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No currency was found"))?;
+    let amount = options
+        .get_int_or_number_value(BET_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No bet was found"))?
+        .cast_to_f64();
+    let bet = match game {
+        Game::Coinflip => {
+            let side = options
+                .get_string_value(SIDE_OPTION_NAME)
+                .transpose()?
+                .ok_or_else(|| anyhow!("No side was found"))?;
+            Bet::Coinflip(side.parse()?)
+        }
+        Game::Dice => {
+            let number = options
+                .get_int_or_number_value(NUMBER_OPTION_NAME)
+                .transpose()?
+                .ok_or_else(|| anyhow!("No number was found"))?
+                .cast_to_i64();
+            Bet::Dice(u8::try_from(number).map_err(|_| anyhow!("That is not a side of the die."))?)
+        }
+        Game::Slots => Bet::Slots,
+    };
+
+    let embed = match play(guild_id, command.user.id, &curr_name, amount, bet).await? {
+        PlayOutcome::OnCooldown(available) =>
+            CreateEmbed::default()
+                .title("Not yet")
+                .description(
+                    format!(
+                        "You can play {} again <t:{}:R>.",
+                        game.as_str(),
+                        available.timestamp()
+                    )
+                )
+                .colour(ACCENT_COLOUR),
+        PlayOutcome::Played(played) => {
+            let result = match game {
+                Game::Coinflip => format!("The coin landed on **{}**.", played.outcome),
+                Game::Dice => format!("The die rolled a **{}**.", played.outcome),
+                Game::Slots => format!("The reels stopped on {}.", played.outcome),
+            };
+            let settled = if played.payout.is_zero() {
+                format!("You lost **{}** {curr_name}.", played.bet)
+            } else {
+                format!(
+                    "You bet **{}** and got back **{}** {curr_name}.",
+                    played.bet,
+                    played.payout
+                )
+            };
+            CreateEmbed::default()
+                .title(game.as_str())
+                .description(format!("{result}\n{settled}"))
+                .colour(ACCENT_COLOUR)
+        }
+    };
+
+    command.edit_response(http, EditInteractionResponse::new().embed(embed)).await?;
+    Ok(())
+}
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+const BET_OPTION_NAME: &str = "bet";
+const SIDE_OPTION_NAME: &str = "side";
+const NUMBER_OPTION_NAME: &str = "number";
+
+fn wager_options(command: CreateCommand) -> CreateCommand {
+    command
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency to bet."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                BET_OPTION_NAME,
+                "How much to bet."
+            ).required(true)
+        )
+}
+
+pub fn coinflip_command() -> CreateCommand {
+    wager_options(
+        CreateCommand::new("coinflip")
+            .description("Bet on the side a coin lands on. Pays about double if you call it.")
+            .dm_permission(false)
+    ).add_option(
+        CreateCommandOption::new(CommandOptionType::String, SIDE_OPTION_NAME, "Heads or tails.")
+            .required(true)
+            .add_string_choice("heads", "heads")
+            .add_string_choice("tails", "tails")
+    )
+}
+
+pub fn dice_command() -> CreateCommand {
+    wager_options(
+        CreateCommand::new("dice")
+            .description("Bet on the number a die rolls. Pays about six times if you guess it.")
+            .dm_permission(false)
+    ).add_option(
+        CreateCommandOption::new(
+            CommandOptionType::Integer,
+            NUMBER_OPTION_NAME,
+            "The number you think the die rolls."
+        )
+            .required(true)
+            .min_int_value(1)
+            .max_int_value(u64::from(DICE_SIDES))
+    )
+}
+
+pub fn slots_command() -> CreateCommand {
+    wager_options(
+        CreateCommand::new("slots")
+            .description("Bet on the slot machine. Two or three of a kind pay out.")
+            .dm_permission(false)
+    )
+}
@@ -0,0 +1,93 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption, CreateMessage, EditInteractionResponse },
+    client::Context,
+};
+use tracing::warn;
+
+use crate::{
+    event_handler::command_handler::{ CommandOptions, IntOrNumber },
+    mechanics::gift::gift,
+};
+
+/// Runs the `/gift` command.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - An option is missing, or the member is not in the guild.
+/// - Any error from gifting.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+    let receiver = options
+        .get_user_value(MEMBER_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No member was found"))?
+        .to_user(http).await?;
+    let item_name = options
+        .get_string_value(ITEM_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No item was found"))?;
+    let amount = options
+        .get_int_or_number_value(AMOUNT_OPTION_NAME)
+        .transpose()?
+        .unwrap_or(IntOrNumber::Int(1))
+        .cast_to_i64();
+
+    if guild_id.member(http, receiver.id).await.is_err() {
+        return Err(anyhow!("Member {} is not in this guild.", receiver.name));
+    }
+
+    let instant = gift(guild_id, &command.user, &receiver, &item_name, amount, http).await?;
+
+    let used = if instant { " It was used right away." } else { "" };
+    let dm = format!("{} gifted you {amount} {item_name}.{used}", command.user.name);
+    // The receiver may have their DMs closed, which is not a reason to fail the gift.
+    if let Err(e) = receiver.direct_message(http, CreateMessage::new().content(dm)).await {
+        warn!("Could not notify {} of gift: {}", receiver.id, e);
+    }
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!("You gifted {} {amount} {item_name}.", receiver.name)
+        )
+    ).await?;
+
+    Ok(())
+}
+
+const MEMBER_OPTION_NAME: &str = "member";
+const ITEM_OPTION_NAME: &str = "item";
+const AMOUNT_OPTION_NAME: &str = "amount";
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("gift")
+        .description("Give another member some of your items.")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                MEMBER_OPTION_NAME,
+                "The member to gift the items to."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                ITEM_OPTION_NAME,
+                "The item to gift."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                AMOUNT_OPTION_NAME,
+                "How many to gift. Defaults to 1."
+            ).required(false)
+        )
+}
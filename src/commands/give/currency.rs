@@ -6,9 +6,9 @@ use serenity::{
 };
 
 use crate::{
-    db::models::{ Balances, Currency },
+    db::models::{ Balances, Currency, TransactionKind, TransactionReason },
     event_handler::command_handler::CommandOptions,
-    util::currency::truncate_2dp,
+    util::money::Money,
 };
 
 #[allow(clippy::unused_async)]
@@ -18,7 +18,7 @@ pub async fn run(
     command: &CommandInteraction,
     http: impl AsRef<Http> + Send + Sync + Clone + CacheHttp
 ) -> Result<()> {
-    let mut amount: f64 = options
+    let amount: f64 = options
         .get_int_or_number_value("amount")
         .ok_or_else(|| anyhow!("No amount was provided."))??
         .cast_to_f64();
@@ -39,13 +39,11 @@ pub async fn run(
         return Err(anyhow!("No member was provided."));
     }
 
-    amount = truncate_2dp(amount);
-
-    if
-        Currency::try_from_name(command.guild_id.unwrap().into(), currency.clone()).await?.is_none()
-    {
-        return Err(anyhow!("Currency {} does not exist.", currency));
-    }
+    let precision = Currency::precision_from_name(
+        command.guild_id.unwrap().into(),
+        currency.clone()
+    ).await?;
+    let amount = Money::from_f64(amount, precision)?;
 
     if
         command.guild_id
@@ -89,7 +87,11 @@ pub async fn run(
         );
     };
 
-    balance.add_amount_unchecked(amount, None).await?;
+    balance.add_amount_unchecked(
+        amount,
+        TransactionReason::new(TransactionKind::Give, command.user.id.into()),
+        None
+    ).await?;
 
     drop(balances);
 
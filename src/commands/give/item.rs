@@ -6,7 +6,7 @@ use serenity::{
 };
 
 use crate::{
-    db::models::{ Inventory, Item },
+    db::models::{ Inventory, Item, TransactionKind, TransactionReason },
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
 };
 
@@ -37,7 +37,14 @@ pub async fn run(
         .as_mut()
         .ok_or_else(|| anyhow!("Member's inventory is being used in a breaking operation."))?;
 
-    member_inv_.give_item(item, amount, None, 0, http).await?;
+    member_inv_.give_item(
+        item,
+        amount,
+        TransactionReason::new(TransactionKind::Give, command.user.id.into()),
+        None,
+        0,
+        http
+    ).await?;
 
     drop(member_inv);
 
@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ ButtonStyle, CommandInteraction, CommandOptionType, Permissions, ReactionType, UserId },
+    builder::{
+        CreateActionRow,
+        CreateButton,
+        CreateCommand,
+        CreateCommandOption,
+        CreateEmbed,
+        CreateEmbedAuthor,
+        EditInteractionResponse,
+    },
+    client::Context,
+};
+
+use crate::{
+    db::models::{ transaction::TransactionChange, Transaction },
+    event_handler::command_handler::CommandOptions,
+    util::paginator::Paginator,
+    ACCENT_COLOUR,
+};
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+const ITEM_OPTION_NAME: &str = "item";
+const MEMBER_OPTION_NAME: &str = "member";
+
+/// How many of the most recent transactions are fetched at most.
+const HISTORY_LIMIT: i64 = 250;
+
+#[allow(clippy::option_if_let_else)]
+/// Run the command.
+///
+/// Members can only see their own history, staff members (those with the manage server
+/// permission) can see the history of anyone.
+///
+/// # Errors
+///
+/// This function can return an error if there is a problem executing the command.
+pub async fn run(options: CommandOptions, command: &CommandInteraction, ctx: &Context) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be used in DMs"))?;
+    let user_id = command.user.id;
+    let currency = options.get_string_value(CURRENCY_OPTION_NAME).transpose()?;
+    let item = options.get_string_value(ITEM_OPTION_NAME).transpose()?;
+    let member = options.get_user_value(MEMBER_OPTION_NAME).transpose()?;
+
+    let target = if let Some(member) = member.filter(|m| *m != user_id) {
+        let is_staff = command.member
+            .as_ref()
+            .and_then(|m| m.permissions)
+            .is_some_and(Permissions::manage_guild);
+        if !is_staff {
+            bail!("Only staff members can view the history of other members.");
+        }
+        member.to_user(&ctx).await?
+    } else {
+        command.user.clone()
+    };
+    let username = target.name.as_str();
+    let icon = target.face();
+
+    let entries = Transaction::history(
+        guild_id.into(),
+        target.id.into(),
+        currency.as_deref(),
+        item.as_deref(),
+        HISTORY_LIMIT
+    ).await?;
+
+    if entries.is_empty() {
+        bail!("No history found.");
+    }
+
+    let mut paginator = Paginator::new(entries, 10)?;
+
+    let controls = history_controls();
+    let (first_button_id, next_button_id, prev_button_id, last_button_id) = (
+        controls.first_button_id,
+        controls.next_button_id,
+        controls.prev_button_id,
+        controls.last_button_id,
+    );
+
+    command.edit_response(
+        &ctx,
+        EditInteractionResponse::new()
+            .embed(make_embed(paginator.first_page(), target.id, username, &icon))
+            .components(vec![controls.row.clone()])
+    ).await?;
+
+    let response = command.get_response(&ctx).await?;
+
+    loop {
+        let interaction = response
+            .await_component_interaction(ctx)
+            .author_id(user_id)
+            .custom_ids(
+                vec![
+                    first_button_id.clone(),
+                    next_button_id.clone(),
+                    prev_button_id.clone(),
+                    last_button_id.clone()
+                ]
+            )
+            .timeout(Duration::from_secs(30)).await;
+        if let Some(i) = interaction {
+            i.defer_ephemeral(&ctx).await?;
+            let id: &str = &i.data.custom_id;
+            let page = match id {
+                id if first_button_id == id => paginator.first_page(),
+                id if next_button_id == id => {
+                    let pg = paginator.next_page();
+                    if let Some(p) = pg {
+                        p
+                    } else {
+                        paginator.current_page()
+                    }
+                }
+                id if prev_button_id == id => {
+                    let pg = paginator.prev_page();
+                    if let Some(p) = pg {
+                        p
+                    } else {
+                        paginator.current_page()
+                    }
+                }
+                id if last_button_id == id => paginator.last_page(),
+                _ => { bail!("Invalid button id") }
+            };
+            command.edit_response(
+                &ctx,
+                EditInteractionResponse::new().embed(
+                    make_embed(page, target.id, username, &icon)
+                )
+            ).await?;
+            i.delete_response(&ctx).await?;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn make_embed(data: &[Transaction], target: UserId, username: &str, icon: &str) -> CreateEmbed {
+    let author = CreateEmbedAuthor::new(username).icon_url(icon);
+    let embed = CreateEmbed::default().title("History").author(author);
+    let mut description = String::new();
+    for transaction in data {
+        let change = match transaction.change() {
+            TransactionChange::Currency { curr_name, delta, resulting } => {
+                let sign = if delta.is_negative() { "" } else { "+" };
+                format!("{sign}{delta} {curr_name} (now {resulting})")
+            }
+            TransactionChange::Item { item_name, delta, resulting } => {
+                let sign = if *delta < 0 { "" } else { "+" };
+                format!("{sign}{delta} {item_name} (now {resulting})")
+            }
+            TransactionChange::Bank { curr_name, delta, resulting } => {
+                let sign = if delta.is_negative() { "" } else { "+" };
+                format!("{sign}{delta} {curr_name} in the bank (now {resulting})")
+            }
+        };
+        description.push_str(
+            &format!(
+                "<t:{}:f> **{}** *{}*",
+                transaction.timestamp().timestamp(),
+                transaction.kind().as_str(),
+                change
+            )
+        );
+        if let Some(actor) = transaction.actor().filter(|a| UserId::from(*a) != target) {
+            description.push_str(&format!(" by <@{}>", UserId::from(actor)));
+        }
+        description.push('\n');
+    }
+    embed.description(description).colour(ACCENT_COLOUR)
+}
+
+struct HistoryControls {
+    row: CreateActionRow,
+    first_button_id: String,
+    next_button_id: String,
+    prev_button_id: String,
+    last_button_id: String,
+}
+
+fn history_controls() -> HistoryControls {
+    let now = chrono::Utc::now();
+    let first_id = format!("{now}first_page");
+    let next_id = format!("{now}next_page");
+    let prev_id = format!("{now}prev_page");
+    let last_id = format!("{now}last_page");
+    let first_button = CreateButton::new(first_id.clone())
+        .emoji(ReactionType::Unicode("⏮️".to_owned()))
+        .style(ButtonStyle::Primary);
+    let last_button = CreateButton::new(last_id.clone())
+        .emoji(ReactionType::Unicode("⏭️".to_owned()))
+        .style(ButtonStyle::Primary);
+    let next_button = CreateButton::new(next_id.clone())
+        .emoji(ReactionType::Unicode("⏩".to_owned()))
+        .style(ButtonStyle::Primary);
+    let prev_button = CreateButton::new(prev_id.clone())
+        .emoji(ReactionType::Unicode("⏪".to_owned()))
+        .style(ButtonStyle::Primary);
+    let action_row = CreateActionRow::Buttons(
+        vec![first_button, prev_button, next_button, last_button]
+    );
+    HistoryControls {
+        row: action_row,
+        first_button_id: first_id,
+        next_button_id: next_id,
+        prev_button_id: prev_id,
+        last_button_id: last_id,
+    }
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("history")
+        .description("View your recent economy history.")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "Only show changes to this currency."
+            ).required(false)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                ITEM_OPTION_NAME,
+                "Only show changes to this item."
+            ).required(false)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                MEMBER_OPTION_NAME,
+                "The member whose history to view. Staff only."
+            ).required(false)
+        )
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use anyhow::{ anyhow, bail, Result };
+use futures::TryStreamExt;
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption, EditInteractionResponse },
+    client::Context,
+    model::Permissions,
+};
+
+use crate::{
+    db::{
+        import::{ BalanceImport, ColumnMapping, ImportReport },
+        models::{ TransactionKind, TransactionReason },
+        uniques::DbUserId,
+    },
+    event_handler::command_handler::CommandOptions,
+};
+
+/// Files bigger than this are not downloaded.
+const MAX_FILE_BYTES: u32 = 25 * 1024 * 1024;
+/// How many of the rows with errors and of the unknown users are listed in the report.
+const MAX_LISTED: usize = 10;
+
+/// Imports balances and items from a CSV file exported by another economy bot. Unless told to
+/// commit, it only shows what would be imported.
+///
+/// # Errors
+///
+/// This function can return an error if the file can't be read, a column or what it is mapped to
+/// is not found, or any `MongoDB` error occurs.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let attachment_id = options
+        .get_attachment_value(FILE_OPTION_NAME)
+        .ok_or_else(|| anyhow!("CSV file not found."))??;
+    let mappings = ColumnMapping::parse_list(
+        &options
+            .get_string_value(COLUMNS_OPTION_NAME)
+            .ok_or_else(|| anyhow!("Column mappings not found."))??
+    )?;
+    let user_column = options.get_string_value(USER_COLUMN_OPTION_NAME).transpose()?;
+    let commit = options.get_bool_value(COMMIT_OPTION_NAME).transpose()?.unwrap_or(false);
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command can't be performed in DMs."))?;
+
+    let attachment = command.data.resolved.attachments
+        .get(&attachment_id)
+        .ok_or_else(|| anyhow!("CSV file not found."))?;
+    if attachment.size > MAX_FILE_BYTES {
+        bail!("CSV file is too big.");
+    }
+    let bytes = attachment.download().await?;
+    let text = String::from_utf8(bytes).map_err(|_| anyhow!("The file is not a text file."))?;
+
+    // The cache does not have every member of big guilds, so ask Discord for all of them.
+    let members = guild_id
+        .members_iter(&http.http)
+        .map_ok(|m| DbUserId::from(m.user.id))
+        .try_collect::<HashSet<_>>().await?;
+
+    let import = BalanceImport::prepare(
+        guild_id.into(),
+        &text,
+        user_column.as_deref(),
+        &mappings,
+        |user_id| members.contains(&user_id)
+    ).await?;
+
+    let content = if commit {
+        let reason = TransactionReason::new(TransactionKind::Import, command.user.id.into());
+        let report = import.commit(reason).await?;
+        format!("**Imported**\n{}", describe(&report))
+    } else {
+        format!(
+            "**Dry run, nothing was imported yet.** Run this again with commit set to true to \
+            import it.\n{}",
+            describe(import.report())
+        )
+    };
+    command.edit_response(&http, EditInteractionResponse::new().content(content)).await?;
+    Ok(())
+}
+
+fn describe(report: &ImportReport) -> String {
+    let mut description = format!(
+        "{} rows read, {} members get something.\n",
+        report.rows,
+        report.members
+    );
+    for (target, total) in &report.totals {
+        description.push_str(&format!("- {target}: {total} in total\n"));
+    }
+    if !report.unknown_users.is_empty() {
+        let listed = report.unknown_users
+            .iter()
+            .take(MAX_LISTED)
+            .map(|id| format!("<@{}>", id.as_i64()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let listed = if report.unknown_users.len() > MAX_LISTED {
+            format!("{listed} and {} more", report.unknown_users.len() - MAX_LISTED)
+        } else {
+            listed
+        };
+        description.push_str(
+            &format!(
+                "{} users are not in the server and are skipped: {}\n",
+                report.unknown_users.len(),
+                listed
+            )
+        );
+    }
+    if !report.errors.is_empty() {
+        description.push_str(&format!("{} rows are skipped:\n", report.errors.len()));
+        for error in report.errors.iter().take(MAX_LISTED) {
+            description.push_str(&format!("- Line {}: {}\n", error.line, error.message));
+        }
+        if report.errors.len() > MAX_LISTED {
+            description.push_str(&format!("- And {} more\n", report.errors.len() - MAX_LISTED));
+        }
+    }
+    description
+}
+
+const FILE_OPTION_NAME: &str = "file";
+const COLUMNS_OPTION_NAME: &str = "columns";
+const USER_COLUMN_OPTION_NAME: &str = "user_column";
+const COMMIT_OPTION_NAME: &str = "commit";
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("import")
+        .description("Import balances and items from a CSV file exported by another economy bot.")
+        .dm_permission(false)
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Attachment,
+                FILE_OPTION_NAME,
+                "The CSV file. The first row has to have the names of the columns."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                COLUMNS_OPTION_NAME,
+                "Which columns go into what, like cash=Coins, bank=Coins, gems=item:Gem"
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                USER_COLUMN_OPTION_NAME,
+                "The column with the user ids, if it is not called something like user id."
+            ).required(false)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                COMMIT_OPTION_NAME,
+                "Import it for real. Otherwise it only shows what would be imported."
+            ).required(false)
+        )
+}
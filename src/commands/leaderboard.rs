@@ -0,0 +1,253 @@
+use std::{ collections::HashMap, time::Duration };
+
+use anyhow::{ anyhow, bail, Result };
+use serenity::{
+    all::{ ButtonStyle, CommandInteraction, CommandOptionType, ReactionType, UserId },
+    builder::{
+        CreateActionRow,
+        CreateButton,
+        CreateCommand,
+        CreateCommandOption,
+        CreateEmbed,
+        CreateEmbedFooter,
+        EditInteractionResponse,
+    },
+    client::Context,
+};
+
+use crate::{
+    db::{ models::{ Balances, Currency }, uniques::{ DbGuildId, DbUserId } },
+    event_handler::command_handler::CommandOptions,
+    util::{ money::Money, paginator::Paginator },
+    ACCENT_COLOUR,
+};
+
+const CURRENCY_OPTION_NAME: &str = "currency";
+
+#[allow(clippy::option_if_let_else)]
+/// Run the command.
+///
+/// If a currency is given, members are ranked by their balance of it. Otherwise they are
+/// ranked by their net worth, which is every balance they have converted to the base currency.
+///
+/// # Errors
+///
+/// This function can return an error if there is a problem executing the command.
+pub async fn run(options: CommandOptions, command: &CommandInteraction, ctx: &Context) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be used in DMs"))?;
+    let user_id = command.user.id;
+    let currency = options.get_string_value(CURRENCY_OPTION_NAME).transpose()?;
+
+    let (title, symbol, entries) = if let Some(currency) = currency {
+        let currency = Currency::try_from_name(guild_id.into(), currency).await?.ok_or_else(||
+            anyhow!("Currency not found")
+        )?;
+        let currency = currency.read().await;
+        let currency_ = currency
+            .as_ref()
+            .ok_or_else(|| anyhow!("Currency is being used in a breaking operation"))?;
+        let title = format!("{} leaderboard", currency_.curr_name().as_str());
+        let symbol = currency_.symbol().to_owned();
+        let entries = Balances::leaderboard(guild_id.into(), currency_.curr_name()).await?;
+        drop(currency);
+        (title, symbol, entries)
+    } else {
+        let (symbol, entries) = net_worth(guild_id.into()).await?;
+        ("Net worth leaderboard".to_owned(), symbol, entries)
+    };
+
+    if entries.is_empty() {
+        bail!("Nobody is on this leaderboard yet.");
+    }
+
+    let caller_rank = entries
+        .iter()
+        .position(|(id, _)| *id == DbUserId::from(user_id))
+        .map(|i| i + 1);
+
+    let ranked = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (id, amount))| (i + 1, UserId::from(id), amount))
+        .collect::<Vec<_>>();
+
+    let mut paginator = Paginator::new(ranked, 10)?;
+
+    let controls = leaderboard_controls();
+    let (first_button_id, next_button_id, prev_button_id, last_button_id) = (
+        controls.first_button_id,
+        controls.next_button_id,
+        controls.prev_button_id,
+        controls.last_button_id,
+    );
+
+    command.edit_response(
+        &ctx,
+        EditInteractionResponse::new()
+            .embed(make_embed(paginator.first_page(), &title, &symbol, caller_rank))
+            .components(vec![controls.row.clone()])
+    ).await?;
+
+    let response = command.get_response(&ctx).await?;
+
+    loop {
+        let interaction = response
+            .await_component_interaction(ctx)
+            .author_id(user_id)
+            .custom_ids(
+                vec![
+                    first_button_id.clone(),
+                    next_button_id.clone(),
+                    prev_button_id.clone(),
+                    last_button_id.clone()
+                ]
+            )
+            .timeout(Duration::from_secs(30)).await;
+        if let Some(i) = interaction {
+            i.defer_ephemeral(&ctx).await?;
+            let id: &str = &i.data.custom_id;
+            let page = match id {
+                id if first_button_id == id => paginator.first_page(),
+                id if next_button_id == id => {
+                    let pg = paginator.next_page();
+                    if let Some(p) = pg {
+                        p
+                    } else {
+                        paginator.current_page()
+                    }
+                }
+                id if prev_button_id == id => {
+                    let pg = paginator.prev_page();
+                    if let Some(p) = pg {
+                        p
+                    } else {
+                        paginator.current_page()
+                    }
+                }
+                id if last_button_id == id => paginator.last_page(),
+                _ => { bail!("Invalid button id") }
+            };
+            command.edit_response(
+                &ctx,
+                EditInteractionResponse::new().embed(make_embed(page, &title, &symbol, caller_rank))
+            ).await?;
+            i.delete_response(&ctx).await?;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sums up every member's balances in terms of the base currency and ranks them.
+///
+/// Balances of currencies that cannot be converted to the base currency are left out.
+async fn net_worth(guild_id: DbGuildId) -> Result<(String, Vec<(DbUserId, Money)>)> {
+    let currencies = Currency::try_from_guild(guild_id).await?;
+
+    let mut convertible: HashMap<String, Currency> = HashMap::new();
+    let mut base_symbol = None;
+    for currency in currencies.iter() {
+        let currency = currency.read().await;
+        let Some(currency_) = currency.as_ref() else {
+            continue;
+        };
+        if currency_.is_base() {
+            base_symbol = Some(currency_.symbol().to_owned());
+        }
+        if currency_.is_base() || currency_.base_value().is_some() {
+            convertible.insert(currency_.curr_name().as_str().to_owned(), currency_.clone());
+        }
+        drop(currency);
+    }
+
+    let Some(base_symbol) = base_symbol else {
+        bail!("No base currency found.");
+    };
+
+    let mut entries = Balances::all_from_guild(guild_id).await?
+        .into_iter()
+        .map(|(user_id, balances)| {
+            let total = balances
+                .iter()
+                .filter_map(|(curr_name, amount)| {
+                    convertible.get(curr_name).and_then(|c| c.as_base(*amount))
+                })
+                .fold(Money::ZERO, Money::saturating_add);
+            (user_id, total)
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    Ok((base_symbol, entries))
+}
+
+fn make_embed(
+    data: &[(usize, UserId, Money)],
+    title: &str,
+    symbol: &str,
+    caller_rank: Option<usize>
+) -> CreateEmbed {
+    let footer = caller_rank.map_or_else(
+        || "You are not on this leaderboard.".to_owned(),
+        |rank| format!("Your rank: #{rank}")
+    );
+    let embed = CreateEmbed::default().title(title).footer(CreateEmbedFooter::new(footer));
+    let mut description = String::new();
+    for (rank, user_id, amount) in data {
+        description.push_str(&format!("**#{rank}** <@{user_id}> *{symbol}{amount}*\n"));
+    }
+    embed.description(description).colour(ACCENT_COLOUR)
+}
+
+struct LeaderboardControls {
+    row: CreateActionRow,
+    first_button_id: String,
+    next_button_id: String,
+    prev_button_id: String,
+    last_button_id: String,
+}
+
+fn leaderboard_controls() -> LeaderboardControls {
+    let now = chrono::Utc::now();
+    let first_id = format!("{now}first_page");
+    let next_id = format!("{now}next_page");
+    let prev_id = format!("{now}prev_page");
+    let last_id = format!("{now}last_page");
+    let first_button = CreateButton::new(first_id.clone())
+        .emoji(ReactionType::Unicode("⏮️".to_owned()))
+        .style(ButtonStyle::Primary);
+    let last_button = CreateButton::new(last_id.clone())
+        .emoji(ReactionType::Unicode("⏭️".to_owned()))
+        .style(ButtonStyle::Primary);
+    let next_button = CreateButton::new(next_id.clone())
+        .emoji(ReactionType::Unicode("⏩".to_owned()))
+        .style(ButtonStyle::Primary);
+    let prev_button = CreateButton::new(prev_id.clone())
+        .emoji(ReactionType::Unicode("⏪".to_owned()))
+        .style(ButtonStyle::Primary);
+    let action_row = CreateActionRow::Buttons(
+        vec![first_button, prev_button, next_button, last_button]
+    );
+    LeaderboardControls {
+        row: action_row,
+        first_button_id: first_id,
+        next_button_id: next_id,
+        prev_button_id: prev_id,
+        last_button_id: last_id,
+    }
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("leaderboard")
+        .description("See who is the richest. Ranks by net worth if no currency is given.")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency to rank members by."
+            ).required(false)
+        )
+}
@@ -1,13 +1,31 @@
+pub mod activity;
+pub mod backup;
 pub mod balance;
+pub mod bank;
 pub mod buy;
+pub mod claim;
+pub mod config_activity;
+pub mod config_claim;
 pub mod config_currency;
 pub mod config_drop_table;
+pub mod config_gambling;
 pub mod config_item;
+pub mod config_rob;
+pub mod config_role_income;
 pub mod config_store;
 pub mod currency;
+pub mod economy;
+pub mod gamble;
+pub mod gift;
 pub mod give;
+pub mod history;
+pub mod import;
 pub mod inv;
+pub mod leaderboard;
+pub mod pay;
 pub mod ping;
+pub mod rob;
 pub mod sell;
 pub mod take;
+pub mod trade;
 pub mod use_item;
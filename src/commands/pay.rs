@@ -0,0 +1,119 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption, CreateMessage, EditInteractionResponse },
+    http::{ CacheHttp, Http },
+};
+use tracing::warn;
+
+use crate::{
+    db::models::Currency,
+    event_handler::command_handler::CommandOptions,
+    mechanics::pay::pay,
+};
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: impl AsRef<Http> + Send + Sync + CacheHttp
+) -> Result<()> {
+    let receiver = options
+        .get_user_value(MEMBER_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No member was found"))?
+        .to_user(&http).await?;
+    let currency = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No currency was found"))?;
+    let amount = options
+        .get_int_or_number_value(AMOUNT_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No amount was found"))?
+        .cast_to_f64();
+    let note = options.get_string_value(NOTE_OPTION_NAME).transpose()?;
+    let sender = command.member
+        .as_ref()
+        .ok_or_else(|| anyhow!("Command can't be performed in DMs"))?;
+
+    if sender.guild_id.member(&http, receiver.id).await.is_err() {
+        return Err(anyhow!("Member {} is not in this guild.", receiver.name));
+    }
+
+    let currency = Currency::try_from_name(sender.guild_id.into(), currency).await?.ok_or_else(||
+        anyhow!("Currency not found")
+    )?;
+    let currency = currency.read().await;
+    let currency = currency
+        .as_ref()
+        .ok_or_else(|| anyhow!("Currency is being used in a breaking operation"))?;
+
+    let paid = pay(currency, amount, sender, &receiver).await?;
+
+    let dm = format!(
+        "{} paid you {} {}{}.{}",
+        sender.user.name,
+        paid,
+        currency.symbol(),
+        currency.curr_name().as_str(),
+        note.map(|n| format!("\nNote: {n}")).unwrap_or_default()
+    );
+    // The receiver may have their DMs closed, which is not a reason to fail the payment.
+    if let Err(e) = receiver.direct_message(&http, CreateMessage::new().content(dm)).await {
+        warn!("Could not notify {} of payment: {}", receiver.id, e);
+    }
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().content(
+            format!(
+                "You paid {} {} {}{}.",
+                receiver.name,
+                paid,
+                currency.symbol(),
+                currency.curr_name().as_str()
+            )
+        )
+    ).await?;
+
+    Ok(())
+}
+
+const MEMBER_OPTION_NAME: &str = "member";
+const CURRENCY_OPTION_NAME: &str = "currency";
+const AMOUNT_OPTION_NAME: &str = "amount";
+const NOTE_OPTION_NAME: &str = "note";
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("pay")
+        .description("Pay another member some of your currency.")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                MEMBER_OPTION_NAME,
+                "The member to pay."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency to pay in."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                AMOUNT_OPTION_NAME,
+                "The amount to pay."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                NOTE_OPTION_NAME,
+                "A note to send to the member along with the payment."
+            ).required(false)
+        )
+}
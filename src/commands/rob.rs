@@ -0,0 +1,100 @@
+use anyhow::{ anyhow, Result };
+use serenity::{
+    all::{ CommandInteraction, CommandOptionType },
+    builder::{ CreateCommand, CreateCommandOption, CreateEmbed, EditInteractionResponse },
+    client::Context,
+};
+
+use crate::{
+    event_handler::command_handler::CommandOptions,
+    mechanics::rob::{ rob, RobOutcome },
+    ACCENT_COLOUR,
+};
+
+/// Runs the `/rob` command.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - An option is missing, or the member is not in the guild.
+/// - Any error from robbing.
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+    let victim = options
+        .get_user_value(MEMBER_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No member was found"))?
+        .to_user(http).await?;
+    let curr_name = options
+        .get_string_value(CURRENCY_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No currency was found"))?;
+
+    if guild_id.member(http, victim.id).await.is_err() {
+        return Err(anyhow!("Member {} is not in this guild.", victim.name));
+    }
+
+    let embed = match rob(guild_id, &command.user, &victim, &curr_name).await? {
+        RobOutcome::Stole(amount) =>
+            CreateEmbed::default()
+                .title("Robbed")
+                .description(format!("You stole **{amount}** {curr_name} from {}.", victim.name)),
+        RobOutcome::Fined(amount) =>
+            CreateEmbed::default()
+                .title("Caught")
+                .description(
+                    format!(
+                        "You were caught trying to rob {} and fined **{amount}** {curr_name}.",
+                        victim.name
+                    )
+                ),
+        RobOutcome::RobberOnCooldown(available) =>
+            CreateEmbed::default()
+                .title("Not yet")
+                .description(
+                    format!("You can rob again <t:{}:R>.", available.timestamp())
+                ),
+        RobOutcome::VictimProtected(available) =>
+            CreateEmbed::default()
+                .title("Not yet")
+                .description(
+                    format!(
+                        "{} was robbed not long ago and can be robbed again <t:{}:R>.",
+                        victim.name,
+                        available.timestamp()
+                    )
+                ),
+    };
+
+    command.edit_response(
+        http,
+        EditInteractionResponse::new().embed(embed.colour(ACCENT_COLOUR))
+    ).await?;
+    Ok(())
+}
+
+const MEMBER_OPTION_NAME: &str = "member";
+const CURRENCY_OPTION_NAME: &str = "currency";
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("rob")
+        .description("Try to steal some of another member's currency. You get fined if you fail.")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                MEMBER_OPTION_NAME,
+                "The member to rob."
+            ).required(true)
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                CURRENCY_OPTION_NAME,
+                "The currency to steal."
+            ).required(true)
+        )
+}
@@ -7,11 +7,13 @@ use serenity::{
 use tokio::join;
 
 use crate::{
-    db::{ models::{ Balances, Currency, Inventory, Item }, CLIENT },
+    db::{
+        models::{ Balances, Currency, Inventory, Item, TransactionKind, TransactionReason },
+        CLIENT,
+    },
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
 };
 
-#[allow(clippy::cast_precision_loss)]
 pub async fn run(
     options: CommandOptions,
     command: &CommandInteraction,
@@ -66,11 +68,15 @@ pub async fn run(
 
     session.start_transaction(None).await?;
 
-    let income = item_.value() * (amount as f64);
+    let income = item_
+        .value()
+        .checked_mul(amount)
+        .ok_or_else(|| anyhow!("That is too many to sell at once."))?;
+    let reason = TransactionReason::new(TransactionKind::Sell, user_id.into());
     balance_
         .ensure_has_currency(std::borrow::Cow::Borrowed(item_.currency_value())).await?
-        .add_amount(income, Some(&mut session)).await?;
-    entry.sub_amount(amount, Some(&mut session)).await?;
+        .add_amount(income, reason, Some(&mut session)).await?;
+    entry.sub_amount(amount, reason, Some(&mut session)).await?;
 
     session.commit_transaction().await?;
 
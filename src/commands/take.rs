@@ -7,9 +7,9 @@ use serenity::{
 };
 
 use crate::{
-    db::models::{ Balances, Currency },
+    db::models::{ Balances, Currency, TransactionKind, TransactionReason },
     event_handler::command_handler::CommandOptions,
-    util::currency::truncate_2dp,
+    util::money::Money,
 };
 
 pub async fn run(
@@ -17,12 +17,10 @@ pub async fn run(
     command: &CommandInteraction,
     http: impl AsRef<Http> + Clone + CacheHttp
 ) -> Result<()> {
-    let amount = truncate_2dp(
-        options
-            .get_int_or_number_value("amount")
-            .ok_or_else(|| anyhow!("Failed to find amount"))??
-            .cast_to_f64()
-    );
+    let amount = options
+        .get_int_or_number_value("amount")
+        .ok_or_else(|| anyhow!("Failed to find amount"))??
+        .cast_to_f64();
     let currency = options
         .get_string_value("currency")
         .ok_or_else(|| anyhow!("Failed to find currency"))??;
@@ -32,9 +30,8 @@ pub async fn run(
         .to_user(&http).await?;
     let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command may not be performed in DMs"))?;
 
-    if Currency::try_from_name(guild_id.into(), currency.clone()).await?.is_none() {
-        return Err(anyhow!("Currency {} does not exist.", currency));
-    }
+    let precision = Currency::precision_from_name(guild_id.into(), currency.clone()).await?;
+    let amount = Money::from_f64(amount, precision)?;
 
     if guild_id.member(&http, member.id).await.is_err() {
         return Err(anyhow!("Member {} is not in guild {}", member, guild_id));
@@ -70,7 +67,11 @@ pub async fn run(
         );
     };
 
-    balance.sub_amount_unchecked(amount, None).await?;
+    balance.sub_amount_unchecked(
+        amount,
+        TransactionReason::new(TransactionKind::Take, command.user.id.into()),
+        None
+    ).await?;
 
     drop(balances);
 
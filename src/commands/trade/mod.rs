@@ -0,0 +1,30 @@
+use anyhow::{ anyhow, bail, Result };
+use serenity::{ all::CommandInteraction, builder::CreateCommand, client::Context };
+
+use crate::event_handler::command_handler::CommandOptions;
+
+pub mod offer;
+
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let (cmd_name, cmd_options) = options
+        .get_subcommand_args_and_name()
+        .ok_or_else(|| anyhow!("Provided argument does not contain a subcommand."))?;
+    match cmd_name.as_str() {
+        // Boxed as well, since the offer holds its message and both collectors for as long as it
+        // is open and would otherwise still make `handle_command` too big.
+        "offer" => Box::pin(offer::run(cmd_options, command, http)).await?,
+        &_ => bail!("Unknown trade subcommand."),
+    }
+    Ok(())
+}
+
+pub fn command() -> CreateCommand {
+    CreateCommand::new("trade")
+        .description("Swap items and currency with another member.")
+        .dm_permission(false)
+        .add_option(offer::option())
+}
@@ -0,0 +1,347 @@
+use std::fmt::Write;
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::Utc;
+use futures::{ stream, StreamExt };
+use serenity::{
+    all::{
+        ActionRowComponent,
+        ButtonStyle,
+        CommandInteraction,
+        CommandOptionType,
+        ComponentInteraction,
+        InputTextStyle,
+        ModalInteraction,
+    },
+    builder::{
+        CreateActionRow,
+        CreateButton,
+        CreateCommandOption,
+        CreateEmbed,
+        CreateInputText,
+        CreateInteractionResponse,
+        CreateInteractionResponseMessage,
+        CreateMessage,
+        CreateModal,
+        EditInteractionResponse,
+        EditMessage,
+    },
+    client::Context,
+    model::user::User,
+};
+
+use crate::{
+    event_handler::command_handler::CommandOptions,
+    mechanics::trade::{ Trade, TradeSide },
+    ACCENT_COLOUR,
+};
+
+/// Runs the `/trade offer` command.
+///
+/// The offer is sent as its own message, so that the partner can see it and use its buttons too.
+/// It stays open until both members confirm, one of them cancels, or it expires.
+///
+/// # Errors
+/// - The command is used in DMs.
+/// - An option is missing, or the member is not in the guild.
+/// - The member cannot be traded with.
+/// - Any error from responding to the buttons.
+#[allow(clippy::too_many_lines)]
+pub async fn run(
+    options: CommandOptions,
+    command: &CommandInteraction,
+    http: &Context
+) -> Result<()> {
+    let guild_id = command.guild_id.ok_or_else(|| anyhow!("Command cannot be done in DMs."))?;
+    let partner = options
+        .get_user_value(MEMBER_OPTION_NAME)
+        .transpose()?
+        .ok_or_else(|| anyhow!("No member was found"))?
+        .to_user(http).await?;
+    if guild_id.member(http, partner.id).await.is_err() {
+        bail!("Member {} is not in this guild.", partner.name);
+    }
+
+    let mut trade = Trade::new(guild_id, &command.user, &partner)?;
+    let users = [&command.user, &partner];
+    let controls = trade_controls();
+
+    let mut message = command.channel_id.send_message(
+        http,
+        CreateMessage::new()
+            .content(format!("<@{}>, {} wants to trade with you.", partner.id, command.user.name))
+            .embed(make_embed(&trade, users))
+            .components(vec![controls.row.clone()])
+    ).await?;
+    command.edit_response(http, EditInteractionResponse::new().content("Trade offered.")).await?;
+
+    let remaining = (trade.expires_at() - Utc::now()).to_std().unwrap_or_default();
+    let buttons = message
+        .await_component_interactions(http)
+        .timeout(remaining)
+        .stream()
+        .map(TradeEvent::Button);
+    let modals = message
+        .await_modal_interactions(http)
+        .timeout(remaining)
+        .stream()
+        .map(TradeEvent::Modal);
+    let mut events = Box::pin(stream::select(buttons, modals));
+
+    while let Some(event) = events.next().await {
+        match event {
+            TradeEvent::Button(i) => {
+                if !trade.is_party(i.user.id) {
+                    i.create_response(http, error_response("This is not your trade.")).await?;
+                    continue;
+                }
+                let id: &str = &i.data.custom_id;
+                match id {
+                    id if controls.add_item_id == id => {
+                        let modal = entry_modal(&controls.item_modal_id, "Add an item", "Item");
+                        i.create_response(http, CreateInteractionResponse::Modal(modal)).await?;
+                    }
+                    id if controls.add_currency_id == id => {
+                        let modal = entry_modal(
+                            &controls.currency_modal_id,
+                            "Add currency",
+                            "Currency"
+                        );
+                        i.create_response(http, CreateInteractionResponse::Modal(modal)).await?;
+                    }
+                    id if controls.confirm_id == id => {
+                        match trade.confirm(i.user.id) {
+                            Ok(false) => {
+                                update_embed(http, &i, &trade, users).await?;
+                            }
+                            Ok(true) => {
+                                // Boxed, since swapping takes both inventories and balances and
+                                // makes for a much bigger future than the rest of the trade.
+                                let content = match Box::pin(trade.execute(http)).await {
+                                    Ok(()) => "The trade is done.".to_owned(),
+                                    Err(e) => format!("The trade failed: {e}"),
+                                };
+                                finish(http, &i, &content).await?;
+                                return Ok(());
+                            }
+                            Err(e) => {
+                                i.create_response(http, error_response(e.to_string())).await?;
+                            }
+                        }
+                    }
+                    id if controls.cancel_id == id => {
+                        finish(http, &i, &format!("{} cancelled the trade.", i.user.name)).await?;
+                        return Ok(());
+                    }
+                    _ => {
+                        i.create_response(http, error_response("Invalid button id.")).await?;
+                    }
+                }
+            }
+            TradeEvent::Modal(m) => {
+                let res = if controls.item_modal_id == m.data.custom_id {
+                    offer_item(&mut trade, &m).await
+                } else if controls.currency_modal_id == m.data.custom_id {
+                    offer_currency(&mut trade, &m).await
+                } else {
+                    Err(anyhow!("Invalid form id."))
+                };
+                if let Err(e) = res {
+                    m.create_response(http, error_response(e.to_string())).await?;
+                    continue;
+                }
+                m.create_response(
+                    http,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new().embed(make_embed(&trade, users))
+                    )
+                ).await?;
+            }
+        }
+    }
+
+    message.edit(
+        http,
+        EditMessage::new().content("This trade expired.").components(vec![])
+    ).await?;
+
+    Ok(())
+}
+
+enum TradeEvent {
+    Button(ComponentInteraction),
+    Modal(ModalInteraction),
+}
+
+async fn offer_item(trade: &mut Trade, modal: &ModalInteraction) -> Result<()> {
+    let item_name = input_value(modal, NAME_INPUT_ID)?;
+    let amount = input_value(modal, AMOUNT_INPUT_ID)?
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| anyhow!("The amount of an item must be a whole number."))?;
+    trade.offer_item(modal.user.id, item_name.trim(), amount).await
+}
+
+async fn offer_currency(trade: &mut Trade, modal: &ModalInteraction) -> Result<()> {
+    let curr_name = input_value(modal, NAME_INPUT_ID)?;
+    let amount = input_value(modal, AMOUNT_INPUT_ID)?
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| anyhow!("The amount must be a number."))?;
+    trade.offer_currency(modal.user.id, curr_name.trim(), amount).await?;
+    Ok(())
+}
+
+fn input_value(modal: &ModalInteraction, custom_id: &str) -> Result<String> {
+    modal.data.components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(text) if text.custom_id == custom_id => {
+                text.value.clone()
+            }
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("The form is missing a field."))
+}
+
+/// Tells only the member who pressed a button or sent a form what went wrong, leaving the trade
+/// as it is.
+fn error_response(content: impl Into<String>) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content(content).ephemeral(true)
+    )
+}
+
+async fn update_embed(
+    http: &Context,
+    interaction: &ComponentInteraction,
+    trade: &Trade,
+    users: [&User; 2]
+) -> Result<()> {
+    interaction.create_response(
+        http,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().embed(make_embed(trade, users))
+        )
+    ).await?;
+    Ok(())
+}
+
+/// Closes the trade, leaving the embed as it was when it ended.
+async fn finish(http: &Context, interaction: &ComponentInteraction, content: &str) -> Result<()> {
+    interaction.create_response(
+        http,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().content(content).components(vec![])
+        )
+    ).await?;
+    Ok(())
+}
+
+fn make_embed(trade: &Trade, users: [&User; 2]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .title("Trade")
+        .description(
+            format!(
+                "Add what you want to give, then confirm. Changing anything takes back both \
+                confirmations. Expires <t:{}:R>.",
+                trade.expires_at().timestamp()
+            )
+        )
+        .colour(ACCENT_COLOUR);
+    for (side, user) in trade.sides().iter().zip(users) {
+        let confirmed = if side.confirmed { " ✅" } else { "" };
+        embed = embed.field(format!("{}{confirmed}", user.name), describe_side(side), true);
+    }
+    embed
+}
+
+fn describe_side(side: &TradeSide) -> String {
+    if side.is_empty() {
+        return "Nothing yet.".to_owned();
+    }
+    let mut description = String::new();
+    for (item_name, amount) in &side.items {
+        let _ = writeln!(description, "**{item_name}** *x{amount}*");
+    }
+    for (curr_name, amount) in &side.currencies {
+        let _ = writeln!(description, "**{amount}** {curr_name}");
+    }
+    description
+}
+
+fn entry_modal(custom_id: &str, title: &str, name_label: &str) -> CreateModal {
+    CreateModal::new(custom_id, title).components(
+        vec![
+            CreateActionRow::InputText(
+                CreateInputText::new(InputTextStyle::Short, name_label, NAME_INPUT_ID)
+            ),
+            CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Short,
+                    "Amount (0 takes it back out)",
+                    AMOUNT_INPUT_ID
+                )
+            )
+        ]
+    )
+}
+
+const NAME_INPUT_ID: &str = "name";
+const AMOUNT_INPUT_ID: &str = "amount";
+
+struct TradeControls {
+    row: CreateActionRow,
+    add_item_id: String,
+    add_currency_id: String,
+    confirm_id: String,
+    cancel_id: String,
+    item_modal_id: String,
+    currency_modal_id: String,
+}
+
+fn trade_controls() -> TradeControls {
+    let now = Utc::now();
+    let add_item_id = format!("{now}add_item");
+    let add_currency_id = format!("{now}add_currency");
+    let confirm_id = format!("{now}confirm");
+    let cancel_id = format!("{now}cancel");
+    let row = CreateActionRow::Buttons(
+        vec![
+            CreateButton::new(add_item_id.clone())
+                .label("Add item")
+                .style(ButtonStyle::Primary),
+            CreateButton::new(add_currency_id.clone())
+                .label("Add currency")
+                .style(ButtonStyle::Primary),
+            CreateButton::new(confirm_id.clone()).label("Confirm").style(ButtonStyle::Success),
+            CreateButton::new(cancel_id.clone()).label("Cancel").style(ButtonStyle::Danger)
+        ]
+    );
+    TradeControls {
+        row,
+        add_item_id,
+        add_currency_id,
+        confirm_id,
+        cancel_id,
+        item_modal_id: format!("{now}item_modal"),
+        currency_modal_id: format!("{now}currency_modal"),
+    }
+}
+
+const MEMBER_OPTION_NAME: &str = "member";
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::SubCommand,
+        "offer",
+        "Offer a trade to another member."
+    ).add_sub_option(
+        CreateCommandOption::new(
+            CommandOptionType::User,
+            MEMBER_OPTION_NAME,
+            "The member to trade with."
+        ).required(true)
+    )
+}
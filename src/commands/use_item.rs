@@ -7,7 +7,7 @@ use serenity::{
 };
 
 use crate::{
-    db::models::{ Inventory, Item },
+    db::models::{ Inventory, Item, TransactionKind, TransactionReason },
     event_handler::command_handler::{ CommandOptions, IntOrNumber },
     mechanics::item_action_handler::use_item,
 };
@@ -52,7 +52,12 @@ pub async fn run(
     let use_result = use_item(command.user.id, user_inventory_, item, amount, 0, http).await?;
 
     if use_result.success {
-        user_inventory_.take_item(&item_name, amount, None).await?;
+        user_inventory_.take_item(
+            &item_name,
+            amount,
+            TransactionReason::new(TransactionKind::ItemUse, command.user.id.into()),
+            None
+        ).await?;
         response_content.push_str(
             use_result.message.unwrap_or_else(|| Cow::Owned(format!("Used {item_name}"))).as_ref()
         );
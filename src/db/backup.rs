@@ -0,0 +1,373 @@
+//! Backing up the economy of a guild into one portable document and restoring it again.
+//!
+//! A `GuildBackup` holds the currencies, items, drop tables, store entries, balances and
+//! inventories of a guild as the same models they are stored as, so it serializes to JSON that can
+//! be kept before risky config changes or taken to another server. Restoring it rewrites every
+//! guild id to the guild it is restored into.
+//!
+//! The format is versioned by `BACKUP_VERSION`. Backups of another version are refused rather
+//! than half understood.
+
+use std::collections::HashSet;
+
+use anyhow::{ bail, Result };
+use chrono::{ DateTime, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, from_document, to_document, Document },
+    options::{ ReadConcern, TransactionOptions },
+    ClientSession,
+    Collection,
+};
+use serde::{ de::DeserializeOwned, Deserialize, Serialize };
+
+use crate::{
+    db::{
+        models::{
+            drop_table::DropTablePart,
+            store::Store,
+            Balance,
+            Balances,
+            Currency,
+            DropTable,
+            Inventory,
+            InventoryEntry,
+            Item,
+            StoreEntry,
+        },
+        uniques::DbGuildId,
+        CLIENT,
+    },
+    mechanics::{ interest, pending_earnings },
+};
+
+/// The version of the backup format that this code writes and reads.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// The whole economy of a guild.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GuildBackup {
+    pub version: u32,
+    /// The guild the backup was taken of.
+    pub guild_id: DbGuildId,
+    pub created_at: DateTime<Utc>,
+    pub currencies: Vec<Currency>,
+    pub items: Vec<Item>,
+    pub drop_tables: Vec<DropTablePart>,
+    pub store_entries: Vec<StoreEntry>,
+    pub balances: Vec<Balance>,
+    pub inventories: Vec<InventoryEntry>,
+}
+
+/// What to do with what a guild already has when restoring a backup into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Keep everything the guild has and only add what it does not have yet. Something counts as
+    /// already there if it has the same name, or the same member and name for balances and
+    /// inventories.
+    Keep,
+    /// Delete everything the guild has first, so it ends up exactly like the backup.
+    Replace,
+}
+
+impl std::str::FromStr for RestoreMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "keep" => Ok(Self::Keep),
+            "replace" => Ok(Self::Replace),
+            _ => bail!("Unknown restore mode {s}, use keep or replace."),
+        }
+    }
+}
+
+/// How many documents of a collection a restore deleted, added and skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoredPart {
+    pub collection: &'static str,
+    pub deleted: u64,
+    pub inserted: usize,
+    pub skipped: usize,
+}
+
+/// A collection that is part of a backup.
+struct Part {
+    collection: &'static str,
+    /// The fields that, with the guild id, tell apart the documents of the collection.
+    key: &'static [&'static str],
+}
+
+const CURRENCIES: Part = Part { collection: "currencies", key: &["CurrName"] };
+const ITEMS: Part = Part { collection: "items", key: &["ItemName"] };
+// A drop table is kept or restored as a whole, not part by part.
+const DROP_TABLES: Part = Part { collection: "dropTables", key: &["DropTableName"] };
+const STORE_ENTRIES: Part = Part { collection: "storeEntries", key: &["ItemName", "CurrName"] };
+const BALANCES: Part = Part { collection: "balances", key: &["UserId", "CurrName"] };
+const INVENTORIES: Part = Part { collection: "inventories", key: &["UserId", "ItemName"] };
+
+impl GuildBackup {
+    /// Takes a backup of a guild. Everything is read in one snapshot, so the backup is consistent
+    /// even if the economy is being used at the same time.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn export(guild_id: DbGuildId) -> Result<Self> {
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        let options = TransactionOptions::builder().read_concern(ReadConcern::snapshot()).build();
+        session.start_transaction(options).await?;
+        let backup = Self {
+            version: BACKUP_VERSION,
+            guild_id,
+            created_at: Utc::now(),
+            currencies: find_all(&CURRENCIES, guild_id, &mut session).await?,
+            items: find_all(&ITEMS, guild_id, &mut session).await?,
+            drop_tables: find_all(&DROP_TABLES, guild_id, &mut session).await?,
+            store_entries: find_all(&STORE_ENTRIES, guild_id, &mut session).await?,
+            balances: find_all(&BALANCES, guild_id, &mut session).await?,
+            inventories: find_all(&INVENTORIES, guild_id, &mut session).await?,
+        };
+        session.commit_transaction().await?;
+        Ok(backup)
+    }
+
+    /// Restores the backup into a guild, which does not have to be the guild it was taken of.
+    /// Either all of it is restored or, if anything goes wrong, none of it.
+    ///
+    /// Currencies get their interest jobs scheduled again. When keeping what the guild has and it
+    /// already has a base currency, the restored currencies are not made the base.
+    ///
+    /// Role and channel ids in the currencies are restored as they are, so they only mean
+    /// something in the guild the backup was taken of.
+    ///
+    /// # Errors
+    /// - The backup is of another version.
+    /// - Any `MongoDB` error occurs.
+    pub async fn restore(
+        &self,
+        guild_id: DbGuildId,
+        mode: RestoreMode
+    ) -> Result<Vec<RestoredPart>> {
+        if self.version != BACKUP_VERSION {
+            bail!(
+                "Backup is of version {}, but only version {BACKUP_VERSION} can be restored.",
+                self.version
+            );
+        }
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let restored = match self.restore_in_session(guild_id, mode, &mut session).await {
+            Ok(restored) => restored,
+            Err(e) => {
+                session.abort_transaction().await?;
+                return Err(e);
+            }
+        };
+        session.commit_transaction().await?;
+
+        invalidate_guild_caches(guild_id).await;
+        if mode == RestoreMode::Replace {
+            pending_earnings::forget_guild(guild_id);
+        }
+        Ok(restored)
+    }
+
+    async fn restore_in_session(
+        &self,
+        guild_id: DbGuildId,
+        mode: RestoreMode,
+        session: &mut ClientSession
+    ) -> Result<Vec<RestoredPart>> {
+        if mode == RestoreMode::Replace {
+            let existing: Vec<Currency> = find_all(&CURRENCIES, guild_id, session).await?;
+            for currency in existing {
+                interest::cancel_job(guild_id, currency.curr_name().as_str(), Some(session)).await?;
+            }
+        }
+
+        let mut currencies = retarget(&self.currencies, guild_id)?;
+        if mode == RestoreMode::Keep && has_base_currency(guild_id, session).await? {
+            for currency in &mut currencies {
+                currency.insert("Base", false);
+            }
+        }
+
+        let mut restored = Vec::with_capacity(6);
+        let (part, inserted) = restore_part(
+            &CURRENCIES,
+            guild_id,
+            currencies,
+            mode,
+            session
+        ).await?;
+        restored.push(part);
+        for currency in inserted {
+            let currency: Currency = from_document(currency)?;
+            interest::sync_job(&currency, Some(session)).await?;
+        }
+        let rest = [
+            (&ITEMS, retarget(&self.items, guild_id)?),
+            (&DROP_TABLES, retarget(&self.drop_tables, guild_id)?),
+            (&STORE_ENTRIES, retarget(&self.store_entries, guild_id)?),
+            (&BALANCES, retarget(&self.balances, guild_id)?),
+            (&INVENTORIES, retarget(&self.inventories, guild_id)?),
+        ];
+        for (part, documents) in rest {
+            restored.push(restore_part(part, guild_id, documents, mode, session).await?.0);
+        }
+        Ok(restored)
+    }
+}
+
+async fn find_all<T: DeserializeOwned + Unpin + Send + Sync>(
+    part: &Part,
+    guild_id: DbGuildId,
+    session: &mut ClientSession
+) -> Result<Vec<T>> {
+    let db = CLIENT.get().await.database("conebot");
+    let coll: Collection<T> = db.collection(part.collection);
+    let filterdoc = doc! { "GuildId": guild_id.as_i64() };
+    Ok(coll.find_with_session(filterdoc, None, session).await?.stream(session).try_collect().await?)
+}
+
+/// Turns models into documents that belong to the given guild.
+fn retarget<T: Serialize>(values: &[T], guild_id: DbGuildId) -> Result<Vec<Document>> {
+    values
+        .iter()
+        .map(|value| {
+            let mut document = to_document(value)?;
+            document.insert("GuildId", guild_id.as_i64());
+            Ok(document)
+        })
+        .collect()
+}
+
+async fn has_base_currency(guild_id: DbGuildId, session: &mut ClientSession) -> Result<bool> {
+    let db = CLIENT.get().await.database("conebot");
+    let coll: Collection<Document> = db.collection(CURRENCIES.collection);
+    let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Base": true };
+    Ok(coll.find_one_with_session(filterdoc, None, session).await?.is_some())
+}
+
+/// Restores the documents of one collection. Returns what was done along with the documents
+/// that were inserted.
+async fn restore_part(
+    part: &Part,
+    guild_id: DbGuildId,
+    documents: Vec<Document>,
+    mode: RestoreMode,
+    session: &mut ClientSession
+) -> Result<(RestoredPart, Vec<Document>)> {
+    let db = CLIENT.get().await.database("conebot");
+    let coll: Collection<Document> = db.collection(part.collection);
+    let filterdoc = doc! { "GuildId": guild_id.as_i64() };
+    let mut restored = RestoredPart { collection: part.collection, ..Default::default() };
+
+    let documents = match mode {
+        RestoreMode::Replace => {
+            restored.deleted = coll
+                .delete_many_with_session(filterdoc, None, session).await?.deleted_count;
+            documents
+        }
+        RestoreMode::Keep => {
+            let existing = coll
+                .find_with_session(filterdoc, None, session).await?
+                .stream(session)
+                .map_ok(|document| key_of(part, &document))
+                .try_collect::<HashSet<_>>().await?;
+            let total = documents.len();
+            let documents = documents
+                .into_iter()
+                .filter(|document| !existing.contains(&key_of(part, document)))
+                .collect::<Vec<_>>();
+            restored.skipped = total - documents.len();
+            documents
+        }
+    };
+    if !documents.is_empty() {
+        coll.insert_many_with_session(&documents, None, session).await?;
+    }
+    restored.inserted = documents.len();
+    Ok((restored, documents))
+}
+
+/// The values of the key fields of a document, as text so they can be compared and hashed.
+fn key_of(part: &Part, document: &Document) -> Vec<String> {
+    part.key
+        .iter()
+        .map(|field| document.get(field).map(ToString::to_string).unwrap_or_default())
+        .collect()
+}
+
+/// Drops everything of a guild that is cached, so that it is read again after a restore.
+async fn invalidate_guild_caches(guild_id: DbGuildId) {
+    Currency::invalidate_guild_cache(guild_id).await;
+    Item::invalidate_guild_cache(guild_id).await;
+    DropTable::invalidate_guild_cache(guild_id).await;
+    Store::invalidate_guild_cache(guild_id).await;
+    Balances::invalidate_guild_cache(guild_id).await;
+    Inventory::invalidate_guild_cache(guild_id).await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_key_of() {
+        let a = doc! { "GuildId": 1_i64, "UserId": 2_i64, "CurrName": "coins", "Amount": 5_i64 };
+        let b = doc! { "GuildId": 3_i64, "UserId": 2_i64, "CurrName": "coins", "Amount": 7_i64 };
+        let c = doc! { "GuildId": 1_i64, "UserId": 2_i64, "CurrName": "gems", "Amount": 5_i64 };
+        assert_eq!(key_of(&BALANCES, &a), key_of(&BALANCES, &b));
+        assert_ne!(key_of(&BALANCES, &a), key_of(&BALANCES, &c));
+    }
+
+    #[test]
+    fn test_retarget() {
+        let entries = [doc! { "GuildId": 1_i64, "ItemName": "apple" }];
+        let retargeted = retarget(&entries, DbGuildId::from(2_u64)).unwrap();
+        assert_eq!(retargeted, vec![doc! { "GuildId": 2_i64, "ItemName": "apple" }]);
+    }
+
+    #[tokio::test]
+    async fn test_export_restore() {
+        crate::init_env().await;
+        let from = DbGuildId::from(123_456_789_u64);
+        let to = DbGuildId::from(192_837_465_u64);
+        let backup = GuildBackup::export(from).await.unwrap();
+        let json = serde_json::to_string(&backup).unwrap();
+        let backup: GuildBackup = serde_json::from_str(&json).unwrap();
+
+        let restored = backup.restore(to, RestoreMode::Replace).await.unwrap();
+        let copy = GuildBackup::export(to).await.unwrap();
+        assert_eq!(copy.currencies.len(), backup.currencies.len());
+        assert_eq!(copy.balances.len(), backup.balances.len());
+        assert_eq!(restored.iter().map(|p| p.inserted).sum::<usize>(), total_len(&backup));
+
+        // Nothing is new the second time around.
+        let restored = backup.restore(to, RestoreMode::Keep).await.unwrap();
+        assert!(restored.iter().all(|p| p.inserted == 0));
+
+        let empty = GuildBackup {
+            currencies: vec![],
+            items: vec![],
+            drop_tables: vec![],
+            store_entries: vec![],
+            balances: vec![],
+            inventories: vec![],
+            ..backup
+        };
+        empty.restore(to, RestoreMode::Replace).await.unwrap();
+        assert_eq!(total_len(&GuildBackup::export(to).await.unwrap()), 0);
+    }
+
+    fn total_len(backup: &GuildBackup) -> usize {
+        backup.currencies.len() +
+            backup.items.len() +
+            backup.drop_tables.len() +
+            backup.store_entries.len() +
+            backup.balances.len() +
+            backup.inventories.len()
+    }
+}
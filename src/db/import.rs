@@ -0,0 +1,501 @@
+//! Importing balances and items from the CSV files that other economy bots export, so a guild
+//! moving over to this bot keeps what its members had.
+//!
+//! The usual export, like the one `UnbelievaBoat` makes, has a column with the user id followed by
+//! a column for each kind of balance, such as `cash`, `bank` and `total`. Staff pick which of
+//! those columns go into which currency or item with a `ColumnMapping`. Several columns can go
+//! into the same currency, in which case they are added together.
+//!
+//! An import is first read into a `BalanceImport`, which checks every row and has a report of
+//! what would happen, without writing anything. Committing it then adds all of the amounts in one
+//! transaction.
+
+use std::{ collections::{ HashMap, HashSet }, fmt };
+
+use anyhow::{ anyhow, bail, Result };
+
+use crate::{
+    db::{
+        models::{ Balances, Currency, Inventory, Item, ItemError, TransactionReason },
+        uniques::{ DbGuildId, DbUserId },
+        CLIENT,
+    },
+    util::{ csv, money::Money },
+};
+
+/// The column names that are taken to be the user id when no user column is given, compared
+/// after going through `normalize`.
+const USER_COLUMNS: [&str; 4] = ["userid", "id", "user", "memberid"];
+
+/// What a column of the file is imported as.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ImportTarget {
+    Currency(String),
+    Item(String),
+}
+
+impl fmt::Display for ImportTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Currency(name) => write!(f, "{name}"),
+            Self::Item(name) => write!(f, "item {name}"),
+        }
+    }
+}
+
+/// A column of the file and what it is imported as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub column: String,
+    pub target: ImportTarget,
+}
+
+impl ColumnMapping {
+    /// Parses mappings written like `cash=Coins, bank=Coins, gems=item:Gem`, where the part after
+    /// `item:` is the name of an item and anything else is the name of a currency.
+    ///
+    /// # Errors
+    /// - A mapping has no `=`, or nothing on either side of it.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>> {
+        let mappings = s
+            .split(',')
+            .filter(|m| !m.trim().is_empty())
+            .map(|m| {
+                let (column, target) = m
+                    .split_once('=')
+                    .ok_or_else(|| {
+                        anyhow!("Mapping {} should look like column=currency.", m.trim())
+                    })?;
+                let (column, target) = (column.trim(), target.trim());
+                let (name, is_item) = target
+                    .strip_prefix("item:")
+                    .map_or((target, false), |item| (item.trim(), true));
+                if column.is_empty() || name.is_empty() {
+                    bail!("Mapping {} is missing a column or what it goes into.", m.trim());
+                }
+                let target = if is_item {
+                    ImportTarget::Item(name.to_owned())
+                } else {
+                    ImportTarget::Currency(name.to_owned())
+                };
+                Ok(Self { column: column.to_owned(), target })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if mappings.is_empty() {
+            bail!("At least one column has to be mapped.");
+        }
+        Ok(mappings)
+    }
+}
+
+/// A row of the file that is not imported because something is wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// What an import does, or would do if it were committed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// How many rows the file has, not counting the header.
+    pub rows: usize,
+    /// How many members get something.
+    pub members: usize,
+    /// Users in the file that are not members of the guild. They are not imported.
+    pub unknown_users: Vec<DbUserId>,
+    pub errors: Vec<RowError>,
+    /// How much of each currency or item is imported in total, formatted for display.
+    pub totals: Vec<(ImportTarget, String)>,
+}
+
+/// The amounts of a currency or an item that each member gets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Amounts {
+    Currency(HashMap<DbUserId, Money>),
+    Item(HashMap<DbUserId, i64>),
+}
+
+/// An import that has been read and checked but not written yet.
+#[derive(Debug)]
+pub struct BalanceImport {
+    guild_id: DbGuildId,
+    amounts: Vec<(ImportTarget, Amounts)>,
+    report: ImportReport,
+}
+
+impl BalanceImport {
+    /// Reads the text of a CSV file, the first row of which has the column names, and works out
+    /// what each member would get. Rows with something wrong in them and users that are not
+    /// members are left out and show up in the report instead. Amounts of currencies are
+    /// truncated to their precision.
+    ///
+    /// # Errors
+    /// - The file is not valid CSV or is empty.
+    /// - The user column or a mapped column is not in the file.
+    /// - A currency or item that is mapped to does not exist, or the item is instant.
+    /// - Any `MongoDB` error occurs.
+    pub async fn prepare(
+        guild_id: DbGuildId,
+        text: &str,
+        user_column: Option<&str>,
+        mappings: &[ColumnMapping],
+        is_member: impl Fn(DbUserId) -> bool
+    ) -> Result<Self> {
+        let mut precisions = HashMap::new();
+        for mapping in mappings {
+            let precision = match &mapping.target {
+                ImportTarget::Currency(name) => {
+                    let currency = Currency::try_from_name(guild_id, name.clone()).await?;
+                    let currency = currency.ok_or_else(|| anyhow!("Currency {name} not found."))?;
+                    let precision = currency
+                        .read().await
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("Currency is being used in a breaking operation."))?
+                        .precision();
+                    Some(precision)
+                }
+                ImportTarget::Item(name) => {
+                    let item = match Item::try_from_name(guild_id, name.clone()).await {
+                        Ok(item) => item,
+                        Err(ItemError::ItemNotFound) => bail!("Item {name} not found."),
+                        Err(ItemError::Other(e)) => {
+                            return Err(e);
+                        }
+                    };
+                    let is_instant = item
+                        .read().await
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("Item is being used in a breaking operation."))?
+                        .is_instant();
+                    if is_instant {
+                        bail!(
+                            "Item {name} is used as soon as it is given, so it can't be imported."
+                        );
+                    }
+                    None
+                }
+            };
+            precisions.insert(mapping.target.clone(), precision);
+        }
+
+        let records = csv::parse(text)?;
+        let (amounts, report) = read(&records, user_column, mappings, &precisions, is_member)?;
+        Ok(Self { guild_id, amounts, report })
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn report(&self) -> &ImportReport {
+        &self.report
+    }
+
+    /// Adds everything to the balances and inventories of the members. Either all of it is added
+    /// or, if anything goes wrong, none of it.
+    ///
+    /// # Errors
+    /// - A resulting amount would overflow.
+    /// - Any `MongoDB` error occurs.
+    pub async fn commit(self, reason: TransactionReason) -> Result<ImportReport> {
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        for (target, amounts) in &self.amounts {
+            let res = match (target, amounts) {
+                (ImportTarget::Currency(name), Amounts::Currency(amounts)) => {
+                    let mut user_ids = amounts.keys().copied().collect::<Vec<_>>();
+                    user_ids.sort_unstable();
+                    Balances::bulk_add_amounts_in_session(
+                        self.guild_id,
+                        name,
+                        &user_ids,
+                        amounts,
+                        reason,
+                        &mut session
+                    ).await.map(|_| ())
+                }
+                (ImportTarget::Item(name), Amounts::Item(amounts)) => {
+                    Inventory::bulk_give_item_in_session(
+                        self.guild_id,
+                        name,
+                        amounts,
+                        reason,
+                        &mut session
+                    ).await
+                }
+                _ => unreachable!("Amounts are always made for the kind of their target."),
+            };
+            if let Err(e) = res {
+                session.abort_transaction().await?;
+                return Err(e);
+            }
+        }
+        session.commit_transaction().await?;
+
+        Balances::invalidate_guild_cache(self.guild_id).await;
+        Inventory::invalidate_guild_cache(self.guild_id).await;
+        Ok(self.report)
+    }
+}
+
+/// Lowercases a column name and strips everything that is not a letter or digit, so `User ID`,
+/// `user_ID` and `userId` are all the same column.
+fn normalize(column: &str) -> String {
+    column
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn find_column(header: &[String], name: &str) -> Result<usize> {
+    let normalized = normalize(name);
+    header
+        .iter()
+        .position(|c| normalize(c) == normalized)
+        .ok_or_else(|| {
+            anyhow!("Column {} is not in the file. Its columns are: {}.", name, header.join(", "))
+        })
+}
+
+/// Works out what each member gets from the records of a file. `precisions` has the precision of
+/// every currency that is mapped to, and `None` for items.
+fn read(
+    records: &[csv::Record],
+    user_column: Option<&str>,
+    mappings: &[ColumnMapping],
+    precisions: &HashMap<ImportTarget, Option<u8>>,
+    is_member: impl Fn(DbUserId) -> bool
+) -> Result<(Vec<(ImportTarget, Amounts)>, ImportReport)> {
+    let (header, rows) = records.split_first().ok_or_else(|| anyhow!("The file is empty."))?;
+    let header = &header.fields;
+    let user_index = match user_column {
+        Some(column) => find_column(header, column)?,
+        None =>
+            header
+                .iter()
+                .position(|c| USER_COLUMNS.contains(&normalize(c).as_str()))
+                .ok_or_else(|| anyhow!("Could not tell which column has the user ids."))?,
+    };
+
+    // The targets in the order they were first mapped, each with the columns that go into it.
+    let mut targets: Vec<(ImportTarget, Vec<usize>)> = Vec::new();
+    for mapping in mappings {
+        let index = find_column(header, &mapping.column)?;
+        match targets.iter_mut().find(|(t, _)| *t == mapping.target) {
+            Some((_, columns)) => columns.push(index),
+            None => targets.push((mapping.target.clone(), vec![index])),
+        }
+    }
+    let mut amounts = targets
+        .iter()
+        .map(|(target, _)| {
+            let amounts = if precisions.get(target).copied().flatten().is_some() {
+                Amounts::Currency(HashMap::new())
+            } else {
+                Amounts::Item(HashMap::new())
+            };
+            (target.clone(), amounts)
+        })
+        .collect::<Vec<_>>();
+
+    let mut report = ImportReport { rows: rows.len(), ..Default::default() };
+    let mut seen = HashSet::new();
+    for row in rows {
+        let res = read_row(row, user_index, &targets, precisions);
+        let (user_id, row_amounts) = match res {
+            Ok(read) => read,
+            Err(e) => {
+                report.errors.push(RowError { line: row.line, message: e.to_string() });
+                continue;
+            }
+        };
+        if !seen.insert(user_id) {
+            report.errors.push(RowError {
+                line: row.line,
+                message: format!("User {} is in the file more than once.", user_id.as_i64()),
+            });
+            continue;
+        }
+        if !is_member(user_id) {
+            report.unknown_users.push(user_id);
+            continue;
+        }
+        let mut gets_something = false;
+        for ((_, amounts), amount) in amounts.iter_mut().zip(row_amounts) {
+            match (amounts, amount) {
+                (Amounts::Currency(amounts), RowAmount::Currency(amount)) if !amount.is_zero() => {
+                    amounts.insert(user_id, amount);
+                    gets_something = true;
+                }
+                (Amounts::Item(amounts), RowAmount::Item(amount)) if amount != 0 => {
+                    amounts.insert(user_id, amount);
+                    gets_something = true;
+                }
+                _ => {}
+            }
+        }
+        if gets_something {
+            report.members += 1;
+        }
+    }
+
+    for (target, amounts) in &amounts {
+        let total = match amounts {
+            Amounts::Currency(amounts) =>
+                amounts
+                    .values()
+                    .try_fold(Money::ZERO, |total, amount| total.checked_add(*amount))
+                    .map_or_else(|| "too much to count".to_owned(), |t| t.to_string()),
+            Amounts::Item(amounts) =>
+                amounts
+                    .values()
+                    .try_fold(0_i64, |total, amount| total.checked_add(*amount))
+                    .map_or_else(|| "too many to count".to_owned(), |t| t.to_string()),
+        };
+        report.totals.push((target.clone(), total));
+    }
+    Ok((amounts, report))
+}
+
+/// What a row has of one currency or item.
+enum RowAmount {
+    Currency(Money),
+    Item(i64),
+}
+
+fn read_row(
+    row: &csv::Record,
+    user_index: usize,
+    targets: &[(ImportTarget, Vec<usize>)],
+    precisions: &HashMap<ImportTarget, Option<u8>>
+) -> Result<(DbUserId, Vec<RowAmount>)> {
+    let field = |index: usize| row.fields.get(index).map_or("", |f| f.trim());
+    let user = field(user_index);
+    let user_id = user
+        .parse::<u64>()
+        .ok()
+        .filter(|id| *id != 0)
+        .ok_or_else(|| anyhow!("{user} is not a user id."))?;
+
+    let mut row_amounts = Vec::with_capacity(targets.len());
+    for (target, columns) in targets {
+        let cells = columns
+            .iter()
+            .map(|c| field(*c))
+            .filter(|cell| !cell.is_empty());
+        let amount = if let Some(precision) = precisions.get(target).copied().flatten() {
+            let mut total = Money::ZERO;
+            for cell in cells {
+                let amount = cell.parse::<Money>()?.truncate(precision);
+                total = total
+                    .checked_add(amount)
+                    .ok_or_else(|| anyhow!("The amount of {target} is too large."))?;
+            }
+            if total.is_negative() {
+                bail!("The amount of {target} is negative.");
+            }
+            RowAmount::Currency(total)
+        } else {
+            let mut total = 0_i64;
+            for cell in cells {
+                let amount = cell
+                    .parse::<i64>()
+                    .map_err(|_| anyhow!("{cell} is not a whole number of {target}."))?;
+                total = total
+                    .checked_add(amount)
+                    .ok_or_else(|| anyhow!("The amount of {target} is too large."))?;
+            }
+            if total < 0 {
+                bail!("The amount of {target} is negative.");
+            }
+            RowAmount::Item(total)
+        };
+        row_amounts.push(amount);
+    }
+    Ok((DbUserId::from(user_id), row_amounts))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let mappings = ColumnMapping::parse_list("cash=Coins, bank = Coins,gems=item: Gem");
+        let mappings = mappings.unwrap();
+        assert_eq!(
+            mappings,
+            vec![
+                ColumnMapping {
+                    column: "cash".to_owned(),
+                    target: ImportTarget::Currency("Coins".to_owned()),
+                },
+                ColumnMapping {
+                    column: "bank".to_owned(),
+                    target: ImportTarget::Currency("Coins".to_owned()),
+                },
+                ColumnMapping {
+                    column: "gems".to_owned(),
+                    target: ImportTarget::Item("Gem".to_owned()),
+                }
+            ]
+        );
+        assert!(ColumnMapping::parse_list("cash").is_err());
+        assert!(ColumnMapping::parse_list("cash=").is_err());
+        assert!(ColumnMapping::parse_list(" , ").is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        let coins = ImportTarget::Currency("Coins".to_owned());
+        let gem = ImportTarget::Item("Gem".to_owned());
+        let precisions = HashMap::from([(coins.clone(), Some(2)), (gem.clone(), None)]);
+        let mappings = ColumnMapping::parse_list("cash=Coins, bank=Coins, gems=item:Gem").unwrap();
+        let records = csv
+            ::parse(
+                "rank,user_ID,cash,bank,total,gems\n\
+                1,10,100.555,50,150.555,2\n\
+                2,20,-10,30,20,\n\
+                3,30,abc,1,1,0\n\
+                4,10,1,1,2,0\n\
+                5,40,-100,5,-95,0\n\
+                6,50,1,1,2,1.5\n\
+                7,60,5,5,10,1\n\
+                8,not a user,1,1,2,1\n"
+            )
+            .unwrap();
+        let is_member = |id: DbUserId| id != DbUserId::from(60_u64);
+        let (amounts, report) = read(&records, None, &mappings, &precisions, is_member).unwrap();
+
+        let whole = |n| Money::from_whole(n).unwrap();
+        assert_eq!(
+            amounts,
+            vec![
+                (
+                    coins.clone(),
+                    Amounts::Currency(
+                        HashMap::from([
+                            (DbUserId::from(10_u64), Money::from_minor(1_505_500)),
+                            (DbUserId::from(20_u64), whole(20)),
+                        ])
+                    ),
+                ),
+                (gem.clone(), Amounts::Item(HashMap::from([(DbUserId::from(10_u64), 2)])))
+            ]
+        );
+        assert_eq!(report.rows, 8);
+        assert_eq!(report.members, 2);
+        assert_eq!(report.unknown_users, vec![DbUserId::from(60_u64)]);
+        assert_eq!(
+            report.errors
+                .iter()
+                .map(|e| e.line)
+                .collect::<Vec<_>>(),
+            vec![4, 5, 6, 7, 9]
+        );
+        assert_eq!(report.totals, vec![(coins, "170.55".to_owned()), (gem, "2".to_owned())]);
+
+        assert!(read(&records, Some("member"), &mappings, &precisions, |_| true).is_err());
+        let missing = ColumnMapping::parse_list("wallet=Coins").unwrap();
+        assert!(read(&records, None, &missing, &precisions, |_| true).is_err());
+    }
+}
@@ -1,11 +1,13 @@
+pub mod backup;
+pub mod import;
 pub mod models;
 
-use std::sync::Arc;
+use std::{ hash::Hash, sync::Arc };
 
 use async_once::AsyncOnce;
 use lazy_static::lazy_static;
 use lru::LruCache;
-use mongodb::Client;
+use mongodb::{ bson::{ doc, Document }, Client };
 
 // Do not, and I repeat, DO NOT try to replace the tokio mutexes with
 // a parking_lot or std mutex. It will not work. It will hang with mongodb operations
@@ -18,6 +20,49 @@ pub type TokioMutexCache<K, V> = tokio::sync::Mutex<LruCache<K, V>>;
 pub type ArcTokioRwLockOption<T> = Arc<tokio::sync::RwLock<Option<T>>>;
 pub type ArcTokioMutexOption<T> = Arc<tokio::sync::Mutex<Option<T>>>;
 
+/// Removes every value a cache holds for which `matches` is true and marks it as gone, so that
+/// tasks still holding on to one see it as being used in a breaking operation.
+///
+/// The cache is not locked while waiting on the values, so this can not deadlock with a task that
+/// holds a value and is waiting on the cache.
+pub async fn uncache_rw<K: Hash + Eq + Clone + Send, T: Send + Sync>(
+    cache: &TokioMutexCache<K, ArcTokioRwLockOption<T>>,
+    matches: impl Fn(&K) -> bool + Send
+) {
+    for value in pop_matching(cache, matches).await {
+        value.write().await.take();
+    }
+}
+
+/// Like `uncache_rw`, for caches of mutexes.
+pub async fn uncache_mutex<K: Hash + Eq + Clone + Send, T: Send>(
+    cache: &TokioMutexCache<K, ArcTokioMutexOption<T>>,
+    matches: impl Fn(&K) -> bool + Send
+) {
+    for value in pop_matching(cache, matches).await {
+        value.lock().await.take();
+    }
+}
+
+async fn pop_matching<K: Hash + Eq + Clone + Send, V: Send>(
+    cache: &TokioMutexCache<K, V>,
+    matches: impl Fn(&K) -> bool + Send
+) -> Vec<V> {
+    let mut cache = cache.lock().await;
+    let keys = cache
+        .iter()
+        .map(|(key, _)| key)
+        .filter(|key| matches(key))
+        .cloned()
+        .collect::<Vec<_>>();
+    let popped = keys
+        .iter()
+        .filter_map(|key| cache.pop(key))
+        .collect();
+    drop(cache);
+    popped
+}
+
 lazy_static! {
     pub static ref CLIENT: AsyncOnce<Client> = AsyncOnce::new(async {
         let uri = std::env::var("MONGO_URI").expect("MONGO_URI must be set");
@@ -35,6 +80,31 @@ async fn test_new_client() {
 
 pub mod uniques;
 
+/// The condition on a stored amount for adding `delta` to it with `$inc` to be allowed.
+///
+/// The amount must not overflow and, if there is a `floor`, must not end up below it. Putting this
+/// in the filter of the update makes the check and the change one atomic operation.
+pub fn inc_bounds(delta: i64, floor: Option<i64>) -> Document {
+    // Worked out in a wider type since the bounds themselves can be out of range.
+    let delta = i128::from(delta);
+    let mut lower = i128::from(i64::MIN) - delta.min(0);
+    if let Some(floor) = floor {
+        lower = lower.max(i128::from(floor) - delta);
+    }
+    let upper = i128::from(i64::MAX) - delta.max(0);
+    match (i64::try_from(lower), i64::try_from(upper)) {
+        (Ok(lower), Ok(upper)) if lower <= upper => doc! { "$gte": lower, "$lte": upper },
+        _ => doc! { "$lt": i64::MIN }, // Nothing can match.
+    }
+}
+
+#[test]
+fn test_inc_bounds() {
+    assert_eq!(inc_bounds(-5, Some(0)), doc! { "$gte": 5_i64, "$lte": i64::MAX });
+    assert_eq!(inc_bounds(5, None), doc! { "$gte": i64::MIN, "$lte": i64::MAX - 5 });
+    assert_eq!(inc_bounds(-1, Some(i64::MAX)), doc! { "$lt": i64::MIN });
+}
+
 /// Simply prepare the database for use.
 /// Environment variables must be set for this to work and
 /// the `MongoDB` service must be running.
@@ -59,7 +129,21 @@ pub async fn init() {
         "dropTables".to_owned(),
         "storeEntries".to_owned(),
         "balances".to_owned(),
-        "inventories".to_owned()
+        "inventories".to_owned(),
+        "transactions".to_owned(),
+        "scheduledJobs".to_owned(),
+        "roleIncomes".to_owned(),
+        "claimConfigs".to_owned(),
+        "claimStreaks".to_owned(),
+        "earnCooldowns".to_owned(),
+        "blockedEarns".to_owned(),
+        "gameConfigs".to_owned(),
+        "gameCooldowns".to_owned(),
+        "gameRecords".to_owned(),
+        "robConfigs".to_owned(),
+        "robCooldowns".to_owned(),
+        "activities".to_owned(),
+        "activityCooldowns".to_owned()
     ];
     collections.into_iter().for_each(|coll| columns.retain(|x| x != &coll));
 
@@ -72,4 +156,59 @@ pub async fn init() {
             }
         }
     }
+
+    if let Err(e) = models::Transaction::create_indexes().await {
+        eprintln!("Error when creating transaction indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::claim::ClaimStreak::create_indexes().await {
+        eprintln!("Error when creating claim streak indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::earn_cooldown::EarnCooldown::create_indexes().await {
+        eprintln!("Error when creating earn cooldown indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::blocked_earns::BlockedEarns::create_indexes().await {
+        eprintln!("Error when creating blocked earns indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::gambling::GameCooldown::create_indexes().await {
+        eprintln!("Error when creating game cooldown indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::gambling::GameRecord::create_indexes().await {
+        eprintln!("Error when creating game record indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::rob::RobCooldown::create_indexes().await {
+        eprintln!("Error when creating rob cooldown indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::role_income::RoleIncome::create_indexes().await {
+        eprintln!("Error when creating role income indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::activity::Activity::create_indexes().await {
+        eprintln!("Error when creating activity indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = models::activity::ActivityCooldown::create_indexes().await {
+        eprintln!("Error when creating activity cooldown indexes: {e}");
+        panic!();
+    }
+
+    if let Err(e) = crate::util::money::migrate_legacy_amounts().await {
+        eprintln!("Error when migrating legacy amounts: {e}");
+        panic!();
+    }
 }
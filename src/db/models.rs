@@ -1,9 +1,18 @@
+pub mod activity;
 pub mod balances;
+pub mod blocked_earns;
+pub mod claim;
 pub mod currency;
 pub mod drop_table;
+pub mod earn_cooldown;
+pub mod gambling;
 pub mod inventory;
 pub mod item;
+pub mod rob;
+pub mod role_income;
+pub mod scheduled_job;
 pub mod store;
+pub mod transaction;
 
 use anyhow::{ anyhow, Result };
 pub use balances::{ Balance, Balances };
@@ -11,9 +20,11 @@ pub use currency::Currency;
 pub use drop_table::DropTable;
 pub use inventory::{ Inventory, InventoryEntry };
 pub use item::{ Item, ItemError };
+pub use scheduled_job::ScheduledJob;
 use serde::Serialize;
 use serde_json::Value;
 pub use store::StoreEntry;
+pub use transaction::{ Transaction, TransactionKind, TransactionReason };
 
 /// This trait exists to serialize any struct that implements
 /// serialize into pairs of strings, representing the field names and values.
@@ -0,0 +1,420 @@
+//! This module contains the structs behind activity commands like `/work`, `/crime` or `/fish`,
+//! which staff make up themselves instead of them being built into the bot.
+//!
+//! `Activity` is one of those commands, and lives in the `activities` collection. Doing it rolls a
+//! drop table, and it may fail instead, costing the member some currency.
+//!
+//! `ActivityCooldown` is when a member last did an activity, in the `activityCooldowns`
+//! collection. Like `EarnCooldown` it is started with a single upsert that a unique index turns
+//! away while the member is still on cooldown.
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Duration, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime },
+    error::{ ErrorKind, WriteFailure },
+    options::{ IndexOptions, ReplaceOptions, UpdateOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+use serde_with::{ serde_as, DurationSeconds };
+
+use crate::{
+    db::{ uniques::{ DbGuildId, DbRoleId, DbUserId }, CLIENT },
+    util::money::Money,
+};
+
+/// The error code `MongoDB` returns when a unique index is violated.
+const DUPLICATE_KEY: i32 = 11000;
+/// The longest a command name can be on Discord.
+const MAX_NAME_LENGTH: usize = 32;
+/// The longest a command description can be on Discord.
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+/// What a member loses when an activity fails.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct ActivityPenalty {
+    pub curr_name: String,
+    /// The least that is taken, as long as the member has it.
+    pub min: Money,
+    /// The most that is taken.
+    pub max: Money,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct Activity {
+    guild_id: DbGuildId,
+    /// The name of the activity, which is also the name of its command.
+    name: String,
+    description: String,
+    drop_table_name: String,
+    /// How many times the drop table is rolled.
+    count: i64,
+    #[serde_as(as = "DurationSeconds<i64>")]
+    cooldown: Duration,
+    /// The role a member needs to do the activity, if any.
+    required_role: Option<DbRoleId>,
+    /// The chance the activity fails instead of rolling the drop table, as a fraction.
+    fail_chance: f64,
+    /// What failing costs. Failing costs nothing without one.
+    penalty: Option<ActivityPenalty>,
+    /// Shown above what the member got.
+    message: Option<String>,
+    /// Shown above what the member lost.
+    fail_message: Option<String>,
+}
+
+impl Activity {
+    /// Sets up an activity, replacing the one with the same name if there is one.
+    ///
+    /// # Errors
+    /// - The name can not be a command name, or the description is empty or too long.
+    /// - The count is not positive, the cooldown is negative or the fail chance is not from 0 to 1.
+    /// - The penalty takes less than nothing, or its minimum is more than its maximum.
+    /// - Any `MongoDB` error occurs.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn set(
+        guild_id: DbGuildId,
+        name: String,
+        description: String,
+        drop_table_name: String,
+        count: i64,
+        cooldown: Duration,
+        required_role: Option<DbRoleId>,
+        fail_chance: f64,
+        penalty: Option<ActivityPenalty>,
+        message: Option<String>,
+        fail_message: Option<String>
+    ) -> Result<Self> {
+        validate_name(&name)?;
+        if description.is_empty() || description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            bail!("The description must be from 1 to {MAX_DESCRIPTION_LENGTH} characters long.");
+        }
+        if count <= 0 {
+            bail!("The drop table has to be rolled at least once.");
+        }
+        if cooldown < Duration::zero() {
+            bail!("The cooldown cannot be negative.");
+        }
+        if !(0.0..=1.0).contains(&fail_chance) {
+            bail!("The fail chance must be from 0% to 100%.");
+        }
+        if let Some(penalty) = &penalty {
+            if penalty.min < Money::ZERO {
+                bail!("The penalty cannot be negative.");
+            }
+            if penalty.min > penalty.max {
+                bail!("The minimum penalty cannot be more than the maximum penalty.");
+            }
+        }
+        let new_self = Self {
+            guild_id,
+            name,
+            description,
+            drop_table_name,
+            count,
+            cooldown,
+            required_role,
+            fail_chance,
+            penalty,
+            message,
+            fail_message,
+        };
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activities");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Name": &new_self.name };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one(filterdoc, &new_self, options).await?;
+        Ok(new_self)
+    }
+
+    /// Gets an activity of a guild by its name.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_from_name(guild_id: DbGuildId, name: &str) -> Result<Option<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activities");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Name": name };
+        Ok(coll.find_one(filterdoc, None).await?)
+    }
+
+    /// Gets every activity of a guild.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_guild(guild_id: DbGuildId) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activities");
+
+        let res = coll.find(doc! { "GuildId": guild_id.as_i64() }, None).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Deletes the activity, along with the cooldowns members have on it.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn delete(self) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activities");
+        let cooldowns: Collection<ActivityCooldown> = db.collection("activityCooldowns");
+
+        let filterdoc = doc! { "GuildId": self.guild_id.as_i64(), "Name": &self.name };
+        coll.delete_one(filterdoc, None).await?;
+        let filterdoc = doc! { "GuildId": self.guild_id.as_i64(), "Activity": &self.name };
+        cooldowns.delete_many(filterdoc, None).await?;
+        Ok(())
+    }
+
+    /// Updates the currency name of the penalty of every activity in a guild that takes the old
+    /// currency.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activities");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "Penalty.CurrName": old_name,
+        };
+        let updatedoc = doc! {
+            "$set": {
+                "Penalty.CurrName": new_name,
+            }
+        };
+        if let Some(s) = session {
+            coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_many(filterdoc, updatedoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the index that keeps activity names unique in a guild.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activities");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "Name": 1 })
+            .options(IndexOptions::builder().name("GuildName".to_owned()).unique(true).build())
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn drop_table_name(&self) -> &str {
+        &self.drop_table_name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn count(&self) -> i64 {
+        self.count
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn required_role(&self) -> Option<DbRoleId> {
+        self.required_role
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn fail_chance(&self) -> f64 {
+        self.fail_chance
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn penalty(&self) -> Option<&ActivityPenalty> {
+        self.penalty.as_ref()
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn fail_message(&self) -> Option<&str> {
+        self.fail_message.as_deref()
+    }
+}
+
+/// Checks that a name can be the name of a slash command: lowercase letters, numbers, `-` and `_`,
+/// at most 32 of them.
+///
+/// # Errors
+/// - The name is empty, too long or has anything else in it.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        bail!("The name must be from 1 to {MAX_NAME_LENGTH} characters long.");
+    }
+    if
+        !name
+            .chars()
+            .all(|c| (c.is_alphanumeric() && !c.is_uppercase()) || c == '-' || c == '_')
+    {
+        bail!("The name can only have lowercase letters, numbers, - and _.");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct ActivityCooldown {
+    guild_id: DbGuildId,
+    user_id: DbUserId,
+    activity: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    last_done: DateTime<Utc>,
+}
+
+impl ActivityCooldown {
+    /// Starts the cooldown of an activity for a member at `now`, unless they are still on it, in
+    /// which case the time it is over is returned.
+    ///
+    /// The upsert only matches a cooldown that is already over, so if the member is still on
+    /// cooldown it tries to insert a second document and the unique index turns it away.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error other than the duplicate key occurs.
+    pub async fn try_start(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        activity: &str,
+        cooldown: Duration,
+        now: DateTime<Utc>
+    ) -> Result<Result<(), DateTime<Utc>>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activityCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "Activity": activity,
+            "LastDone": { "$lte": mongodb::bson::DateTime::from_chrono(now - cooldown) },
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "LastDone": mongodb::bson::DateTime::from_chrono(now),
+            },
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        match coll.update_one(filterdoc, updatedoc, options).await {
+            Ok(_) => Ok(Ok(())),
+            Err(e) => {
+                if let ErrorKind::Write(WriteFailure::WriteError(ref write_error)) = *e.kind {
+                    if write_error.code == DUPLICATE_KEY {
+                        let filterdoc =
+                            doc! {
+                            "GuildId": guild_id.as_i64(),
+                            "UserId": user_id.as_i64(),
+                            "Activity": activity,
+                        };
+                        let prev = coll
+                            .find_one(filterdoc, None).await?
+                            .ok_or_else(|| anyhow!("The cooldown disappeared, try again."))?;
+                        return Ok(Err(prev.last_done + cooldown));
+                    }
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Takes back a cooldown started at `started`, for when doing the activity did not go
+    /// through. Does nothing if the member started another one since.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn undo(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        activity: &str,
+        started: DateTime<Utc>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activityCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "Activity": activity,
+            "LastDone": mongodb::bson::DateTime::from_chrono(started),
+        };
+        coll.delete_one(filterdoc, None).await?;
+        Ok(())
+    }
+
+    /// Creates the unique index that `try_start` relies on.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("activityCooldowns");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "UserId": 1, "Activity": 1 })
+            .options(
+                IndexOptions::builder().name("GuildUserActivity".to_owned()).unique(true).build()
+            )
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("fish_2").is_ok());
+        assert!(validate_name("go-mining").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("Work").is_err());
+        assert!(validate_name("go mining").is_err());
+        assert!(validate_name(&"a".repeat(33)).is_err());
+    }
+}
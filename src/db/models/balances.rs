@@ -1,34 +1,41 @@
 //! This module contains the Balance and Balances structs and their methods.
 //!
 //! This is the main way that the bot stores the balances of each currency for each guild member.
-//! Balances are relatively simple as they just consist of a guild id, a user id, the currency name,
-//! and the amount of that currency that the user has.
+//! A balance consists of a guild id, a user id, the currency name, and two amounts of that
+//! currency: the wallet (`amount`), which is what the member can spend, and the bank (`bank`),
+//! which is kept apart from it. Each of them is a `Money`, a whole number of minor units.
 //!
-//! The only operations that one might want to do with balances is modify how much of one currency
-//! a user has, initialize a new balance, get how much of one currency the user has or delete that
-//! balance altogether. So therefore there is a relatively low amount of methods as you should not
-//! be doing things like changing the user that has that amount of currency or changing the currency.
+//! Besides adding to, taking from and setting the wallet, a member can deposit part of their
+//! wallet into the bank and withdraw it back, each as one conditional update so that neither part
+//! ever goes negative and the bank stays within the capacity of the currency. Interest, or a
+//! wealth tax, is applied to every balance of a currency in a guild at once with
+//! `Balances::apply_interest`, to either the wallet or the bank (see `Pocket`). Every change is
+//! written to the ledger (see `Transaction`) alongside it.
 //!
 
 use crate::db::uniques::{ CurrencyNameRef, DbGuildId, DbUserId };
-use crate::db::{ ArcTokioMutexOption, ArcTokioRwLockOption, TokioMutexCache };
+use crate::db::{ inc_bounds, ArcTokioMutexOption, ArcTokioRwLockOption, TokioMutexCache };
+use crate::util::money::{ Money, MONEY_SCALE };
 use anyhow::{ anyhow, Result };
 use futures::TryStreamExt;
 use lazy_static::lazy_static;
 use lru::LruCache;
 use mongodb::bson::doc;
-use mongodb::{ ClientSession, Collection };
+use mongodb::options::{ FindOneAndUpdateOptions, FindOptions, ReturnDocument };
+use mongodb::{ ClientSession, Collection, Cursor };
 use serde::{ Deserialize, Serialize };
 use std::borrow::Cow;
+use std::collections::{ HashMap, HashSet };
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use tokio::sync::{ Mutex, MutexGuard };
 
+use super::transaction::{ Transaction, TransactionChange, TransactionReason };
 use super::Currency;
 
 /// This struct represents all of the balances for every currency for a certain user in a certain
 /// guild.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
 pub struct Balances {
     guild_id: DbGuildId,
@@ -42,15 +49,82 @@ pub struct Balances {
 /// be created by a balances struct, but to prevent stupid things, clone has not been implemented
 /// for it and the Balances struct if it wishes to make another it should use `transmute_copy`.
 #[allow(clippy::unsafe_derive_deserialize)] // Shush I know what I'm doing.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
 pub struct Balance {
     guild_id: DbGuildId,
     user_id: DbUserId,
     pub curr_name: String,
-    pub amount: f64,
+    /// The wallet, what the member can spend.
+    pub amount: Money,
+    /// What the member keeps in the bank, if the currency has one. Balances from before banks
+    /// existed do not have it stored, which is the same as having nothing in the bank.
+    #[serde(default)]
+    bank: Money,
 }
 
+/// One of the two parts of a balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pocket {
+    /// What the member can spend, `Balance::amount`.
+    Wallet,
+    /// What the member keeps in the bank, `Balance::bank`.
+    Bank,
+}
+
+impl Pocket {
+    /// The field of the balance document the part is stored in.
+    const fn field(self) -> &'static str {
+        match self {
+            Self::Wallet => "Amount",
+            Self::Bank => "Bank",
+        }
+    }
+
+    const fn of(self, balance: &Balance) -> Money {
+        match self {
+            Self::Wallet => balance.amount,
+            Self::Bank => balance.bank,
+        }
+    }
+
+    fn change(self, curr_name: &str, delta: Money, resulting: Money) -> TransactionChange {
+        let curr_name = curr_name.to_owned();
+        match self {
+            Self::Wallet => TransactionChange::Currency { curr_name, delta, resulting },
+            Self::Bank => TransactionChange::Bank { curr_name, delta, resulting },
+        }
+    }
+}
+
+/// A single row of a leaderboard aggregation.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all(deserialize = "PascalCase"))]
+struct LeaderboardEntry {
+    user_id: DbUserId,
+    amount: Money,
+}
+
+/// A member's balances as grouped together by an aggregation.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all(deserialize = "PascalCase"))]
+struct GroupedBalances {
+    #[serde(rename = "_id")]
+    user_id: DbUserId,
+    balances: Vec<GroupedBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all(deserialize = "PascalCase"))]
+struct GroupedBalance {
+    curr_name: String,
+    amount: Money,
+}
+
+/// How many members `bulk_add_amounts` updates with a single `update_many`, since each of them is
+/// a branch in the update.
+pub(crate) const BULK_CHUNK_SIZE: usize = 500;
+
 lazy_static! {
     pub static ref CACHE_BALANCES: TokioMutexCache<(DbGuildId, DbUserId), ArcTokioMutexOption<Balances>> =
         Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap()));
@@ -266,6 +340,660 @@ impl Balances {
         Ok(())
     }
 
+    /// Ranks every member of a guild by how much of a currency they have, richest first. What is
+    /// in the bank counts too.
+    ///
+    /// This goes straight to the database with an aggregation instead of going through the
+    /// cache, since loading every member's balances into it would just evict everyone else.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn leaderboard(
+        guild_id: DbGuildId,
+        curr_name: CurrencyNameRef<'_>
+    ) -> Result<Vec<(DbUserId, Money)>> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let pipeline = vec![
+            doc! {
+                "$match": {
+                    "GuildId": guild_id.as_i64(),
+                    "CurrName": curr_name.as_str(),
+                },
+            },
+            doc! {
+                "$project": {
+                    "_id": 0,
+                    "UserId": 1,
+                    "Amount": { "$add": ["$Amount", { "$ifNull": ["$Bank", 0_i64] }] },
+                },
+            },
+            doc! {
+                "$sort": {
+                    "Amount": -1,
+                    "UserId": 1,
+                },
+            }
+        ];
+        let mut res = coll.aggregate(pipeline, None).await?;
+        drop(db);
+
+        let mut leaderboard = Vec::new();
+        while let Some(doc) = res.try_next().await? {
+            let entry: LeaderboardEntry = mongodb::bson::from_document(doc)?;
+            leaderboard.push((entry.user_id, entry.amount));
+        }
+        Ok(leaderboard)
+    }
+
+    /// Fetches every balance of every member in a guild, grouped per member, without
+    /// going through the cache. The amounts include what is in the bank.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn all_from_guild(guild_id: DbGuildId) -> Result<Vec<(DbUserId, Vec<(String, Money)>)>> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let pipeline = vec![
+            doc! {
+                "$match": {
+                    "GuildId": guild_id.as_i64(),
+                },
+            },
+            doc! {
+                "$group": {
+                    "_id": "$UserId",
+                    "Balances": {
+                        "$push": {
+                            "CurrName": "$CurrName",
+                            "Amount": { "$add": ["$Amount", { "$ifNull": ["$Bank", 0_i64] }] },
+                        },
+                    },
+                },
+            }
+        ];
+        let mut res = coll.aggregate(pipeline, None).await?;
+        drop(db);
+
+        let mut grouped = Vec::new();
+        while let Some(doc) = res.try_next().await? {
+            let group: GroupedBalances = mongodb::bson::from_document(doc)?;
+            grouped.push((
+                group.user_id,
+                group.balances
+                    .into_iter()
+                    .map(|b| (b.curr_name, b.amount))
+                    .collect(),
+            ));
+        }
+        Ok(grouped)
+    }
+
+    /// Gets a cursor over the balances of a guild, of only one currency if a name is given, sorted
+    /// by currency and then by member. Nothing goes through the cache and the balances are only
+    /// fetched as the cursor is read, so even the biggest guilds can be gone through this way.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn cursor_of_guild(
+        guild_id: DbGuildId,
+        curr_name: Option<&str>
+    ) -> Result<Cursor<Balance>> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let mut filterdoc = doc! {
+            "GuildId": guild_id.as_i64(),
+        };
+        if let Some(curr_name) = curr_name {
+            filterdoc.insert("CurrName", curr_name);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! { "CurrName": 1, "UserId": 1 })
+            .build();
+        Ok(coll.find(filterdoc, options).await?)
+    }
+
+    /// Adds the same amount of a currency to many members at once, with one `$inc` over all of
+    /// their balances rather than going through each member's `Balances`. Members who do not have
+    /// a balance for the currency yet get one. A ledger entry is written for each member.
+    ///
+    /// Members whose balances are currently cached are locked, in order of their ids, for the
+    /// duration so that the cached amounts can be brought up to date afterwards. Members that are
+    /// not cached are not touched at all besides the update itself.
+    ///
+    /// # Errors
+    /// - The amount is negative.
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_add_amount(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        user_ids: &[DbUserId],
+        amount: Money,
+        reason: TransactionReason
+    ) -> Result<()> {
+        if amount.is_negative() {
+            return Err(anyhow!("Cannot add a negative amount."));
+        }
+        if user_ids.is_empty() {
+            return Ok(());
+        }
+        let mut user_ids = user_ids.to_vec();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let cached = Self::cached_of(guild_id, &user_ids).await;
+        let mut guards = Vec::with_capacity(cached.len());
+        for balances in &cached {
+            guards.push(balances.lock().await);
+        }
+
+        let mut session = super::super::CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res = Self::bulk_add_amount_in_session(
+            guild_id,
+            curr_name,
+            &user_ids,
+            amount,
+            reason,
+            &mut session
+        ).await;
+        let resulting = match res {
+            Ok(resulting) => resulting,
+            Err(e) => {
+                for guard in guards {
+                    Self::invalidate_cache(guard).await.ok();
+                }
+                session.abort_transaction().await?;
+                return Err(e);
+            }
+        };
+        session.commit_transaction().await?;
+
+        Self::store_resulting(&mut guards, guild_id, curr_name, Pocket::Wallet, &resulting);
+        drop(guards);
+        Ok(())
+    }
+
+    async fn bulk_add_amount_in_session(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        user_ids: &[DbUserId],
+        amount: Money,
+        reason: TransactionReason,
+        session: &mut ClientSession
+    ) -> Result<HashMap<DbUserId, Money>> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let ids = user_ids
+            .iter()
+            .map(|id| id.as_i64())
+            .collect::<Vec<_>>();
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": curr_name,
+            "UserId": { "$in": &ids },
+        };
+
+        Self::insert_missing(guild_id, curr_name, user_ids, &filterdoc, &coll, session).await?;
+
+        let updatedoc = doc! {
+            "$inc": {
+                "Amount": amount,
+            },
+        };
+        coll.update_many_with_session(filterdoc.clone(), updatedoc, None, session).await?;
+
+        let resulting = coll
+            .find_with_session(filterdoc, None, session).await?
+            .stream(session)
+            .map_ok(|b| (b.user_id, b.amount))
+            .try_collect::<HashMap<_, _>>().await?;
+
+        let changes = resulting
+            .iter()
+            .map(|(user_id, resulting)| {
+                (
+                    *user_id,
+                    TransactionChange::Currency {
+                        curr_name: curr_name.to_owned(),
+                        delta: amount,
+                        resulting: *resulting,
+                    },
+                )
+            })
+            .collect();
+        Transaction::record_many(guild_id, reason, changes, Some(session)).await?;
+        Ok(resulting)
+    }
+
+    /// Adds a different amount of a currency to each of many members at once, like earnings that
+    /// were collected over a while. Works the same as `bulk_add_amount` otherwise, including
+    /// creating missing balances and writing a ledger entry for each member.
+    ///
+    /// The amounts are added with one pipeline `update_many` per `BULK_CHUNK_SIZE` members, which
+    /// picks the amount of each balance by its user id.
+    ///
+    /// # Errors
+    /// - Any of the amounts is negative.
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_add_amounts(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        amounts: &HashMap<DbUserId, Money>,
+        reason: TransactionReason
+    ) -> Result<()> {
+        if amounts.values().any(|amount| amount.is_negative()) {
+            return Err(anyhow!("Cannot add a negative amount."));
+        }
+        let mut user_ids = amounts
+            .iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        if user_ids.is_empty() {
+            return Ok(());
+        }
+        user_ids.sort_unstable();
+
+        let cached = Self::cached_of(guild_id, &user_ids).await;
+        let mut guards = Vec::with_capacity(cached.len());
+        for balances in &cached {
+            guards.push(balances.lock().await);
+        }
+
+        let mut session = super::super::CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res = Self::bulk_add_amounts_in_session(
+            guild_id,
+            curr_name,
+            &user_ids,
+            amounts,
+            reason,
+            &mut session
+        ).await;
+        let resulting = match res {
+            Ok(resulting) => resulting,
+            Err(e) => {
+                for guard in guards {
+                    Self::invalidate_cache(guard).await.ok();
+                }
+                session.abort_transaction().await?;
+                return Err(e);
+            }
+        };
+        session.commit_transaction().await?;
+
+        Self::store_resulting(&mut guards, guild_id, curr_name, Pocket::Wallet, &resulting);
+        drop(guards);
+        Ok(())
+    }
+
+    /// `bulk_add_amounts` inside of a session someone else takes care of, for when it is part of
+    /// something bigger. Cached balances are not touched, so the caller has to invalidate them.
+    /// The user ids must be sorted and not have an amount of zero.
+    ///
+    /// Returns the resulting amount of every balance that changed.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub(crate) async fn bulk_add_amounts_in_session(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        user_ids: &[DbUserId],
+        amounts: &HashMap<DbUserId, Money>,
+        reason: TransactionReason,
+        session: &mut ClientSession
+    ) -> Result<HashMap<DbUserId, Money>> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let ids = user_ids
+            .iter()
+            .map(|id| id.as_i64())
+            .collect::<Vec<_>>();
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": curr_name,
+            "UserId": { "$in": &ids },
+        };
+        Self::insert_missing(guild_id, curr_name, user_ids, &filterdoc, &coll, session).await?;
+
+        for chunk in user_ids.chunks(BULK_CHUNK_SIZE) {
+            let branches = chunk
+                .iter()
+                .map(|id| {
+                    doc! {
+                        "case": { "$eq": ["$UserId", id.as_i64()] },
+                        "then": amounts[id],
+                    }
+                })
+                .collect::<Vec<_>>();
+            let chunk_ids = chunk
+                .iter()
+                .map(|id| id.as_i64())
+                .collect::<Vec<_>>();
+            let mut chunk_filter = filterdoc.clone();
+            chunk_filter.insert("UserId", doc! { "$in": chunk_ids });
+            let updatedoc =
+                vec![
+                doc! {
+                "$set": {
+                    "Amount": {
+                        "$add": [
+                            "$Amount",
+                            { "$switch": { "branches": branches, "default": 0_i64 } },
+                        ],
+                    },
+                },
+            }
+            ];
+            coll.update_many_with_session(chunk_filter, updatedoc, None, session).await?;
+        }
+
+        let resulting = coll
+            .find_with_session(filterdoc, None, session).await?
+            .stream(session)
+            .map_ok(|b| (b.user_id, b.amount))
+            .try_collect::<HashMap<_, _>>().await?;
+
+        let changes = resulting
+            .iter()
+            .map(|(user_id, resulting)| {
+                (
+                    *user_id,
+                    TransactionChange::Currency {
+                        curr_name: curr_name.to_owned(),
+                        delta: amounts[user_id],
+                        resulting: *resulting,
+                    },
+                )
+            })
+            .collect();
+        Transaction::record_many(guild_id, reason, changes, Some(session)).await?;
+        Ok(resulting)
+    }
+
+    /// Inserts an empty balance of a currency for each of the members that the filter does not
+    /// find one for.
+    async fn insert_missing(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        user_ids: &[DbUserId],
+        filterdoc: &mongodb::bson::Document,
+        coll: &Collection<Balance>,
+        session: &mut ClientSession
+    ) -> Result<()> {
+        let existing = coll
+            .find_with_session(filterdoc.clone(), None, session).await?
+            .stream(session)
+            .map_ok(|b| b.user_id)
+            .try_collect::<HashSet<_>>().await?;
+        let missing = user_ids
+            .iter()
+            .filter(|id| !existing.contains(id))
+            .map(|id| Balance {
+                guild_id,
+                user_id: *id,
+                curr_name: curr_name.to_owned(),
+                amount: Money::ZERO,
+                bank: Money::ZERO,
+            })
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            coll.insert_many_with_session(missing, None, session).await?;
+        }
+        Ok(())
+    }
+
+    /// Applies interest, or a wealth tax if the rate is negative, to one part of every balance of
+    /// a currency in a guild where that part is positive and at least `threshold`. The rate is a
+    /// fraction of it, so `0.01` adds 1% and `-0.01` takes 1%. What each balance gains or loses is
+    /// truncated to the precision of the currency. A ledger entry is written for every balance that
+    /// changed.
+    ///
+    /// All of the balances are updated with one `update_many`. Cached balances are locked and
+    /// brought up to date the same way as in `bulk_add_amount`. A balance that would end up above
+    /// `Money::MAX` is left at `Money::MAX`.
+    ///
+    /// Returns how many balances changed.
+    ///
+    /// # Errors
+    /// - The rate would take more than the whole balance.
+    /// - The precision is more than `MONEY_SCALE`.
+    /// - Any `MongoDB` error occurs.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn apply_interest(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        pocket: Pocket,
+        rate: f64,
+        threshold: Money,
+        precision: u8,
+        reason: TransactionReason
+    ) -> Result<usize> {
+        if !rate.is_finite() || rate < -1.0 {
+            return Err(anyhow!("Interest rate must be a number of at least -100%."));
+        }
+        if precision > MONEY_SCALE {
+            return Err(anyhow!("Precision cannot be more than {MONEY_SCALE} decimal places."));
+        }
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let filterdoc = interest_filter(guild_id, curr_name, pocket, threshold);
+
+        let mut user_ids = coll
+            .find(filterdoc, None).await?
+            .map_ok(|b| b.user_id)
+            .try_collect::<Vec<_>>().await?;
+        if user_ids.is_empty() {
+            return Ok(0);
+        }
+        user_ids.sort_unstable();
+
+        let cached = Self::cached_of(guild_id, &user_ids).await;
+        let mut guards = Vec::with_capacity(cached.len());
+        for balances in &cached {
+            guards.push(balances.lock().await);
+        }
+
+        let mut session = super::super::CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res = Self::apply_interest_in_session(
+            guild_id,
+            curr_name,
+            pocket,
+            &user_ids,
+            rate,
+            threshold,
+            precision,
+            reason,
+            &mut session
+        ).await;
+        let resulting = match res {
+            Ok(resulting) => resulting,
+            Err(e) => {
+                for guard in guards {
+                    Self::invalidate_cache(guard).await.ok();
+                }
+                session.abort_transaction().await?;
+                return Err(e);
+            }
+        };
+        session.commit_transaction().await?;
+
+        Self::store_resulting(&mut guards, guild_id, curr_name, pocket, &resulting);
+        drop(guards);
+        Ok(resulting.len())
+    }
+
+    /// Does the actual work of `apply_interest`. Returns the resulting amounts of the balances
+    /// that changed.
+    #[allow(clippy::too_many_arguments)]
+    async fn apply_interest_in_session(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        pocket: Pocket,
+        user_ids: &[DbUserId],
+        rate: f64,
+        threshold: Money,
+        precision: u8,
+        reason: TransactionReason,
+        session: &mut ClientSession
+    ) -> Result<HashMap<DbUserId, Money>> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Balance> = db.collection("balances");
+        let ids = user_ids
+            .iter()
+            .map(|id| id.as_i64())
+            .collect::<Vec<_>>();
+        let mut filterdoc = interest_filter(guild_id, curr_name, pocket, threshold);
+        filterdoc.insert("UserId", doc! { "$in": &ids });
+
+        let before = coll
+            .find_with_session(filterdoc.clone(), None, session).await?
+            .stream(session)
+            .map_ok(|b| (b.user_id, pocket.of(&b)))
+            .try_collect::<HashMap<_, _>>().await?;
+
+        // Amounts are whole numbers of minor units, so truncating the interest to the precision of
+        // the currency means truncating it to a multiple of this many minor units.
+        let step = 10_i64.pow(u32::from(MONEY_SCALE - precision));
+        // Worked out as decimals rather than doubles, which cannot hold every amount above 2^53
+        // minor units, and capped at the largest amount so that one balance cannot fail the rest.
+        // The rate goes in as its shortest decimal representation, so 0.015 is exactly 0.015.
+        let amount = doc! { "$toDecimal": format!("${}", pocket.field()) };
+        let interest =
+            doc! {
+            "$multiply": [
+                {
+                    "$trunc": {
+                        "$divide": [
+                            { "$multiply": [&amount, { "$toDecimal": rate.to_string() }] },
+                            step,
+                        ],
+                    },
+                },
+                step,
+            ],
+        };
+        let updatedoc =
+            vec![
+            doc! {
+            "$set": {
+                pocket.field(): {
+                    "$toLong": {
+                        "$min": [
+                            { "$add": [&amount, interest] },
+                            { "$toDecimal": Money::MAX },
+                        ],
+                    },
+                },
+            },
+        }
+        ];
+        coll.update_many_with_session(filterdoc.clone(), updatedoc, None, session).await?;
+
+        let ids = before.keys().map(|id| id.as_i64()).collect::<Vec<_>>();
+        filterdoc.remove(pocket.field());
+        filterdoc.insert("UserId", doc! { "$in": &ids });
+        let resulting = coll
+            .find_with_session(filterdoc, None, session).await?
+            .stream(session)
+            .try_filter_map(|b| {
+                let after = pocket.of(&b);
+                let changed = before.get(&b.user_id).is_some_and(|before| *before != after);
+                futures::future::ready(Ok(changed.then_some((b.user_id, after))))
+            })
+            .try_collect::<HashMap<_, _>>().await?;
+
+        let changes = resulting
+            .iter()
+            .filter_map(|(user_id, resulting)| {
+                let delta = resulting.checked_sub(*before.get(user_id)?)?;
+                Some((*user_id, pocket.change(curr_name, delta, *resulting)))
+            })
+            .collect();
+        Transaction::record_many(guild_id, reason, changes, Some(session)).await?;
+        Ok(resulting)
+    }
+
+    /// Gets the `Balances` of the given members that are currently in the cache, in the same
+    /// order as the ids. Lock them in that order to avoid deadlocking with other bulk updates.
+    async fn cached_of(guild_id: DbGuildId, user_ids: &[DbUserId]) -> Vec<ArcTokioMutexOption<Self>> {
+        let mut cache = CACHE_BALANCES.lock().await;
+        let cached = user_ids
+            .iter()
+            .filter_map(|id| cache.get(&(guild_id, *id)).cloned())
+            .collect::<Vec<_>>();
+        drop(cache);
+        cached
+    }
+
+    /// Brings one part of the cached balances of a currency up to date after a bulk update.
+    fn store_resulting(
+        guards: &mut [MutexGuard<'_, Option<Self>>],
+        guild_id: DbGuildId,
+        curr_name: &str,
+        pocket: Pocket,
+        resulting: &HashMap<DbUserId, Money>
+    ) {
+        for guard in guards {
+            let Some(balances) = guard.as_mut() else {
+                continue;
+            };
+            let Some(amount) = resulting.get(&balances.user_id) else {
+                continue;
+            };
+            let i = balances.balances.iter().position(|b| b.curr_name == curr_name);
+            let balance = if let Some(i) = i {
+                &mut balances.balances[i]
+            } else {
+                balances.balances.push(Balance {
+                    guild_id,
+                    user_id: balances.user_id,
+                    curr_name: curr_name.to_owned(),
+                    amount: Money::ZERO,
+                    bank: Money::ZERO,
+                });
+                balances.balances.last_mut().unwrap()
+            };
+            match pocket {
+                Pocket::Wallet => balance.amount = *amount,
+                Pocket::Bank => balance.bank = *amount,
+            }
+        }
+    }
+
+    /// Locks the balances of two different members, always the one with the lower id first.
+    /// Otherwise two tasks locking the same two members the other way around, like two members
+    /// paying each other at the same time, would each hold one lock and wait forever on the other.
+    /// The guards are returned in the order the members were given.
+    ///
+    /// # Warning
+    /// The members must not be the same, and neither of their balances may already be locked by
+    /// the caller, or this ***WILL*** deadlock.
+    pub async fn lock_pair<'a>(
+        first: (&'a ArcTokioMutexOption<Self>, DbUserId),
+        second: (&'a ArcTokioMutexOption<Self>, DbUserId)
+    ) -> (MutexGuard<'a, Option<Self>>, MutexGuard<'a, Option<Self>>) {
+        if first.1 < second.1 {
+            let first = first.0.lock().await;
+            let second = second.0.lock().await;
+            (first, second)
+        } else {
+            let second = second.0.lock().await;
+            let first = first.0.lock().await;
+            (first, second)
+        }
+    }
+
+    /// Drops the balances of every member of a guild from the cache, like `invalidate_cache` does
+    /// for one member.
+    pub async fn invalidate_guild_cache(guild_id: DbGuildId) {
+        crate::db::uncache_mutex(&CACHE_BALANCES, |(g, _)| *g == guild_id).await;
+    }
+
     pub async fn invalidate_cache(mut self_: MutexGuard<'_, Option<Self>>) -> Result<()> {
         let take_res = self_.take();
         let Some(self__) = take_res else {
@@ -279,6 +1007,20 @@ impl Balances {
     }
 }
 
+/// Matches the balances of a currency that interest applies to, going by one part of them.
+fn interest_filter(
+    guild_id: DbGuildId,
+    curr_name: &str,
+    pocket: Pocket,
+    threshold: Money
+) -> mongodb::bson::Document {
+    doc! {
+        "GuildId": guild_id.as_i64(),
+        "CurrName": curr_name,
+        pocket.field(): { "$gt": 0_i64, "$gte": threshold },
+    }
+}
+
 impl Balance {
     /// Attempts to make a new balance corresponding to a specific user, currency and guild.
     ///
@@ -306,7 +1048,8 @@ impl Balance {
             guild_id,
             user_id,
             curr_name,
-            amount: 0.0,
+            amount: Money::ZERO,
+            bank: Money::ZERO,
         };
 
         coll.insert_one(&user_balance, None).await?;
@@ -337,176 +1080,345 @@ impl Balance {
     }
 
     #[allow(clippy::must_use_candidate)]
-    pub const fn amount(&self) -> f64 {
+    pub const fn amount(&self) -> Money {
         self.amount
     }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn bank(&self) -> Money {
+        self.bank
+    }
+
+    /// Moves the specified amount from the wallet into the bank. If there is a `capacity`, the
+    /// bank may not end up holding more than that.
+    ///
+    /// # Errors
+    /// - If any `MongoDB` error occurs.
+    /// - If the balance does not exist in the database.
+    /// - The amount is not positive.
+    /// - There is not that much in the wallet.
+    /// - The bank would end up holding more than the capacity.
+    pub async fn deposit(
+        &mut self,
+        amount: Money,
+        capacity: Option<Money>,
+        reason: TransactionReason,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if amount <= Money::ZERO {
+            return Err(anyhow!("Can only deposit more than 0."));
+        }
+        self.move_to_bank(amount, capacity, reason, session).await
+    }
+
+    /// Moves the specified amount from the bank back into the wallet.
+    ///
+    /// # Errors
+    /// - If any `MongoDB` error occurs.
+    /// - If the balance does not exist in the database.
+    /// - The amount is not positive.
+    /// - There is not that much in the bank.
+    /// - The wallet would overflow.
+    pub async fn withdraw(
+        &mut self,
+        amount: Money,
+        reason: TransactionReason,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if amount <= Money::ZERO {
+            return Err(anyhow!("Can only withdraw more than 0."));
+        }
+        let delta = Money::ZERO
+            .checked_sub(amount)
+            .ok_or_else(|| anyhow!("Cannot withdraw that amount, would overflow."))?;
+        self.move_to_bank(delta, None, reason, session).await
+    }
+
+    /// Moves `delta` from the wallet into the bank, or the other way around if it is negative,
+    /// with one conditional `$inc` like `inc_amount`. Neither part may end up negative, and the
+    /// bank may only go over the `capacity` by withdrawing. Both changes are written to the ledger.
+    ///
+    /// # Errors
+    /// - If any `MongoDB` error occurs.
+    /// - If the balance does not exist in the database.
+    /// - There is not enough in the part the amount comes from.
+    /// - The bank would end up holding more than the capacity.
+    /// - Either part would overflow.
+    async fn move_to_bank(
+        &mut self,
+        delta: Money,
+        capacity: Option<Money>,
+        reason: TransactionReason,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("balances");
+
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "UserId": self.user_id.as_i64(),
+            "CurrName": self.curr_name.as_str(),
+        };
+        let wallet_delta = Money::ZERO
+            .checked_sub(delta)
+            .ok_or_else(|| anyhow!("Cannot move that amount, would overflow."))?;
+        let depositing = delta > Money::ZERO;
+        let mut conditional = filterdoc.clone();
+        conditional.insert(
+            "Amount",
+            inc_bounds(wallet_delta.as_minor(), depositing.then_some(0))
+        );
+        let mut bank_bounds = inc_bounds(delta.as_minor(), Some(0));
+        if let Some(capacity) = capacity.filter(|_| depositing) {
+            if delta > capacity {
+                return Err(anyhow!("The bank can only hold {capacity}."));
+            }
+            // Cannot overflow since both are positive.
+            bank_bounds.insert("$lte", capacity.as_minor() - delta.as_minor());
+        }
+        if depositing {
+            // Balances from before banks existed have nothing in the bank, not no bank.
+            conditional.insert(
+                "$or",
+                vec![doc! { "Bank": { "$exists": false } }, doc! { "Bank": bank_bounds }]
+            );
+        } else {
+            conditional.insert("Bank", bank_bounds);
+        }
+        let updatedoc =
+            doc! {
+            "$inc": {
+                "Amount": wallet_delta,
+                "Bank": delta,
+            },
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let updated = if let Some(s) = session.as_deref_mut() {
+            coll.find_one_and_update_with_session(conditional, updatedoc, options, s).await?
+        } else {
+            coll.find_one_and_update(conditional, updatedoc, options).await?
+        };
+        let Some(updated) = updated else {
+            // Find out why it did not match, and bring the amounts here up to date while at it.
+            let current = if let Some(s) = session {
+                coll.find_one_with_session(filterdoc, None, s).await?
+            } else {
+                coll.find_one(filterdoc, None).await?
+            };
+            let Some(current) = current else {
+                return Err(anyhow!("Failed to update balance."));
+            };
+            self.amount = current.amount;
+            self.bank = current.bank;
+            if depositing && current.amount < delta {
+                return Err(anyhow!("Cannot deposit more than is in the wallet."));
+            }
+            if !depositing && current.bank < wallet_delta {
+                return Err(anyhow!("Cannot withdraw more than is in the bank."));
+            }
+            if let Some(capacity) = capacity.filter(|_| depositing) {
+                if current.bank.checked_add(delta).is_none_or(|bank| bank > capacity) {
+                    return Err(anyhow!("The bank can only hold {capacity}."));
+                }
+            }
+            return Err(anyhow!("Cannot move that much, would overflow."));
+        };
+
+        let changes = vec![
+            (self.user_id, Pocket::Wallet.change(&self.curr_name, wallet_delta, updated.amount)),
+            (self.user_id, Pocket::Bank.change(&self.curr_name, delta, updated.bank))
+        ];
+        Transaction::record_many(self.guild_id, reason, changes, session).await?;
+        self.amount = updated.amount;
+        self.bank = updated.bank;
+        Ok(())
+    }
     /// Sets the amount of the currency that the user said to the specified amount.
     ///
     /// # Errors
     /// - If any `MongoDB` error occurs.
     /// - If the amount of modified documents is 0.
-    /// - If the amount specified is infinite.
-    /// - If the amount specified is NaN.
     #[inline]
     pub async fn set_amount(
         &mut self,
-        amount: f64,
+        amount: Money,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
-        if amount.is_infinite() {
-            return Err(anyhow!("Amount cannot be infinite."));
-        }
-        if amount.is_nan() {
-            return Err(anyhow!("Amount cannot be NaN."));
-        }
-
-        self.set_amount_unchecked(amount, session).await
+        self.set_amount_unchecked(amount, reason, session).await
     }
 
     /// Adds the specified amount to the current amount.
     ///
     /// # Errors
     /// - If any `MongoDB` error occurs.
-    /// - If the amount of modified documents is 0.
+    /// - If the balance does not exist in the database.
     /// - The specified amount is negative.
-    /// - The specified amount is infinite.
-    /// - The specified amount would cause the balance to overflow to infinity.
-    /// - The specified amount would cause a NaN.
-    #[allow(clippy::float_cmp)] // we compare ***TRUNCATED*** values.
+    /// - The specified amount would cause the balance to overflow.
     pub async fn add_amount(
         &mut self,
-        mut amount: f64,
+        amount: Money,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
-        if amount.is_nan() {
-            return Err(anyhow!("Cannot add NaN."));
-        }
-        if amount < 0.0 {
+        if amount.is_negative() {
             return Err(anyhow!("Cannot add a negative amount."));
         }
-        if amount.is_infinite() {
-            return Err(anyhow!("Cannot add infinity."));
-        }
-        // Round provided amount to 2 decimal places
-        amount = if amount.trunc() == amount || (amount * 100.0).trunc() == amount * 100.0 {
-            amount
-        } else {
-            (amount * 100.0).round() * 0.01 // multiplication is faster than division
-        };
-        let new_amount = self.amount.mul_add(100.0, amount * 100.0).round() * 0.01;
-        if new_amount.is_infinite() {
-            return Err(anyhow!("Cannot add that amount, would overflow to infinity."));
-        } else if new_amount.is_nan() {
-            return Err(anyhow!("Cannot add that amount, would cause a NaN."));
-        }
-        self.set_amount(new_amount, session).await
+        self.inc_amount(amount, None, reason, session).await
     }
 
     /// Subtracts the specified amount from the current amount.
     ///
+    /// Whether there is enough is decided by the database rather than the amount here, so two
+    /// subtractions at once can never both spend the same money.
+    ///
     /// # Errors
     /// - If any `MongoDB` error occurs.
-    /// - If the amount of modified documents is 0.
+    /// - If the balance does not exist in the database.
     /// - If the amount to subtract is greater than the current amount.
     /// - The specified amount is negative.
-    /// - The specified amount is infinite.
-    /// - The specified amount would cause a NaN.
     pub async fn sub_amount(
         &mut self,
-        mut amount: f64,
+        amount: Money,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
-        if amount.is_nan() {
-            return Err(anyhow!("Cannot subtract NaN."));
-        }
-        if amount < 0.0 {
+        if amount.is_negative() {
             return Err(anyhow!("Cannot subtract a negative amount."));
         }
-        if amount.is_infinite() {
-            return Err(anyhow!("Cannot subtract infinity."));
-        }
-        amount = (amount * 100.0).round() * 0.01; // multiplication is faster than division
-        if amount > self.amount {
-            return Err(anyhow!("Cannot subtract more than the current amount."));
-        }
-        let new_amount = self.amount.mul_add(100.0, -amount * 100.0).round() * 0.01;
-        if new_amount.is_nan() {
-            return Err(anyhow!("Cannot subtract that amount, would cause a NaN."));
-        }
-        self.set_amount(new_amount, session).await
+        let delta = Money::ZERO
+            .checked_sub(amount)
+            .ok_or_else(|| anyhow!("Cannot subtract that amount, would overflow."))?;
+        self.inc_amount(delta, Some(Money::ZERO), reason, session).await
     }
 
     /// Subtracts the specified amount from the current amount without checking if the balance
     /// will go into the negatives and without checking if the amount is negative. However, it still
-    /// checks to see if the result of the operations turns out to be NaN.
+    /// checks to see if the result of the operation overflows.
     ///
     /// # Errors
     /// - If any `MongoDB` error occurs.
-    /// - If the amount of modified documents is 0.
-    /// - The specified amount would cause a NaN.
+    /// - If the balance does not exist in the database.
+    /// - The specified amount would cause the balance to overflow.
     pub async fn sub_amount_unchecked(
         &mut self,
-        mut amount: f64,
+        amount: Money,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
-        if amount.is_nan() {
-            return Err(anyhow!("Cannot subtract NaN."));
-        }
-        amount = (amount * 100.0).round() / 100.0;
-        let new_amount = self.amount.mul_add(100.0, -amount * 100.0).round() / 100.0;
-        if new_amount.is_nan() {
-            return Err(anyhow!("Cannot subtract that amount, would cause a NaN."));
-        }
-        self.set_amount_unchecked(new_amount, session).await
+        let delta = Money::ZERO
+            .checked_sub(amount)
+            .ok_or_else(|| anyhow!("Cannot subtract that amount, would overflow."))?;
+        self.inc_amount(delta, None, reason, session).await
     }
 
-    /// Adds the specified amount to the current amount without checking if the balance
-    /// will go into infinity and without checking if the amount is negative. However, it still
-    /// checks to see if the result of the operations turns out to be NaN.
+    /// Adds the specified amount to the current amount without checking if the amount is
+    /// negative. However, it still checks to see if the result of the operation overflows.
     ///
     /// # Errors
     ///
     /// - If any `MongoDB` error occurs.
-    /// - If the amount of modified documents is 0.
-    /// - The specified amount would cause a NaN.
+    /// - If the balance does not exist in the database.
+    /// - The specified amount would cause the balance to overflow.
     pub async fn add_amount_unchecked(
         &mut self,
-        mut amount: f64,
+        amount: Money,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
-        if amount.is_nan() {
-            return Err(anyhow!("Cannot add NaN."));
-        }
-        amount = (amount * 100.0).round() / 100.0;
-        let new_amount = self.amount.mul_add(100.0, amount * 100.0).round() / 100.0;
-        if new_amount.is_nan() {
-            return Err(anyhow!("Cannot add that amount, would cause a NaN."));
-        }
-        self.set_amount_unchecked(new_amount, session).await
+        self.inc_amount(amount, None, reason, session).await
+    }
+
+    /// Changes the amount by `delta` with one conditional `$inc`, so the result is right no matter
+    /// how out of date the amount here is or who else is changing the balance at the same time.
+    /// The update only matches if the balance would not overflow and, if there is a `floor`, would
+    /// end up at least that much. Either way the amount here is refreshed from the database.
+    ///
+    /// Every relative change ends up here, so this is also where it gets written to the ledger.
+    ///
+    /// # Errors
+    /// - If any `MongoDB` error occurs.
+    /// - If the balance does not exist in the database.
+    /// - The balance would end up below the floor.
+    /// - The balance would overflow.
+    async fn inc_amount(
+        &mut self,
+        delta: Money,
+        floor: Option<Money>,
+        reason: TransactionReason,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("balances");
+
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "UserId": self.user_id.as_i64(),
+            "CurrName": self.curr_name.as_str(),
+        };
+        let mut conditional = filterdoc.clone();
+        conditional.insert("Amount", inc_bounds(delta.as_minor(), floor.map(Money::as_minor)));
+        let updatedoc =
+            doc! {
+            "$inc": {
+                "Amount": delta,
+            },
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let updated = if let Some(s) = session.as_deref_mut() {
+            coll.find_one_and_update_with_session(conditional, updatedoc, options, s).await?
+        } else {
+            coll.find_one_and_update(conditional, updatedoc, options).await?
+        };
+        let Some(updated) = updated else {
+            // Find out why it did not match, and bring the amount here up to date while at it.
+            let current = if let Some(s) = session {
+                coll.find_one_with_session(filterdoc, None, s).await?
+            } else {
+                coll.find_one(filterdoc, None).await?
+            };
+            let Some(current) = current else {
+                return Err(anyhow!("Failed to update balance."));
+            };
+            self.amount = current.amount;
+            if floor.is_some_and(|floor| current.amount.checked_add(delta) < Some(floor)) {
+                return Err(anyhow!("Cannot subtract more than the current amount."));
+            }
+            return Err(anyhow!("Cannot change the amount by that much, would overflow."));
+        };
+
+        let change = TransactionChange::Currency {
+            curr_name: self.curr_name.clone(),
+            delta,
+            resulting: updated.amount,
+        };
+        Transaction::record(self.guild_id, self.user_id, reason, change, session).await?;
+        self.amount = updated.amount;
+        Ok(())
     }
 
-    /// Sets the amount to the specified amount without checking if the amount is infinite.
-    /// However, it still checks to see if the amount is NaN.
+    /// Sets the amount to the specified amount without any checks.
+    ///
+    /// The change written to the ledger is worked out from the amount in the database right
+    /// before the update, not the amount here.
     ///
     /// # Errors
     /// - If any `MongoDB` error occurs.
-    /// - If the amount of modified documents is 0.
-    /// - The specified amount is NaN.
+    /// - If the balance does not exist in the database.
     pub async fn set_amount_unchecked(
         &mut self,
-        mut amount: f64,
-        session: Option<&mut ClientSession>
+        amount: Money,
+        reason: TransactionReason,
+        mut session: Option<&mut ClientSession>
     ) -> Result<()> {
-        if amount.is_nan() {
-            return Err(anyhow!("Cannot set NaN."));
-        }
-        // -0 on a balance sheet looks a bit odd, so we just set it to 0
-        // praying that somehow it is not a special kind of -0 that cannot
-        // be compared to this -0 we have here.
-        if amount == -0.0 {
-            amount = 0.0;
-        }
-        amount = (amount * 100.0).round() / 100.0;
         let db = super::super::CLIENT.get().await.database("conebot");
         let coll: Collection<Self> = db.collection("balances");
 
@@ -522,30 +1434,44 @@ impl Balance {
                 "Amount": amount,
             },
         };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::Before)
+            .build();
 
-        let res;
-        if let Some(session) = session {
-            res = coll.update_one_with_session(filterdoc, updatedoc, None, session).await?;
+        let before = if let Some(s) = session.as_deref_mut() {
+            coll.find_one_and_update_with_session(filterdoc, updatedoc, options, s).await?
         } else {
-            res = coll.update_one(filterdoc, updatedoc, None).await?;
-        }
-        if res.modified_count == 0 {
-            return Err(anyhow!("Failed to update balance."));
-        }
+            coll.find_one_and_update(filterdoc, updatedoc, options).await?
+        };
+        let before = before.ok_or_else(|| anyhow!("Failed to update balance."))?;
+
+        let delta = amount
+            .checked_sub(before.amount)
+            .ok_or_else(|| anyhow!("Cannot set that amount, the change would overflow."))?;
+        let change = TransactionChange::Currency {
+            curr_name: self.curr_name.clone(),
+            delta,
+            resulting: amount,
+        };
+        Transaction::record(self.guild_id, self.user_id, reason, change, session).await?;
         self.amount = amount;
         Ok(())
     }
 
     /// Clears the user's balance for this currency.
     ///
-    /// Literally just an alias for `set_amount(0.0)`.
+    /// Literally just an alias for `set_amount(Money::ZERO)`.
     ///
     /// # Errors
     /// - If any `MongoDB` error occurs.
     /// - If the amount of modified documents is 0.
     #[inline]
-    pub async fn clear(&mut self, session: Option<&mut ClientSession>) -> Result<()> {
-        self.set_amount(0.0, session).await
+    pub async fn clear(
+        &mut self,
+        reason: TransactionReason,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        self.set_amount(Money::ZERO, reason, session).await
     }
 
     /// Checks if this balance belongs to a valid currency.
@@ -596,6 +1522,11 @@ impl Balance {
 
 #[cfg(test)]
 mod test {
+    use crate::db::models::{ TransactionKind, TransactionReason };
+    use crate::util::money::{ Money, MONEY_SCALE };
+
+    const REASON: TransactionReason = TransactionReason::system(TransactionKind::Give);
+
     const TEST_USER_ID: u64 = 987_654_321;
     const TEST_GUILD_ID: u64 = 123_456_789;
     #[tokio::test]
@@ -611,7 +1542,6 @@ mod test {
     }
 
     #[tokio::test]
-    #[allow(clippy::float_cmp)]
     async fn test_checked_amount_operations() {
         crate::init_env().await;
         let user = crate::db::uniques::DbUserId::from(TEST_USER_ID);
@@ -623,48 +1553,64 @@ mod test {
             .iter_mut()
             .find(|b| b.curr_name == "test")
             .unwrap();
-        let error_margin: f64 = f64::EPSILON;
+        let money = |s: &str| s.parse::<Money>().unwrap();
+
+        balance.set_amount(money("30"), REASON, None).await.ok();
+
+        assert_eq!(balance.amount, money("30")); // value in DB is 30
+        balance.add_amount(money("1"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("31"));
+        balance.sub_amount(money("1"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("30"));
 
-        balance.set_amount(30.0, None).await.ok();
+        assert!(balance.add_amount(Money::MAX, REASON, None).await.is_err()); // overflow check
+        assert!(balance.sub_amount(money("32"), REASON, None).await.is_err());
 
-        assert!((balance.amount - 30.0).abs() < error_margin); // value in DB is 30.0
-        balance.add_amount(1.0, None).await.unwrap();
-        assert!((balance.amount - 31.0).abs() < error_margin);
-        balance.sub_amount(1.0, None).await.unwrap();
-        assert!((balance.amount - 30.0).abs() < error_margin);
+        assert!(balance.add_amount(money("-1"), REASON, None).await.is_err()); // negative check
+        assert!(balance.sub_amount(money("-1"), REASON, None).await.is_err());
 
-        assert!(balance.add_amount(f64::INFINITY, None).await.is_err()); // inf check
-        assert!(balance.sub_amount(f64::INFINITY, None).await.is_err());
+        balance.set_amount(money("0.1"), REASON, None).await.unwrap(); // The classic float trap.
+        balance.add_amount(money("0.2"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("0.3"));
+        balance.sub_amount(money("0.2"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("0.1"));
 
-        assert!(balance.add_amount(f64::MAX, None).await.is_err()); // overflow check
-        assert!(balance.sub_amount(32.0, None).await.is_err());
+        balance.set_amount(money("30"), REASON, None).await.ok(); // Reset amount
 
-        assert!(balance.add_amount(-1.0, None).await.is_err()); // negative check
-        assert!(balance.sub_amount(-1.0, None).await.is_err());
+        drop(balances);
+    }
 
-        assert!(balance.add_amount(f64::NAN, None).await.is_err()); // NaN check
-        assert!(balance.sub_amount(f64::NAN, None).await.is_err());
+    #[tokio::test]
+    async fn test_unchecked_amount_operations() {
+        crate::init_env().await;
+        let user = crate::db::uniques::DbUserId::from(TEST_USER_ID);
+        let guild = crate::db::uniques::DbGuildId::from(TEST_GUILD_ID);
+        let balances = super::Balances::try_from_user(guild, user).await.unwrap();
+        let mut balances = balances.lock().await;
+        let balances_ = balances.as_mut().unwrap();
+        let balance = balances_.balances
+            .iter_mut()
+            .find(|b| b.curr_name == "test")
+            .unwrap();
+        let money = |s: &str| s.parse::<Money>().unwrap();
+        assert_eq!(balance.amount, money("30"));
 
-        balance.set_amount(0.1, None).await.unwrap(); // Rounding to 2dp check.
-        balance.add_amount(0.2, None).await.unwrap(); // Precision cmp is required here without error margin.
-        assert_eq!(balance.amount, 0.3);
-        balance.sub_amount(0.2, None).await.unwrap();
-        assert_eq!(balance.amount, 0.1);
+        balance.sub_amount_unchecked(money("31"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("-1"));
+        balance.set_amount(money("30"), REASON, None).await.unwrap();
 
-        balance.set_amount(0.111, None).await.unwrap(); // Rounding to 2dp check part 2.
-        assert_eq!(balance.amount, 0.11);
-        balance.add_amount(0.222, None).await.unwrap();
-        assert_eq!(balance.amount, 0.33);
-        balance.sub_amount(0.222, None).await.unwrap();
-        assert_eq!(balance.amount, 0.11);
+        balance.add_amount_unchecked(money("-1"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("29"));
+        balance.sub_amount_unchecked(money("-1"), REASON, None).await.unwrap();
+        assert_eq!(balance.amount, money("30"));
 
-        balance.set_amount(30.0, None).await.ok(); // Reset amount
+        assert!(balance.add_amount_unchecked(Money::MAX, REASON, None).await.is_err()); // overflow check
 
         drop(balances);
     }
 
     #[tokio::test]
-    async fn test_unchecked_amount_operations() {
+    async fn test_bank_operations() {
         crate::init_env().await;
         let user = crate::db::uniques::DbUserId::from(TEST_USER_ID);
         let guild = crate::db::uniques::DbGuildId::from(TEST_GUILD_ID);
@@ -675,24 +1621,183 @@ mod test {
             .iter_mut()
             .find(|b| b.curr_name == "test")
             .unwrap();
-        let error_margin: f64 = f64::EPSILON;
-        assert!(balance.amount - 30.0 < error_margin);
+        let money = |s: &str| s.parse::<Money>().unwrap();
+        balance.set_amount(money("30"), REASON, None).await.unwrap();
+        if !balance.bank.is_zero() {
+            balance.withdraw(balance.bank, REASON, None).await.unwrap();
+            balance.set_amount(money("30"), REASON, None).await.unwrap();
+        }
 
-        balance.add_amount_unchecked(f64::INFINITY, None).await.unwrap();
-        assert!(balance.amount.is_infinite() && balance.amount.is_sign_positive());
-        balance.set_amount(30.0, None).await.unwrap();
-        balance.sub_amount_unchecked(f64::INFINITY, None).await.unwrap();
-        assert!(balance.amount.is_infinite() && balance.amount.is_sign_negative());
-        balance.set_amount(30.0, None).await.unwrap();
+        balance.deposit(money("10"), None, REASON, None).await.unwrap();
+        assert_eq!((balance.amount, balance.bank), (money("20"), money("10")));
+        // Spending only ever touches the wallet.
+        assert!(balance.sub_amount(money("25"), REASON, None).await.is_err());
+        assert!(balance.deposit(money("21"), None, REASON, None).await.is_err());
+        assert!(balance.deposit(money("5"), Some(money("12")), REASON, None).await.is_err());
+        balance.deposit(money("2"), Some(money("12")), REASON, None).await.unwrap();
+        assert_eq!((balance.amount, balance.bank), (money("18"), money("12")));
+
+        assert!(balance.withdraw(money("13"), REASON, None).await.is_err());
+        assert!(balance.withdraw(money("-1"), REASON, None).await.is_err());
+        balance.withdraw(money("12"), REASON, None).await.unwrap();
+        assert_eq!((balance.amount, balance.bank), (money("30"), Money::ZERO));
+
+        drop(balances);
+    }
 
-        balance.add_amount_unchecked(-1.0, None).await.unwrap();
-        assert!(balance.amount - 29.0 < error_margin);
-        balance.sub_amount_unchecked(-1.0, None).await.unwrap();
-        assert!(balance.amount - 30.0 < error_margin);
+    #[tokio::test]
+    async fn test_stale_amount_operations() {
+        crate::init_env().await;
+        let user = crate::db::uniques::DbUserId::from(TEST_USER_ID);
+        let guild = crate::db::uniques::DbGuildId::from(TEST_GUILD_ID);
+        let balances = super::Balances::try_from_user(guild, user).await.unwrap();
+        let mut balances = balances.lock().await;
+        let balances_ = balances.as_mut().unwrap();
+        let balance = balances_.balances
+            .iter_mut()
+            .find(|b| b.curr_name == "test")
+            .unwrap();
+        let money = |s: &str| s.parse::<Money>().unwrap();
+        balance.set_amount(money("30"), REASON, None).await.unwrap();
+
+        // A copy that went stale, like one in a cache of another process.
+        let mut stale = super::Balance {
+            guild_id: balance.guild_id,
+            user_id: balance.user_id,
+            curr_name: balance.curr_name.clone(),
+            amount: balance.amount,
+            bank: balance.bank,
+        };
+        balance.sub_amount(money("20"), REASON, None).await.unwrap();
+        assert_eq!(stale.amount, money("30"));
 
-        assert!(balance.add_amount_unchecked(f64::NAN, None).await.is_err()); // NaN check
-        assert!(balance.sub_amount_unchecked(f64::NAN, None).await.is_err());
+        // The database decides, and the stale copy is brought up to date either way.
+        assert!(stale.sub_amount(money("20"), REASON, None).await.is_err());
+        assert_eq!(stale.amount, money("10"));
+        stale.add_amount(money("5"), REASON, None).await.unwrap();
+        assert_eq!(stale.amount, money("15"));
 
+        balance.set_amount(money("30"), REASON, None).await.unwrap(); // Reset amount
         drop(balances);
     }
+
+    #[tokio::test]
+    async fn test_apply_interest() {
+        crate::init_env().await;
+        let guild = crate::db::uniques::DbGuildId::from(TEST_GUILD_ID);
+        let curr_name = "interest test";
+        let rich = crate::db::uniques::DbUserId::from(1_u64);
+        let poor = crate::db::uniques::DbUserId::from(2_u64);
+        let money = |s: &str| s.parse::<Money>().unwrap();
+        let set_amount_of = |user, amount| async move {
+            let balances = super::Balances::try_from_user(guild, user).await.unwrap();
+            let mut balances = balances.lock().await;
+            let balances_ = balances.as_mut().unwrap();
+            let balance = balances_.ensure_has_currency(curr_name.into()).await.unwrap();
+            balance.set_amount(amount, REASON, None).await.unwrap();
+            drop(balances);
+        };
+        let amount_of = |user| async move {
+            let balances = super::Balances::try_from_user(guild, user).await.unwrap();
+            let balances = balances.lock().await;
+            let amount = balances
+                .as_ref()
+                .unwrap()
+                .balances.iter()
+                .find(|b| b.curr_name == curr_name)
+                .map_or(Money::ZERO, super::Balance::amount);
+            drop(balances);
+            amount
+        };
+
+        set_amount_of(rich, money("100.55")).await;
+        set_amount_of(poor, money("5")).await;
+
+        // 1.5% of 100.55 is 1.50825, which truncates to 1.50 at 2 decimal places.
+        let changed = super::Balances::apply_interest(
+            guild,
+            curr_name,
+            super::Pocket::Wallet,
+            0.015,
+            money("10"),
+            2,
+            REASON
+        ).await.unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(amount_of(rich).await, money("102.05"));
+        assert_eq!(amount_of(poor).await, money("5")); // Below the threshold.
+
+        // 10% of 102.05 is 10.205, which truncates to 10 with no decimal places.
+        super::Balances::apply_interest(
+            guild,
+            curr_name,
+            super::Pocket::Wallet,
+            -0.1,
+            Money::ZERO,
+            0,
+            REASON
+        ).await.unwrap();
+        assert_eq!(amount_of(rich).await, money("92.05"));
+
+        assert!(
+            super::Balances::apply_interest(
+                guild,
+                curr_name,
+                super::Pocket::Wallet,
+                -1.5,
+                Money::ZERO,
+                2,
+                REASON
+            ).await.is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_interest_large_balances() {
+        crate::init_env().await;
+        let guild = crate::db::uniques::DbGuildId::from(TEST_GUILD_ID);
+        let curr_name = "large interest test";
+        let user = crate::db::uniques::DbUserId::from(1_u64);
+        let set_amount = |amount| async move {
+            let balances = super::Balances::try_from_user(guild, user).await.unwrap();
+            let mut balances = balances.lock().await;
+            let balances_ = balances.as_mut().unwrap();
+            let balance = balances_.ensure_has_currency(curr_name.into()).await.unwrap();
+            balance.set_amount(amount, REASON, None).await.unwrap();
+            drop(balances);
+        };
+        let amount = || async move {
+            let balances = super::Balances::try_from_user(guild, user).await.unwrap();
+            let balances = balances.lock().await;
+            let amount = balances
+                .as_ref()
+                .unwrap()
+                .balances.iter()
+                .find(|b| b.curr_name == curr_name)
+                .map_or(Money::ZERO, super::Balance::amount);
+            drop(balances);
+            amount
+        };
+        let double = || {
+            super::Balances::apply_interest(
+                guild,
+                curr_name,
+                super::Pocket::Wallet,
+                1.0,
+                Money::ZERO,
+                MONEY_SCALE,
+                REASON
+            )
+        };
+
+        // 2^53 + 1 is the first whole number that a double cannot hold.
+        set_amount(Money::from_minor(9_007_199_254_740_993)).await;
+        double().await.unwrap();
+        assert_eq!(amount().await, Money::from_minor(18_014_398_509_481_986));
+
+        // Doubling this does not fit, so it stops at the largest amount instead of failing.
+        set_amount(Money::from_minor(i64::MAX / 2 + 1)).await;
+        assert_eq!(double().await.unwrap(), 1);
+        assert_eq!(amount().await, Money::MAX);
+    }
 }
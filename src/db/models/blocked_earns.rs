@@ -0,0 +1,170 @@
+//! This module contains the `BlockedEarns` struct, which counts how many times each anti-farming
+//! rule of a currency stopped a message from earning it, so staff can see what the rules do.
+
+use anyhow::Result;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::doc,
+    options::{ IndexOptions, UpdateOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+
+use crate::db::{ uniques::DbGuildId, CLIENT };
+
+/// A rule that can stop a message from earning currency even though it is not on cooldown.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum FarmingRule {
+    /// The message was shorter than the minimum length.
+    MinLength,
+    /// The message was the same, or nearly the same, as the previous one of the member.
+    Repeated,
+    /// The account of the member was too new.
+    AccountAge,
+    /// The member joined the server too recently.
+    MemberAge,
+    /// The message was nothing but emojis and links.
+    EmojiOrLinkOnly,
+}
+
+impl FarmingRule {
+    pub const ALL: [Self; 5] = [
+        Self::MinLength,
+        Self::Repeated,
+        Self::AccountAge,
+        Self::MemberAge,
+        Self::EmojiOrLinkOnly,
+    ];
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::MinLength => "Message too short",
+            Self::Repeated => "Repeated message",
+            Self::AccountAge => "Account too new",
+            Self::MemberAge => "Joined too recently",
+            Self::EmojiOrLinkOnly => "Only emojis or links",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct BlockedEarns {
+    guild_id: DbGuildId,
+    curr_name: String,
+    rule: FarmingRule,
+    count: i64,
+}
+
+impl BlockedEarns {
+    /// Counts one more earn of a currency that a rule blocked.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn increment(guild_id: DbGuildId, curr_name: &str, rule: FarmingRule) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("blockedEarns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": curr_name,
+            "Rule": mongodb::bson::to_bson(&rule)?,
+        };
+        let updatedoc = doc! {
+            "$inc": {
+                "Count": 1_i64,
+            }
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        coll.update_one(filterdoc, updatedoc, options).await?;
+        Ok(())
+    }
+
+    /// Gets the counts of every rule that has blocked an earn of a currency.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_currency(guild_id: DbGuildId, curr_name: &str) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("blockedEarns");
+
+        let filterdoc = doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": curr_name,
+        };
+        Ok(coll.find(filterdoc, None).await?.try_collect().await?)
+    }
+
+    /// Updates the currency name of every count in a guild for the old currency name.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("blockedEarns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": old_name,
+        };
+        let updatedoc = doc! {
+            "$set": {
+                "CurrName": new_name,
+            }
+        };
+        if let Some(s) = session {
+            coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_many(filterdoc, updatedoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the unique index that keeps `increment` from making two counts for the same rule.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("blockedEarns");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "CurrName": 1, "Rule": 1 })
+            .options(
+                IndexOptions::builder().name("GuildCurrencyRule".to_owned()).unique(true).build()
+            )
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn guild_id(&self) -> DbGuildId {
+        self.guild_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn curr_name(&self) -> &str {
+        &self.curr_name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn rule(&self) -> FarmingRule {
+        self.rule
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn count(&self) -> i64 {
+        self.count
+    }
+}
@@ -0,0 +1,520 @@
+//! This module contains the structs behind the `/daily` and `/weekly` commands.
+//!
+//! `ClaimConfig` is what a guild set up as the reward for each kind of claim, and lives in the
+//! `claimConfigs` collection. There is at most one per guild for each kind.
+//!
+//! `ClaimStreak` is what a member has done so far, and lives in the `claimStreaks` collection.
+//! It is both the cooldown, since it remembers when the member last claimed, and the streak, which
+//! counts how many times in a row the member claimed without letting the grace period run out.
+//! Since it is stored in the database, cooldowns survive restarts.
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Duration, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime },
+    options::{ IndexOptions, ReplaceOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+use serde_with::{ serde_as, DurationSeconds };
+
+use crate::{
+    db::{ uniques::{ DbGuildId, DbUserId }, CLIENT },
+    util::money::Money,
+};
+
+/// Which command a claim belongs to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimKind {
+    Daily,
+    Weekly,
+}
+
+impl ClaimKind {
+    /// How long a member has to wait between claims.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn cooldown(self) -> Duration {
+        match self {
+            Self::Daily => Duration::days(1),
+            Self::Weekly => Duration::weeks(1),
+        }
+    }
+
+    /// The name of the kind, which is also how it is stored in the database.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+}
+
+impl std::str::FromStr for ClaimKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            _ => Err(anyhow!("Unknown claim kind {s}.")),
+        }
+    }
+}
+
+/// What a member gets for claiming, before the streak multiplier.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "Type", rename_all_fields = "PascalCase")]
+pub enum ClaimPayout {
+    /// A random amount of a currency between `min` and `max`, inclusive.
+    Currency {
+        curr_name: String,
+        min: Money,
+        max: Money,
+    },
+    /// A fixed amount of an item.
+    Item {
+        item_name: String,
+        amount: i64,
+    },
+    /// A number of rolls on a drop table.
+    DropTable {
+        drop_table_name: String,
+        count: i64,
+    },
+}
+
+impl ClaimPayout {
+    /// Checks that the amounts of the payout make sense.
+    ///
+    /// # Errors
+    /// - Any amount is not positive, or the minimum is more than the maximum.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Currency { min, max, .. } => {
+                if *min <= Money::ZERO {
+                    bail!("The minimum amount must be more than 0.");
+                }
+                if min > max {
+                    bail!("The minimum amount cannot be more than the maximum amount.");
+                }
+            }
+            Self::Item { amount: count, .. } | Self::DropTable { count, .. } => {
+                if *count <= 0 {
+                    bail!("The amount must be more than 0.");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct ClaimConfig {
+    guild_id: DbGuildId,
+    kind: ClaimKind,
+    payout: ClaimPayout,
+    /// How much the multiplier goes up for every claim in a row after the first, as a fraction.
+    /// With `0.1` the second claim in a row gets 1.1 times the payout, the third 1.2 times and so
+    /// on.
+    streak_bonus: f64,
+    /// The streak after which the multiplier stops going up.
+    max_streak: i64,
+    /// How long after the cooldown is over a member can still claim without losing their streak.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    grace: Duration,
+}
+
+impl ClaimConfig {
+    /// Sets the reward of a kind of claim in a guild, replacing the one that was there before.
+    /// Streaks are kept.
+    ///
+    /// # Errors
+    /// - The payout is invalid.
+    /// - The streak bonus or grace period is negative, or the max streak is not positive.
+    /// - Any `MongoDB` error occurs.
+    pub async fn set(
+        guild_id: DbGuildId,
+        kind: ClaimKind,
+        payout: ClaimPayout,
+        streak_bonus: f64,
+        max_streak: i64,
+        grace: Duration
+    ) -> Result<Self> {
+        payout.validate()?;
+        if !streak_bonus.is_finite() || streak_bonus < 0.0 {
+            bail!("The streak bonus cannot be negative.");
+        }
+        if max_streak < 1 {
+            bail!("The max streak must be at least 1.");
+        }
+        if grace < Duration::zero() {
+            bail!("The grace period cannot be negative.");
+        }
+        let new_self = Self {
+            guild_id,
+            kind,
+            payout,
+            streak_bonus,
+            max_streak,
+            grace,
+        };
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimConfigs");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Kind": kind.as_str() };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one(filterdoc, &new_self, options).await?;
+        Ok(new_self)
+    }
+
+    /// Gets the reward of a kind of claim in a guild.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_from_kind(guild_id: DbGuildId, kind: ClaimKind) -> Result<Option<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimConfigs");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Kind": kind.as_str() };
+        Ok(coll.find_one(filterdoc, None).await?)
+    }
+
+    /// Gets the rewards of every kind of claim in a guild.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_guild(guild_id: DbGuildId) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimConfigs");
+
+        let res = coll.find(doc! { "GuildId": guild_id.as_i64() }, None).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Removes the reward, so that the claim command can not be used in the guild anymore.
+    /// Streaks are kept in case it is set up again.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn delete(self) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimConfigs");
+
+        let filterdoc = doc! { "GuildId": self.guild_id.as_i64(), "Kind": self.kind.as_str() };
+        coll.delete_one(filterdoc, None).await?;
+        Ok(())
+    }
+
+    /// Updates the currency name of every claim reward in a guild paying out the old currency.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        Self::bulk_update_payout_name(
+            guild_id,
+            "Currency",
+            "CurrName",
+            old_name,
+            new_name,
+            session
+        ).await
+    }
+
+    /// Updates the item name of every claim reward in a guild giving out the old item.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_item_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        Self::bulk_update_payout_name(
+            guild_id,
+            "Item",
+            "ItemName",
+            old_name,
+            new_name,
+            session
+        ).await
+    }
+
+    async fn bulk_update_payout_name(
+        guild_id: DbGuildId,
+        payout_type: &str,
+        field: &str,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimConfigs");
+
+        let field = format!("Payout.{field}");
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "Payout.Type": payout_type,
+            &field: old_name,
+        };
+        let updatedoc = doc! {
+            "$set": {
+                &field: new_name,
+            }
+        };
+        if let Some(s) = session {
+            coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_many(filterdoc, updatedoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// How many times the payout is multiplied by for a claim that makes a streak of `streak`.
+    #[allow(clippy::must_use_candidate)]
+    #[allow(clippy::cast_precision_loss)] // Streaks are nowhere near big enough to lose precision.
+    pub fn multiplier(&self, streak: i64) -> f64 {
+        let bonus_claims = streak.min(self.max_streak).max(1) - 1;
+        self.streak_bonus.mul_add(bonus_claims as f64, 1.0)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn guild_id(&self) -> DbGuildId {
+        self.guild_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn kind(&self) -> ClaimKind {
+        self.kind
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn payout(&self) -> &ClaimPayout {
+        &self.payout
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn streak_bonus(&self) -> f64 {
+        self.streak_bonus
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn max_streak(&self) -> i64 {
+        self.max_streak
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn grace(&self) -> Duration {
+        self.grace
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct ClaimStreak {
+    guild_id: DbGuildId,
+    user_id: DbUserId,
+    kind: ClaimKind,
+    /// How many times in a row the member has claimed.
+    streak: i64,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    last_claim: DateTime<Utc>,
+}
+
+impl ClaimStreak {
+    /// Gets the streak of a member for a kind of claim, if they ever claimed it.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_from_user(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        kind: ClaimKind,
+        session: Option<&mut ClientSession>
+    ) -> Result<Option<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimStreaks");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "Kind": kind.as_str(),
+        };
+        if let Some(s) = session {
+            Ok(coll.find_one_with_session(filterdoc, None, s).await?)
+        } else {
+            Ok(coll.find_one(filterdoc, None).await?)
+        }
+    }
+
+    /// Works out what the streak of a member becomes if they claim at `now`, given their streak
+    /// so far. Claiming within the grace period after the cooldown is over continues the streak,
+    /// claiming any later starts a new one.
+    ///
+    /// # Errors
+    /// If the member is still on cooldown, with the time the cooldown is over.
+    pub fn next_streak(
+        prev: Option<&Self>,
+        kind: ClaimKind,
+        grace: Duration,
+        now: DateTime<Utc>
+    ) -> Result<i64, DateTime<Utc>> {
+        let Some(prev) = prev else {
+            return Ok(1);
+        };
+        let available = prev.last_claim + kind.cooldown();
+        if now < available {
+            return Err(available);
+        }
+        if now <= available + grace {
+            Ok(prev.streak.saturating_add(1))
+        } else {
+            Ok(1)
+        }
+    }
+
+    /// Records a claim made at `now`, replacing `prev`.
+    ///
+    /// This only succeeds if the streak in the database is still `prev`, so a member that manages
+    /// to claim twice at the same time only gets through once.
+    ///
+    /// # Errors
+    /// - The member claimed in the meantime.
+    /// - Any `MongoDB` error occurs.
+    pub async fn record(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        kind: ClaimKind,
+        prev: Option<&Self>,
+        streak: i64,
+        now: DateTime<Utc>,
+        session: Option<&mut ClientSession>
+    ) -> Result<Self> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimStreaks");
+
+        let new_self = Self {
+            guild_id,
+            user_id,
+            kind,
+            streak,
+            last_claim: now,
+        };
+        let Some(prev) = prev else {
+            // The unique index makes a second insert fail.
+            if let Some(s) = session {
+                coll.insert_one_with_session(&new_self, None, s).await?;
+            } else {
+                coll.insert_one(&new_self, None).await?;
+            }
+            return Ok(new_self);
+        };
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "Kind": kind.as_str(),
+            "LastClaim": mongodb::bson::DateTime::from_chrono(prev.last_claim),
+        };
+        let res = if let Some(s) = session {
+            coll.replace_one_with_session(filterdoc, &new_self, None, s).await?
+        } else {
+            coll.replace_one(filterdoc, &new_self, None).await?
+        };
+        if res.matched_count == 0 {
+            bail!("You already claimed this.");
+        }
+        Ok(new_self)
+    }
+
+    /// Creates the index that keeps one streak per member per kind of claim.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("claimStreaks");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "UserId": 1, "Kind": 1 })
+            .options(
+                IndexOptions::builder().name("GuildUserKind".to_owned()).unique(true).build()
+            )
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn streak(&self) -> i64 {
+        self.streak
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn last_claim(&self) -> DateTime<Utc> {
+        self.last_claim
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn streak(streak: i64, last_claim: DateTime<Utc>) -> ClaimStreak {
+        ClaimStreak {
+            guild_id: DbGuildId::from(1_u64),
+            user_id: DbUserId::from(1_u64),
+            kind: ClaimKind::Daily,
+            streak,
+            last_claim,
+        }
+    }
+
+    #[test]
+    fn test_next_streak() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let grace = Duration::hours(12);
+        let next = |prev: Option<&ClaimStreak>, hours| {
+            ClaimStreak::next_streak(prev, ClaimKind::Daily, grace, start + Duration::hours(hours))
+        };
+        assert_eq!(next(None, 0), Ok(1));
+        let prev = streak(3, start);
+        assert_eq!(next(Some(&prev), 23), Err(start + Duration::days(1)));
+        assert_eq!(next(Some(&prev), 24), Ok(4));
+        assert_eq!(next(Some(&prev), 36), Ok(4));
+        assert_eq!(next(Some(&prev), 37), Ok(1));
+    }
+
+    #[test]
+    fn test_multiplier() {
+        let config = ClaimConfig {
+            guild_id: DbGuildId::from(1_u64),
+            kind: ClaimKind::Weekly,
+            payout: ClaimPayout::Item { item_name: "test".to_owned(), amount: 1 },
+            streak_bonus: 0.25,
+            max_streak: 3,
+            grace: Duration::zero(),
+        };
+        assert!((config.multiplier(1) - 1.0).abs() < f64::EPSILON);
+        assert!((config.multiplier(2) - 1.25).abs() < f64::EPSILON);
+        assert!((config.multiplier(3) - 1.5).abs() < f64::EPSILON);
+        assert!((config.multiplier(10) - 1.5).abs() < f64::EPSILON);
+    }
+}
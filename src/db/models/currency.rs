@@ -19,9 +19,10 @@
 //! and also a configurable timeout between earning currency. This is to prevent spamming and to make it more
 //! fair for everyone.
 pub mod builder;
+pub mod multiplier;
 pub mod name_updates_handler;
 
-use std::{ num::NonZeroUsize, sync::Arc };
+use std::{ num::NonZeroUsize, sync::{ atomic::{ AtomicU64, Ordering }, Arc } };
 
 use anyhow::{ anyhow, bail, Result };
 use chrono::Duration;
@@ -39,9 +40,13 @@ use serenity::model::prelude::RoleId;
 use thiserror::Error;
 use tokio::sync::{ Mutex, RwLock, RwLockWriteGuard };
 
+use crate::db::models::earn_cooldown::EarnCooldown;
+use crate::db::models::role_income::RoleIncome;
 use crate::db::models::ToKVs;
 use crate::db::uniques::CurrencyNameRef;
 use crate::db::CLIENT;
+use crate::mechanics::interest;
+use crate::util::money::{ Money, MONEY_SCALE };
 use crate::db::{
     uniques::DbChannelId,
     uniques::DbGuildId,
@@ -50,6 +55,7 @@ use crate::db::{
     TokioMutexCache,
 };
 
+use self::multiplier::{ EarnMultiplier, MultiplierStacking };
 use self::name_updates_handler::handle_name_updates;
 
 #[derive(Debug, Error)]
@@ -97,6 +103,9 @@ pub struct Currency {
     base_value: Option<f64>,
     /// Whether this currency can be paid to members by other members via the pay command.
     pay: bool,
+    /// Whether members can wager this currency in the gambling games.
+    #[serde(default = "default_true")]
+    gamble: bool,
     /// Whether this currency can be earned by members via chatting in the server.
     earn_by_chat: bool,
     /// If the channels list is in whitelist mode or blacklist mode.
@@ -112,20 +121,128 @@ pub struct Currency {
     /// The list of roles that this currency cannot be earned by (ACTIVE ONLY WITH BLACKLIST).
     roles_blacklist: Vec<DbRoleId>,
     /// The minimum amount of currency that may be earned per message assuming earn_by_chat is true.
-    earn_min: f64,
+    earn_min: Money,
     /// The maximum amount of currency that may be earned per message assuming earn_by_chat is true.
-    earn_max: f64,
+    earn_max: Money,
     /// The amount of time in seconds that must pass before a member can earn currency again via a chat message.
     #[serde_as(as = "DurationSeconds<i64>")]
     earn_timeout: Duration,
+    /// How many decimal places amounts of this currency have, at most `MONEY_SCALE`.
+    /// Currencies made before this existed did not have it, so they get the 2 they always had.
+    #[serde(default = "default_precision")]
+    precision: u8,
+    /// The percentage of every balance that is added to it every `interest_interval`. If it is
+    /// negative it is taken away instead, as a wealth tax. `None` turns both off.
+    #[serde(default)]
+    interest_rate: Option<f64>,
+    /// How often interest is applied.
+    #[serde(default = "default_interest_interval")]
+    #[serde_as(as = "DurationSeconds<i64>")]
+    interest_interval: Duration,
+    /// Balances below this amount do not earn interest or pay the tax.
+    #[serde(default)]
+    interest_threshold: Money,
+    /// Whether members can keep some of this currency in a bank. Buying, exchanging and staff
+    /// taking only ever touch the rest, the wallet, and if there is interest it is only paid on
+    /// what is in the bank.
+    #[serde(default)]
+    bank: bool,
+    /// The most a member can deposit into the bank, `None` means there is no limit. Interest can
+    /// still take the bank past it.
+    #[serde(default)]
+    bank_capacity: Option<Money>,
+    /// Whether this currency can be earned by members via spending time in voice channels.
+    #[serde(default)]
+    earn_by_voice: bool,
+    /// The amount of currency earned per whole minute spent in a voice channel, assuming
+    /// `earn_by_voice` is true.
+    #[serde(default)]
+    voice_rate: Money,
+    /// Whether members that are muted, by themselves or by staff, do not earn by voice.
+    #[serde(default = "default_true")]
+    voice_exclude_muted: bool,
+    /// Whether members that are deafened, by themselves or by staff, do not earn by voice.
+    #[serde(default = "default_true")]
+    voice_exclude_deafened: bool,
+    /// Whether members that are the only non-bot in their voice channel do not earn by voice.
+    #[serde(default = "default_true")]
+    voice_exclude_alone: bool,
+    /// Whether members in the AFK channel of the server do not earn by voice.
+    #[serde(default = "default_true")]
+    voice_exclude_afk: bool,
+    /// Multipliers on what is earned by chatting for having a role or chatting in a channel.
+    #[serde(default)]
+    earn_multipliers: Vec<EarnMultiplier>,
+    /// How the multipliers that apply to a member at once are combined.
+    #[serde(default)]
+    multiplier_stacking: MultiplierStacking,
+    /// Messages with fewer characters than this do not earn currency.
+    #[serde(default)]
+    min_message_length: u32,
+    /// Whether messages that are the same, or nearly the same, as the previous message of the
+    /// member do not earn currency.
+    #[serde(default)]
+    ignore_repeated_messages: bool,
+    /// How old the Discord account of a member must be for them to earn by chatting.
+    #[serde(default = "Duration::zero")]
+    #[serde_as(as = "DurationSeconds<i64>")]
+    min_account_age: Duration,
+    /// How long a member must have been in the server for them to earn by chatting.
+    #[serde(default = "Duration::zero")]
+    #[serde_as(as = "DurationSeconds<i64>")]
+    min_member_age: Duration,
+    /// Whether messages that are nothing but emojis and links do not earn currency.
+    #[serde(default)]
+    ignore_emoji_and_link_only: bool,
+}
+
+const fn default_precision() -> u8 {
+    2
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+const fn default_interest_interval() -> Duration {
+    Duration::days(1)
+}
+
+fn validate_interest_rate(rate: Option<f64>) -> Result<()> {
+    if let Some(rate) = rate {
+        if !rate.is_finite() || rate < -100.0 {
+            bail!("Interest rate must be a percentage of at least -100.");
+        }
+    }
+    Ok(())
+}
+
+fn validate_interest_interval(interval: Duration) -> Result<()> {
+    if interval.num_seconds() < interest::MIN_INTERVAL_SECONDS {
+        bail!("Interest interval must be at least {} seconds.", interest::MIN_INTERVAL_SECONDS);
+    }
+    Ok(())
 }
 
 lazy_static! {
     // Need me that concurrency.
     static ref CACHE_CURRENCY: TokioMutexCache<(DbGuildId, String), ArcTokioRwLockOption<Currency>> =
         Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap()));
+    // The names of the currencies of each guild. Only the names are kept, and the currencies
+    // themselves are always looked up in the cache above, since it may drop a currency and fetch a
+    // new copy of it at any time. Always lock the cache above first if both are needed.
+    static ref CACHE_GUILD_CURRENCIES: TokioMutexCache<DbGuildId, Arc<[String]>> =
+        Mutex::new(LruCache::new(NonZeroUsize::new(1000).unwrap()));
 }
 
+/// How many times a currency has been created, renamed or deleted, so that `try_from_guild` can
+/// tell whether what it fetched is still up to date. Only touched while holding the lock on
+/// `CACHE_CURRENCY`.
+static GUILD_CURRENCIES_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+/// Every currency of a guild, cheap to clone.
+pub type GuildCurrencies = Arc<[ArcTokioRwLockOption<Currency>]>;
+
 impl Currency {
     /// Attempts to fetch a Currency object from the database given a guild id and a currency name.
     ///
@@ -173,30 +290,124 @@ impl Currency {
         return_val
     }
 
+    /// Looks up how many decimal places a currency has, for when an amount of it needs to be
+    /// truncated but the currency itself is not needed.
+    ///
+    /// # Errors
+    /// - If no such currency exists.
+    /// - If the currency is being used in a breaking operation.
+    /// - If any mongodb errors occur.
+    pub async fn precision_from_name(guild_id: DbGuildId, curr_name: String) -> Result<u8> {
+        let currency = Self::try_from_name(guild_id, curr_name.clone()).await?.ok_or_else(||
+            anyhow!("Currency {curr_name} does not exist.")
+        )?;
+        let currency = currency.read().await;
+        let precision = currency
+            .as_ref()
+            .ok_or_else(|| anyhow!("Currency {curr_name} is being used in a breaking operation."))?
+            .precision;
+        drop(currency);
+        Ok(precision)
+    }
+
     /// Attempts to fetch all of the currencies that a guild has made.
     ///
+    /// The names of a guild's currencies are cached, so after the first call this only touches
+    /// the database for the currencies that fell out of the cache `try_from_name` uses, or when a
+    /// currency of the guild is created, renamed or deleted. Every currency is looked up in that
+    /// cache, so the list and `try_from_name` always hand out the same `Arc`.
+    ///
     /// # Errors
     /// - If any mongodb errors occur.
-    pub async fn try_from_guild(guild_id: DbGuildId) -> Result<Vec<ArcTokioRwLockOption<Self>>> {
-        let mut cache = CACHE_CURRENCY.lock().await;
+    pub async fn try_from_guild(guild_id: DbGuildId) -> Result<GuildCurrencies> {
+        loop {
+            let mut cache = CACHE_CURRENCY.lock().await;
+            let names = CACHE_GUILD_CURRENCIES.lock().await.get(&guild_id).cloned();
+
+            let mut currencies = Vec::new();
+            let mut missing = Vec::new();
+            if let Some(names) = &names {
+                for curr_name in names.iter() {
+                    match cache.get(&(guild_id, curr_name.clone())) {
+                        Some(currency) => currencies.push(currency.clone()),
+                        None => missing.push(curr_name.clone()),
+                    }
+                }
+                if missing.is_empty() {
+                    return Ok(currencies.into());
+                }
+            }
+            let changes = GUILD_CURRENCIES_CHANGES.load(Ordering::Relaxed);
+            // Let go of the cache while waiting on the database, since every other currency lookup
+            // needs it too.
+            drop(cache);
+
+            // Boxed, since the cursor makes for a big future and this is awaited by a lot of
+            // commands.
+            let only = names.is_some().then_some(missing.as_slice());
+            let fetched = Box::pin(Self::fetch_guild(guild_id, only)).await?;
+
+            let mut cache = CACHE_CURRENCY.lock().await;
+            // A currency that was created, renamed or deleted in the meantime would leave what was
+            // fetched out of date, so start over.
+            if GUILD_CURRENCIES_CHANGES.load(Ordering::Relaxed) != changes {
+                continue;
+            }
+            let mut fetched_names = Vec::new();
+            for curr in fetched {
+                fetched_names.push(curr.curr_name.clone());
+                let key = (guild_id, curr.curr_name.clone());
+                let currency = cache
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        let tmp = Arc::new(RwLock::new(Some(curr)));
+                        cache.put(key, tmp.clone());
+                        tmp
+                    });
+                currencies.push(currency);
+            }
+            if names.is_none() {
+                CACHE_GUILD_CURRENCIES.lock().await.put(guild_id, fetched_names.into());
+            }
+            drop(cache); // please the linter
+            return Ok(currencies.into());
+        }
+    }
 
+    /// Fetches the currencies of a guild from the database, only the ones with the given names if
+    /// there are any.
+    async fn fetch_guild(
+        guild_id: DbGuildId,
+        curr_names: Option<&[String]>
+    ) -> Result<Vec<Self>> {
         let db = super::super::CLIENT.get().await.database("conebot");
         let coll: Collection<Self> = db.collection("currencies");
-        let filterdoc = doc! {
+        let mut filterdoc = doc! {
             "GuildId": guild_id.as_i64(),
         };
-        let mut res = coll.find(filterdoc, None).await?;
+        if let Some(curr_names) = curr_names {
+            filterdoc.insert("CurrName", doc! { "$in": curr_names });
+        }
+        let res = coll.find(filterdoc, None).await?;
         drop(db); // Drop locks on mutexes as soon as possible.
+        Ok(res.try_collect().await?)
+    }
 
-        let mut currencies = Vec::new();
-        while let Some(curr) = res.try_next().await? {
-            let curr_name = curr.curr_name().to_owned();
-            let tmp = Arc::new(RwLock::new(Some(curr)));
-            currencies.push(tmp.clone());
-            cache.put((guild_id, curr_name.into_string()), tmp);
-        }
-        drop(cache); // please the linter
-        Ok(currencies)
+    /// Drops every currency of a guild from the cache, like `invalidate_cache` does for one, along
+    /// with the list of them.
+    pub async fn invalidate_guild_cache(guild_id: DbGuildId) {
+        crate::db::uncache_rw(&CACHE_CURRENCY, |(g, _)| *g == guild_id).await;
+        let cache = CACHE_CURRENCY.lock().await;
+        Self::forget_guild(guild_id).await;
+        drop(cache);
+    }
+
+    /// Drops the cached list of currencies of a guild, for when one is created, renamed or
+    /// deleted. Must be called while holding the lock on `CACHE_CURRENCY`.
+    async fn forget_guild(guild_id: DbGuildId) {
+        GUILD_CURRENCIES_CHANGES.fetch_add(1, Ordering::Relaxed);
+        CACHE_GUILD_CURRENCIES.lock().await.pop(&guild_id);
     }
 
     #[allow(clippy::must_use_candidate)]
@@ -242,6 +453,11 @@ impl Currency {
         self.pay
     }
 
+    #[allow(clippy::must_use_candidate)]
+    pub const fn gamble(&self) -> bool {
+        self.gamble
+    }
+
     #[allow(clippy::must_use_candidate)]
     pub const fn earn_by_chat(&self) -> bool {
         self.earn_by_chat
@@ -282,12 +498,12 @@ impl Currency {
     }
 
     #[allow(clippy::must_use_candidate)]
-    pub const fn earn_min(&self) -> f64 {
+    pub const fn earn_min(&self) -> Money {
         self.earn_min
     }
 
     #[allow(clippy::must_use_candidate)]
-    pub const fn earn_max(&self) -> f64 {
+    pub const fn earn_max(&self) -> Money {
         self.earn_max
     }
 
@@ -296,9 +512,132 @@ impl Currency {
         self.earn_timeout
     }
 
+    #[allow(clippy::must_use_candidate)]
+    pub const fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn interest_rate(&self) -> Option<f64> {
+        self.interest_rate
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn interest_interval(&self) -> Duration {
+        self.interest_interval
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn interest_threshold(&self) -> Money {
+        self.interest_threshold
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn bank(&self) -> bool {
+        self.bank
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn bank_capacity(&self) -> Option<Money> {
+        self.bank_capacity
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn earn_by_voice(&self) -> bool {
+        self.earn_by_voice
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn voice_rate(&self) -> Money {
+        self.voice_rate
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn voice_exclude_muted(&self) -> bool {
+        self.voice_exclude_muted
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn voice_exclude_deafened(&self) -> bool {
+        self.voice_exclude_deafened
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn voice_exclude_alone(&self) -> bool {
+        self.voice_exclude_alone
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn voice_exclude_afk(&self) -> bool {
+        self.voice_exclude_afk
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    #[inline]
+    pub fn earn_multipliers(&self) -> &[EarnMultiplier] {
+        &self.earn_multipliers
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn multiplier_stacking(&self) -> MultiplierStacking {
+        self.multiplier_stacking
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn min_message_length(&self) -> u32 {
+        self.min_message_length
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn ignore_repeated_messages(&self) -> bool {
+        self.ignore_repeated_messages
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn min_account_age(&self) -> Duration {
+        self.min_account_age
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn min_member_age(&self) -> Duration {
+        self.min_member_age
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn ignore_emoji_and_link_only(&self) -> bool {
+        self.ignore_emoji_and_link_only
+    }
+
+    /// Works out the multiplier on what a member with these roles earns by chatting in a channel
+    /// with this lineage, combining every multiplier that applies according to the stacking mode.
+    #[allow(clippy::must_use_candidate)]
+    pub fn earn_multiplier(&self, roles: &[RoleId], channel_lineage: &[ChannelId]) -> f64 {
+        self.multiplier_stacking.combine(
+            self.earn_multipliers
+                .iter()
+                .filter(|m| m.applies_to(roles, channel_lineage))
+                .map(EarnMultiplier::multiplier)
+        )
+    }
+
+    /// Converts an amount of this currency to the base currency. Returns `None` if this currency
+    /// has no value in terms of the base currency or if the result would be too large.
     #[inline]
-    pub fn as_base(&self, amount: f64) -> Option<f64> {
-        if self.base { Some(amount) } else { self.base_value.map(|base_value| amount * base_value) }
+    pub fn as_base(&self, amount: Money) -> Option<Money> {
+        if self.base {
+            Some(amount)
+        } else {
+            self.base_value.and_then(|base_value| amount.checked_mul_rate(base_value, MONEY_SCALE).ok())
+        }
+    }
+
+    /// Turns a float given by a user into an amount of this currency, truncated to its precision.
+    ///
+    /// # Errors
+    ///
+    /// If the float is NaN, infinite or too large.
+    pub fn amount_from_f64(&self, amount: f64) -> Result<Money> {
+        Money::from_f64(amount, self.precision)
     }
 
     /// Attempts to change the name of this currency.
@@ -397,6 +736,7 @@ impl Currency {
         }
 
         cache.pop(&(self__.guild_id, self__.curr_name.clone()));
+        Self::forget_guild(self__.guild_id).await;
         cache.put((self__.guild_id, new_name), Arc::new(RwLock::new(Some(self__))));
         drop(self_); // please the linter
         drop(cache); // all hail the linter
@@ -604,6 +944,41 @@ impl Currency {
         Ok(())
     }
 
+    /// Updates whether the members can wager the currency in the gambling games.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_gamble(
+        &mut self,
+        new_gamble: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "Gamble": new_gamble,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.gamble = new_gamble;
+
+        Ok(())
+    }
+
     /// Updates whether the members can earn the currency by chatting.
     ///
     /// # Errors
@@ -1224,7 +1599,7 @@ impl Currency {
     /// If any mongodb operation errors.
     pub async fn update_earn_min(
         &mut self,
-        new_earn_min: f64,
+        new_earn_min: Money,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
         let filterdoc =
@@ -1259,7 +1634,7 @@ impl Currency {
     /// If any mongodb operation errors.
     pub async fn update_earn_max(
         &mut self,
-        new_earn_max: f64,
+        new_earn_max: Money,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
         let filterdoc =
@@ -1318,75 +1693,811 @@ impl Currency {
         }
 
         self.earn_timeout = new_earn_timeout;
+        EarnCooldown::forget_local(self.guild_id, &self.curr_name);
 
         Ok(())
     }
 
-    /// Consumes an Arc Mutex to a currency and deletes it from the database. Waits for
-    /// all other references to the currency to be dropped before deleting.
+    /// Updates how many decimal places amounts of this currency have. Amounts that members
+    /// already have are left as they are, only new ones are truncated to the new precision.
     ///
     /// # Errors
     ///
-    /// Returns an error if the currency is already being used in a breaking operation.
-    ///
-    /// # Panics
-    ///
-    /// It should not. This is here to please the linter.
-    pub async fn delete_currency(self_: ArcTokioRwLockOption<Self>) -> Result<()> {
-        let mut cache = CACHE_CURRENCY.lock().await; // Get the cache here so no other task
-        // can get the currency while were working on it.
-        let mut self_ = self_.write().await;
-        let Some(self__) = self_.take() else {
-            return Err(anyhow!("Currency is already being used in a breaking operation."));
+    /// If the precision is more than `MONEY_SCALE`, or any mongodb operation errors.
+    pub async fn update_precision(
+        &mut self,
+        new_precision: u8,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if new_precision > MONEY_SCALE {
+            bail!("Precision cannot be more than {MONEY_SCALE} decimal places.");
+        }
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "Precision": i32::from(new_precision),
+            },
         };
-
-        // Remove the currency from the cache.
-        cache.pop(&(self__.guild_id, self__.curr_name.clone()));
-        // Keep the cache past this point so that another task
-        // will not try to get the currency from the db while we're deleting it.
-
-        // Delete the currency from the database.
         let db = super::super::CLIENT.get().await.database("conebot");
         let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.precision = new_precision;
+
+        Ok(())
+    }
+
+    /// Updates the interest rate, in percent per interval, and schedules or cancels the job that
+    /// applies it. A negative rate is a wealth tax and `None` turns interest off.
+    ///
+    /// # Errors
+    ///
+    /// If the rate would take more than the whole balance, or any mongodb operation errors.
+    pub async fn update_interest_rate(
+        &mut self,
+        new_interest_rate: Option<f64>,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        validate_interest_rate(new_interest_rate)?;
         let filterdoc =
             doc! {
-            "GuildId": self__.guild_id.as_i64(),
-            "CurrName": &self__.curr_name,
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
         };
-        coll.delete_one(filterdoc, None).await?;
+        let updatedoc =
+            doc! {
+            "$set": {
+                "InterestRate": new_interest_rate,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = &mut session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        let was_on = self.interest_rate.is_some();
+        self.interest_rate = new_interest_rate;
+        if was_on != new_interest_rate.is_some() {
+            interest::sync_job(self, session).await?;
+        }
 
-        drop(self_); // please the linter
-        drop(cache); // all hail the linter
         Ok(())
     }
 
-    /// Consumes an `RwLockWriteGuard` to a currency and invalidates it in the cache. Waits for
-    /// all other references to the currency to be dropped before invalidating.
+    /// Updates how often interest is applied. If interest is on, the next time it is applied is
+    /// moved to one new interval from now.
     ///
-    /// Use this in case a transaction fails and you want to invalidate the cache.
-    pub async fn invalidate_cache(mut self_: RwLockWriteGuard<'_, Option<Self>>) -> Result<()> {
-        let mut cache = CACHE_CURRENCY.lock().await;
-        let Some(self__) = self_.take() else {
-            return Err(anyhow!("Currency is already being used in a breaking operation."));
+    /// # Errors
+    ///
+    /// If the interval is shorter than `interest::MIN_INTERVAL_SECONDS`, or any mongodb operation errors.
+    pub async fn update_interest_interval(
+        &mut self,
+        new_interest_interval: Duration,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        validate_interest_interval(new_interest_interval)?;
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "InterestInterval": new_interest_interval.num_seconds(),
+            },
         };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
 
-        cache.pop(&(self__.guild_id, self__.curr_name));
+        if let Some(s) = &mut session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.interest_interval = new_interest_interval;
+        interest::sync_job(self, session).await?;
 
-        drop(self_);
-        drop(cache);
         Ok(())
     }
-}
 
-impl ToKVs for Currency {
-    fn try_to_kvs(&self) -> Result<Vec<(String, String)>> {
-        match serde_json::to_value(self)? {
-            Value::Object(o) =>
-                Ok(
-                    o
-                        .into_iter()
-                        .map(|(k, v)| {
-                            if k == "ChannelsBlacklist" || k == "ChannelsWhitelist" {
+    /// Updates the amount below which balances do not earn interest or pay the tax.
+    ///
+    /// # Errors
+    ///
+    /// If the threshold is negative, or any mongodb operation errors.
+    pub async fn update_interest_threshold(
+        &mut self,
+        new_interest_threshold: Money,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if new_interest_threshold.is_negative() {
+            bail!("Interest threshold cannot be negative.");
+        }
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "InterestThreshold": new_interest_threshold,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = &mut session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.interest_threshold = new_interest_threshold;
+
+        Ok(())
+    }
+
+    /// Updates whether the members can keep the currency in a bank. Turning it off does not empty
+    /// the banks, members can still withdraw what they have in them.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_bank(
+        &mut self,
+        new_bank: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "Bank": new_bank,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.bank = new_bank;
+
+        Ok(())
+    }
+
+    /// Updates the most a member can keep in the bank, `None` for no limit. Members that already
+    /// have more than that in the bank keep it, but cannot deposit more.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity is negative, or any mongodb operation errors.
+    pub async fn update_bank_capacity(
+        &mut self,
+        new_bank_capacity: Option<Money>,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if new_bank_capacity.is_some_and(Money::is_negative) {
+            bail!("Bank capacity cannot be negative.");
+        }
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "BankCapacity": new_bank_capacity,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.bank_capacity = new_bank_capacity;
+
+        Ok(())
+    }
+
+    /// Updates whether the members can earn the currency by spending time in voice channels.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_earn_by_voice(
+        &mut self,
+        new_earn_by_voice: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "EarnByVoice": new_earn_by_voice,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.earn_by_voice = new_earn_by_voice;
+
+        Ok(())
+    }
+
+    /// Updates the amount of currency earned per minute in a voice channel.
+    ///
+    /// # Errors
+    ///
+    /// If the rate is negative, or any mongodb operation errors.
+    pub async fn update_voice_rate(
+        &mut self,
+        new_voice_rate: Money,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if new_voice_rate.is_negative() {
+            bail!("Voice rate cannot be negative.");
+        }
+        let new_voice_rate = new_voice_rate.truncate(self.precision);
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "VoiceRate": new_voice_rate,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.voice_rate = new_voice_rate;
+
+        Ok(())
+    }
+
+    /// Updates whether muted members are left out of earning by voice.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_voice_exclude_muted(
+        &mut self,
+        new_voice_exclude_muted: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "VoiceExcludeMuted": new_voice_exclude_muted,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.voice_exclude_muted = new_voice_exclude_muted;
+
+        Ok(())
+    }
+
+    /// Updates whether deafened members are left out of earning by voice.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_voice_exclude_deafened(
+        &mut self,
+        new_voice_exclude_deafened: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "VoiceExcludeDeafened": new_voice_exclude_deafened,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.voice_exclude_deafened = new_voice_exclude_deafened;
+
+        Ok(())
+    }
+
+    /// Updates whether members alone in their voice channel are left out of earning by voice.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_voice_exclude_alone(
+        &mut self,
+        new_voice_exclude_alone: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "VoiceExcludeAlone": new_voice_exclude_alone,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.voice_exclude_alone = new_voice_exclude_alone;
+
+        Ok(())
+    }
+
+    /// Updates whether members in the AFK channel are left out of earning by voice.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_voice_exclude_afk(
+        &mut self,
+        new_voice_exclude_afk: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "VoiceExcludeAfk": new_voice_exclude_afk,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.voice_exclude_afk = new_voice_exclude_afk;
+
+        Ok(())
+    }
+
+    /// Sets the multiplier for a role or channel, replacing the one it had before if any.
+    ///
+    /// # Errors
+    ///
+    /// If the multiplier is invalid, or any mongodb operation errors.
+    pub async fn set_earn_multiplier(
+        &mut self,
+        multiplier: EarnMultiplier,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        multiplier.validate()?;
+        let mut multipliers = self.earn_multipliers.clone();
+        multipliers.retain(|m| !m.same_target(&multiplier));
+        multipliers.push(multiplier);
+        self.overwrite_earn_multipliers(multipliers, session).await
+    }
+
+    /// Removes the multiplier of the same role or channel as the one given.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors, or if the role or channel has no multiplier.
+    pub async fn remove_earn_multiplier(
+        &mut self,
+        multiplier: EarnMultiplier,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let mut multipliers = self.earn_multipliers.clone();
+        multipliers.retain(|m| !m.same_target(&multiplier));
+        if multipliers.len() == self.earn_multipliers.len() {
+            bail!("There is no multiplier for that.");
+        }
+        self.overwrite_earn_multipliers(multipliers, session).await
+    }
+
+    /// Overwrites the multipliers with the given ones.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn overwrite_earn_multipliers(
+        &mut self,
+        new_earn_multipliers: Vec<EarnMultiplier>,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "EarnMultipliers": mongodb::bson::to_bson(&new_earn_multipliers)?,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.earn_multipliers = new_earn_multipliers;
+
+        Ok(())
+    }
+
+    /// Updates how the multipliers that apply to a member at once are combined.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_multiplier_stacking(
+        &mut self,
+        new_multiplier_stacking: MultiplierStacking,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "MultiplierStacking": new_multiplier_stacking.as_str(),
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.multiplier_stacking = new_multiplier_stacking;
+
+        Ok(())
+    }
+
+    /// Updates the minimum length of messages that earn the currency.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_min_message_length(
+        &mut self,
+        new_min_message_length: u32,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "MinMessageLength": new_min_message_length,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.min_message_length = new_min_message_length;
+
+        Ok(())
+    }
+
+    /// Updates whether repeated messages are kept from earning the currency.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_ignore_repeated_messages(
+        &mut self,
+        new_ignore_repeated_messages: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "IgnoreRepeatedMessages": new_ignore_repeated_messages,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.ignore_repeated_messages = new_ignore_repeated_messages;
+
+        Ok(())
+    }
+
+    /// Updates how old an account must be to earn the currency by chatting.
+    ///
+    /// # Errors
+    ///
+    /// If the age is negative, or any mongodb operation errors.
+    pub async fn update_min_account_age(
+        &mut self,
+        new_min_account_age: Duration,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if new_min_account_age < Duration::zero() {
+            bail!("Minimum account age cannot be negative.");
+        }
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "MinAccountAge": new_min_account_age.num_seconds(),
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.min_account_age = new_min_account_age;
+
+        Ok(())
+    }
+
+    /// Updates how long a member must have been in the server to earn the currency by chatting.
+    ///
+    /// # Errors
+    ///
+    /// If the age is negative, or any mongodb operation errors.
+    pub async fn update_min_member_age(
+        &mut self,
+        new_min_member_age: Duration,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if new_min_member_age < Duration::zero() {
+            bail!("Minimum member age cannot be negative.");
+        }
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "MinMemberAge": new_min_member_age.num_seconds(),
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.min_member_age = new_min_member_age;
+
+        Ok(())
+    }
+
+    /// Updates whether messages of only emojis and links are kept from earning the currency.
+    ///
+    /// # Errors
+    ///
+    /// If any mongodb operation errors.
+    pub async fn update_ignore_emoji_and_link_only(
+        &mut self,
+        new_ignore_emoji_and_link_only: bool,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "CurrName": &self.curr_name,
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "IgnoreEmojiAndLinkOnly": new_ignore_emoji_and_link_only,
+            },
+        };
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+
+        if let Some(s) = session {
+            coll.update_one_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_one(filterdoc, updatedoc, None).await?;
+        }
+
+        self.ignore_emoji_and_link_only = new_ignore_emoji_and_link_only;
+
+        Ok(())
+    }
+
+    /// Consumes an Arc Mutex to a currency and deletes it from the database. Waits for
+    /// all other references to the currency to be dropped before deleting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the currency is already being used in a breaking operation.
+    ///
+    /// # Panics
+    ///
+    /// It should not. This is here to please the linter.
+    pub async fn delete_currency(self_: ArcTokioRwLockOption<Self>) -> Result<()> {
+        let mut cache = CACHE_CURRENCY.lock().await; // Get the cache here so no other task
+        // can get the currency while were working on it.
+        let mut self_ = self_.write().await;
+        let Some(self__) = self_.take() else {
+            return Err(anyhow!("Currency is already being used in a breaking operation."));
+        };
+
+        // Remove the currency from the cache.
+        cache.pop(&(self__.guild_id, self__.curr_name.clone()));
+        Self::forget_guild(self__.guild_id).await;
+        // Keep the cache past this point so that another task
+        // will not try to get the currency from the db while we're deleting it.
+
+        // Delete the currency from the database.
+        let db = super::super::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("currencies");
+        let filterdoc =
+            doc! {
+            "GuildId": self__.guild_id.as_i64(),
+            "CurrName": &self__.curr_name,
+        };
+        coll.delete_one(filterdoc, None).await?;
+        interest::cancel_job(self__.guild_id, &self__.curr_name, None).await?;
+        RoleIncome::delete_for_currency(self__.guild_id, &self__.curr_name, None).await?;
+
+        drop(self_); // please the linter
+        drop(cache); // all hail the linter
+        Ok(())
+    }
+
+    /// Consumes an `RwLockWriteGuard` to a currency and invalidates it in the cache. Waits for
+    /// all other references to the currency to be dropped before invalidating.
+    ///
+    /// Use this in case a transaction fails and you want to invalidate the cache.
+    pub async fn invalidate_cache(mut self_: RwLockWriteGuard<'_, Option<Self>>) -> Result<()> {
+        let mut cache = CACHE_CURRENCY.lock().await;
+        let Some(self__) = self_.take() else {
+            return Err(anyhow!("Currency is already being used in a breaking operation."));
+        };
+
+        cache.pop(&(self__.guild_id, self__.curr_name));
+        Self::forget_guild(self__.guild_id).await;
+
+        drop(self_);
+        drop(cache);
+        Ok(())
+    }
+}
+
+impl ToKVs for Currency {
+    fn try_to_kvs(&self) -> Result<Vec<(String, String)>> {
+        match serde_json::to_value(self)? {
+            Value::Object(o) =>
+                Ok(
+                    o
+                        .into_iter()
+                        .map(|(k, v)| {
+                            if k == "ChannelsBlacklist" || k == "ChannelsWhitelist" {
                                 // take string array, make it into json array, iterate over that,
                                 // and for every value convert to str, then to string, then replace
                                 // quotation marks to nothing. Then use the string obtained to make a DB
@@ -1445,6 +2556,38 @@ impl ToKVs for Currency {
                                         .trim_end_matches(&[' ', ','])
                                         .to_owned(),
                                 ))
+                            } else if k == "EarnMultipliers" {
+                                let multipliers: Vec<EarnMultiplier> = serde_json::from_value(v)?;
+                                let list = multipliers
+                                    .iter()
+                                    .map(|m| {
+                                        let mention = match m {
+                                            EarnMultiplier::Role { role_id, .. } =>
+                                                Mention::from(RoleId::from(*role_id)),
+                                            EarnMultiplier::Channel { channel_id, .. } =>
+                                                Mention::from(ChannelId::from(*channel_id)),
+                                        };
+                                        format!("{mention} x{}", m.multiplier())
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                Ok((k, list))
+                            } else if k == "InterestRate" {
+                                let rate = v
+                                    .as_f64()
+                                    .map_or_else(|| "Off".to_owned(), |rate| format!("{rate}%"));
+                                Ok((k, rate))
+                            } else if
+                                k == "EarnMin" ||
+                                k == "EarnMax" ||
+                                k == "InterestThreshold" ||
+                                k == "VoiceRate"
+                            {
+                                // Amounts are stored as minor units, which nobody wants to read.
+                                let minor = v
+                                    .as_i64()
+                                    .ok_or_else(|| anyhow!("Could not convert to json i64."))?;
+                                Ok((k, Money::from_minor(minor).to_string()))
                             } else {
                                 Ok((k, v.to_string()))
                             }
@@ -1511,6 +2654,44 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn try_from_guild_after_eviction() {
+        crate::init_env().await;
+        let guild_id = DbGuildId::from(123_456_789_u64);
+        let curr_name = "test";
+        Currency::try_from_guild(guild_id).await.unwrap();
+        // Drop the currency from the cache like the LRU would, so the edit below happens on a new
+        // copy of it.
+        CACHE_CURRENCY.lock().await.pop(&(guild_id, curr_name.to_owned()));
+
+        let currency = Currency::try_from_name(guild_id, curr_name.to_owned()).await
+            .unwrap()
+            .unwrap();
+        let mut currency_ = currency.write().await;
+        let currency__ = currency_.as_mut().unwrap();
+        let earn_by_chat = !currency__.earn_by_chat();
+        currency__.update_earn_by_chat(earn_by_chat, None).await.unwrap();
+        drop(currency_);
+
+        let currencies = Currency::try_from_guild(guild_id).await.unwrap();
+        let mut found = false;
+        for currency in currencies.iter() {
+            let currency = currency.read().await;
+            if let Some(currency) = currency.as_ref().filter(|c| c.curr_name == curr_name) {
+                assert_eq!(currency.earn_by_chat(), earn_by_chat);
+                found = true;
+            }
+        }
+        assert!(found);
+
+        let currency = Currency::try_from_name(guild_id, curr_name.to_owned()).await
+            .unwrap()
+            .unwrap();
+        let mut currency_ = currency.write().await;
+        currency_.as_mut().unwrap().update_earn_by_chat(!earn_by_chat, None).await.unwrap();
+        drop(currency_);
+    }
+
     async fn sleepy_fetch_currency(guild_id: u64, curr_name: &str, millis: u64, i: usize) {
         tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
         let currency = Currency::try_from_name(guild_id.into(), curr_name.to_owned()).await
@@ -1550,8 +2731,8 @@ mod test {
             .roles_whitelist_add(DbRoleId::from(456_i64))
             .roles_blacklist(Some(vec![DbRoleId::from(789_i64)]))
             .roles_blacklist_add(DbRoleId::from(101_112_i64))
-            .earn_min(Some(10.0))
-            .earn_max(Some(100.0))
+            .earn_min(Money::from_whole(10))
+            .earn_max(Money::from_whole(100))
             .earn_timeout(Duration::seconds(60));
         let curr = curr.build().await.unwrap();
         for i in (0..20).rev() {
@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
 use crate::db::{ uniques::{ DbChannelId, DbGuildId, DbRoleId }, ArcTokioRwLockOption };
+use crate::mechanics::interest;
+use crate::util::money::{ Money, MONEY_SCALE };
 use anyhow::{ Ok, Result };
 use chrono::Duration;
 use mongodb::{ bson::doc, Collection };
 
-use super::Currency;
+use super::{ multiplier::{ EarnMultiplier, MultiplierStacking }, Currency };
 
 #[derive(Debug, Clone)]
 pub struct Builder {
@@ -16,6 +18,7 @@ pub struct Builder {
     base: Option<bool>,
     base_value: Option<f64>,
     pay: Option<bool>,
+    gamble: Option<bool>,
     earn_by_chat: Option<bool>,
     channels_is_whitelist: Option<bool>,
     roles_is_whitelist: Option<bool>,
@@ -23,9 +26,28 @@ pub struct Builder {
     roles_whitelist: Vec<DbRoleId>,
     channels_blacklist: Vec<DbChannelId>,
     roles_blacklist: Vec<DbRoleId>,
-    earn_min: Option<f64>,
-    earn_max: Option<f64>,
+    earn_min: Option<Money>,
+    earn_max: Option<Money>,
     earn_timeout: Option<Duration>,
+    precision: Option<u8>,
+    interest_rate: Option<f64>,
+    interest_interval: Option<Duration>,
+    interest_threshold: Option<Money>,
+    bank: Option<bool>,
+    bank_capacity: Option<Money>,
+    earn_by_voice: Option<bool>,
+    voice_rate: Option<Money>,
+    voice_exclude_muted: Option<bool>,
+    voice_exclude_deafened: Option<bool>,
+    voice_exclude_alone: Option<bool>,
+    voice_exclude_afk: Option<bool>,
+    earn_multipliers: Vec<EarnMultiplier>,
+    multiplier_stacking: Option<MultiplierStacking>,
+    min_message_length: Option<u32>,
+    ignore_repeated_messages: Option<bool>,
+    min_account_age: Option<Duration>,
+    min_member_age: Option<Duration>,
+    ignore_emoji_and_link_only: Option<bool>,
 }
 
 impl Builder {
@@ -39,6 +61,7 @@ impl Builder {
             base: None,
             base_value: None,
             pay: None,
+            gamble: None,
             earn_by_chat: None,
             channels_is_whitelist: None,
             roles_is_whitelist: None,
@@ -49,6 +72,25 @@ impl Builder {
             earn_min: None,
             earn_max: None,
             earn_timeout: None,
+            precision: None,
+            interest_rate: None,
+            interest_interval: None,
+            interest_threshold: None,
+            bank: None,
+            bank_capacity: None,
+            earn_by_voice: None,
+            voice_rate: None,
+            voice_exclude_muted: None,
+            voice_exclude_deafened: None,
+            voice_exclude_alone: None,
+            voice_exclude_afk: None,
+            earn_multipliers: Vec::new(),
+            multiplier_stacking: None,
+            min_message_length: None,
+            ignore_repeated_messages: None,
+            min_account_age: None,
+            min_member_age: None,
+            ignore_emoji_and_link_only: None,
         }
     }
 
@@ -86,12 +128,44 @@ impl Builder {
     /// assert_eq!(currency.earn_min, 1.0);
     /// assert_eq!(currency.earn_max, 10.0);
     /// assert_eq!(currency.earn_timeout, 30);
+    /// assert_eq!(currency.precision, 2);
     /// ```
     ///
     /// # Errors
     ///
-    /// Returns an error if the currency already exists, or if any mongodb operation errors.
+    /// Returns an error if the currency already exists, if the precision is more than `MONEY_SCALE`,
+    /// or if any mongodb operation errors.
+    #[allow(clippy::too_many_lines)]
     pub async fn build(self) -> Result<ArcTokioRwLockOption<Currency>> {
+        let precision = self.precision.unwrap_or(2);
+        if precision > MONEY_SCALE {
+            return Err(
+                anyhow::anyhow!("Precision cannot be more than {MONEY_SCALE} decimal places")
+            );
+        }
+        super::validate_interest_rate(self.interest_rate)?;
+        let interest_interval = self.interest_interval.unwrap_or_else(super::default_interest_interval);
+        super::validate_interest_interval(interest_interval)?;
+        let interest_threshold = self.interest_threshold.unwrap_or(Money::ZERO).truncate(precision);
+        if interest_threshold.is_negative() {
+            return Err(anyhow::anyhow!("Interest threshold cannot be negative."));
+        }
+        let bank_capacity = self.bank_capacity.map(|c| c.truncate(precision));
+        if bank_capacity.is_some_and(Money::is_negative) {
+            return Err(anyhow::anyhow!("Bank capacity cannot be negative."));
+        }
+        for multiplier in &self.earn_multipliers {
+            multiplier.validate()?;
+        }
+        let min_account_age = self.min_account_age.unwrap_or_else(Duration::zero);
+        let min_member_age = self.min_member_age.unwrap_or_else(Duration::zero);
+        if min_account_age < Duration::zero() || min_member_age < Duration::zero() {
+            return Err(anyhow::anyhow!("Minimum ages cannot be negative."));
+        }
+        let voice_rate = self.voice_rate.unwrap_or(Money::ZERO).truncate(precision);
+        if voice_rate.is_negative() {
+            return Err(anyhow::anyhow!("Voice rate cannot be negative."));
+        }
         // check if currency already exists
         let db = super::super::super::CLIENT.get().await.database("conebot");
         let coll: Collection<Currency> = db.collection("currencies");
@@ -114,8 +188,10 @@ impl Builder {
         let roles_whitelist = self.roles_whitelist;
         let channels_blacklist = self.channels_blacklist;
         let roles_blacklist = self.roles_blacklist;
-        let earn_min = self.earn_min.unwrap_or(1.0);
-        let earn_max = self.earn_max.unwrap_or(100.0);
+        let earn_min = self.earn_min.unwrap_or_else(|| Money::from_minor(10_000)).truncate(precision);
+        let earn_max = self.earn_max
+            .unwrap_or_else(|| Money::from_minor(1_000_000))
+            .truncate(precision);
         let earn_timeout = self.earn_timeout.unwrap_or_else(|| Duration::seconds(30));
 
         let curr = Currency {
@@ -126,6 +202,7 @@ impl Builder {
             base,
             base_value,
             pay,
+            gamble: self.gamble.unwrap_or(true),
             earn_by_chat,
             channels_is_whitelist,
             roles_is_whitelist,
@@ -136,6 +213,25 @@ impl Builder {
             earn_min,
             earn_max,
             earn_timeout,
+            precision,
+            interest_rate: self.interest_rate,
+            interest_interval,
+            interest_threshold,
+            bank: self.bank.unwrap_or(false),
+            bank_capacity,
+            earn_by_voice: self.earn_by_voice.unwrap_or(false),
+            voice_rate,
+            voice_exclude_muted: self.voice_exclude_muted.unwrap_or(true),
+            voice_exclude_deafened: self.voice_exclude_deafened.unwrap_or(true),
+            voice_exclude_alone: self.voice_exclude_alone.unwrap_or(true),
+            voice_exclude_afk: self.voice_exclude_afk.unwrap_or(true),
+            earn_multipliers: self.earn_multipliers,
+            multiplier_stacking: self.multiplier_stacking.unwrap_or_default(),
+            min_message_length: self.min_message_length.unwrap_or(0),
+            ignore_repeated_messages: self.ignore_repeated_messages.unwrap_or(false),
+            min_account_age,
+            min_member_age,
+            ignore_emoji_and_link_only: self.ignore_emoji_and_link_only.unwrap_or(false),
         };
         // if base is set to true, check if there is another currency where base is true and set it to false
         if curr.base {
@@ -146,10 +242,12 @@ impl Builder {
 
         let mut cache = super::CACHE_CURRENCY.lock().await;
         coll.insert_one(curr.clone(), None).await?;
+        interest::sync_job(&curr, None).await?;
         let arc_currency: ArcTokioRwLockOption<Currency> = Arc::new(
             tokio::sync::RwLock::new(Some(curr))
         );
         cache.push((self.guild_id, self.curr_name.clone()), arc_currency.clone());
+        Currency::forget_guild(self.guild_id).await;
         drop(cache);
         Ok(arc_currency)
     }
@@ -207,6 +305,13 @@ impl Builder {
         self.pay = pay.into();
         self
     }
+    /// `gamble`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `true`
+    pub fn gamble(&mut self, gamble: impl Into<Option<bool>>) -> &mut Self {
+        self.gamble = gamble.into();
+        self
+    }
     /// `earn_by_chat`
     /// If `None` is passed, or the method is not called,
     /// it falls back to the default value of `false`
@@ -330,14 +435,14 @@ impl Builder {
     /// `earn_min`
     /// If `None` is passed, or the method is not called,
     /// it falls back to the default value of `1.0`
-    pub fn earn_min(&mut self, earn_min: impl Into<Option<f64>>) -> &mut Self {
+    pub fn earn_min(&mut self, earn_min: impl Into<Option<Money>>) -> &mut Self {
         self.earn_min = earn_min.into();
         self
     }
     /// `earn_max`
     /// If `None` is passed, or the method is not called,
     /// it falls back to the default value of `10.0`
-    pub fn earn_max(&mut self, earn_max: impl Into<Option<f64>>) -> &mut Self {
+    pub fn earn_max(&mut self, earn_max: impl Into<Option<Money>>) -> &mut Self {
         self.earn_max = earn_max.into();
         self
     }
@@ -348,6 +453,174 @@ impl Builder {
         self.earn_timeout = earn_timeout.into();
         self
     }
+    /// `precision`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `2`
+    pub fn precision(&mut self, precision: impl Into<Option<u8>>) -> &mut Self {
+        self.precision = precision.into();
+        self
+    }
+    /// `interest_rate`
+    /// In percent per interval, negative for a wealth tax.
+    /// If `None` is passed, or the method is not called, there is no interest.
+    pub fn interest_rate(&mut self, interest_rate: impl Into<Option<f64>>) -> &mut Self {
+        self.interest_rate = interest_rate.into();
+        self
+    }
+    /// `interest_interval`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of 1 day
+    pub fn interest_interval(
+        &mut self,
+        interest_interval: impl Into<Option<Duration>>
+    ) -> &mut Self {
+        self.interest_interval = interest_interval.into();
+        self
+    }
+    /// `interest_threshold`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `0`
+    pub fn interest_threshold(&mut self, interest_threshold: impl Into<Option<Money>>) -> &mut Self {
+        self.interest_threshold = interest_threshold.into();
+        self
+    }
+    /// `bank`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `false`
+    pub fn bank(&mut self, bank: impl Into<Option<bool>>) -> &mut Self {
+        self.bank = bank.into();
+        self
+    }
+    /// `bank_capacity`
+    /// If `None` is passed, or the method is not called,
+    /// there is no limit to how much members can keep in the bank
+    pub fn bank_capacity(&mut self, bank_capacity: impl Into<Option<Money>>) -> &mut Self {
+        self.bank_capacity = bank_capacity.into();
+        self
+    }
+    /// `earn_by_voice`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `false`
+    pub fn earn_by_voice(&mut self, earn_by_voice: impl Into<Option<bool>>) -> &mut Self {
+        self.earn_by_voice = earn_by_voice.into();
+        self
+    }
+    /// `voice_rate`
+    /// Per minute spent in a voice channel.
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `0`
+    pub fn voice_rate(&mut self, voice_rate: impl Into<Option<Money>>) -> &mut Self {
+        self.voice_rate = voice_rate.into();
+        self
+    }
+    /// `voice_exclude_muted`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `true`
+    pub fn voice_exclude_muted(
+        &mut self,
+        voice_exclude_muted: impl Into<Option<bool>>
+    ) -> &mut Self {
+        self.voice_exclude_muted = voice_exclude_muted.into();
+        self
+    }
+    /// `voice_exclude_deafened`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `true`
+    pub fn voice_exclude_deafened(
+        &mut self,
+        voice_exclude_deafened: impl Into<Option<bool>>
+    ) -> &mut Self {
+        self.voice_exclude_deafened = voice_exclude_deafened.into();
+        self
+    }
+    /// `voice_exclude_alone`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `true`
+    pub fn voice_exclude_alone(
+        &mut self,
+        voice_exclude_alone: impl Into<Option<bool>>
+    ) -> &mut Self {
+        self.voice_exclude_alone = voice_exclude_alone.into();
+        self
+    }
+    /// `voice_exclude_afk`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `true`
+    pub fn voice_exclude_afk(&mut self, voice_exclude_afk: impl Into<Option<bool>>) -> &mut Self {
+        self.voice_exclude_afk = voice_exclude_afk.into();
+        self
+    }
+    /// `earn_multipliers`
+    /// If `None` is passed, it resets the
+    /// field to an empty vector.
+    /// And if the method is not called,
+    /// it falls back to anything provided
+    /// with calls to `earn_multipliers_add()`.
+    pub fn earn_multipliers(
+        &mut self,
+        earn_multipliers: impl Into<Option<Vec<EarnMultiplier>>>
+    ) -> &mut Self {
+        self.earn_multipliers = earn_multipliers.into().unwrap_or_default();
+        self
+    }
+    /// `earn_multipliers_add`
+    /// Adds a multiplier, replacing any earlier one for the same role or channel.
+    pub fn earn_multipliers_add(&mut self, multiplier: EarnMultiplier) -> &mut Self {
+        self.earn_multipliers.retain(|m| !m.same_target(&multiplier));
+        self.earn_multipliers.push(multiplier);
+        self
+    }
+    /// `multiplier_stacking`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `MultiplierStacking::Max`
+    pub fn multiplier_stacking(
+        &mut self,
+        multiplier_stacking: impl Into<Option<MultiplierStacking>>
+    ) -> &mut Self {
+        self.multiplier_stacking = multiplier_stacking.into();
+        self
+    }
+    /// `min_message_length`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `0`
+    pub fn min_message_length(&mut self, min_message_length: impl Into<Option<u32>>) -> &mut Self {
+        self.min_message_length = min_message_length.into();
+        self
+    }
+    /// `ignore_repeated_messages`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `false`
+    pub fn ignore_repeated_messages(
+        &mut self,
+        ignore_repeated_messages: impl Into<Option<bool>>
+    ) -> &mut Self {
+        self.ignore_repeated_messages = ignore_repeated_messages.into();
+        self
+    }
+    /// `min_account_age`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `0`
+    pub fn min_account_age(&mut self, min_account_age: impl Into<Option<Duration>>) -> &mut Self {
+        self.min_account_age = min_account_age.into();
+        self
+    }
+    /// `min_member_age`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `0`
+    pub fn min_member_age(&mut self, min_member_age: impl Into<Option<Duration>>) -> &mut Self {
+        self.min_member_age = min_member_age.into();
+        self
+    }
+    /// `ignore_emoji_and_link_only`
+    /// If `None` is passed, or the method is not called,
+    /// it falls back to the default value of `false`
+    pub fn ignore_emoji_and_link_only(
+        &mut self,
+        ignore_emoji_and_link_only: impl Into<Option<bool>>
+    ) -> &mut Self {
+        self.ignore_emoji_and_link_only = ignore_emoji_and_link_only.into();
+        self
+    }
 }
 
 #[tokio::test]
@@ -372,8 +645,8 @@ async fn test_currency_builder() {
         .channels_blacklist_add(DbChannelId::from(456_i64))
         .roles_blacklist(Some(vec![DbRoleId::from(123_i64)]))
         .roles_blacklist_add(DbRoleId::from(456_i64))
-        .earn_min(Some(1.0))
-        .earn_max(Some(10.0))
+        .earn_min(Money::from_whole(1))
+        .earn_max(Money::from_whole(10))
         .earn_timeout(Some(Duration::seconds(60)));
     let curr = curr.build().await.unwrap();
     let curr2 = curr.read().await;
@@ -400,11 +673,8 @@ async fn test_currency_builder() {
     );
     assert_eq!(curr3.roles_blacklist, vec![DbRoleId::from(123_i64), DbRoleId::from(456_i64)]);
 
-    let error_margin_f64 = f64::EPSILON;
-    let res1 = curr3.earn_min - 1.0;
-    let res2 = curr3.earn_max - 10.0;
-    assert!(res1 < error_margin_f64);
-    assert!(res2 < error_margin_f64);
+    assert_eq!(curr3.earn_min, Money::from_minor(10_000));
+    assert_eq!(curr3.earn_max, Money::from_minor(100_000));
     assert_eq!(curr3.earn_timeout, Duration::seconds(60));
 
     drop(curr2);
@@ -0,0 +1,148 @@
+//! Multipliers on what members earn of a currency by chatting, for having a certain role or
+//! chatting in a certain channel.
+//!
+//! A member can have several roles with a multiplier and also be in a channel with one, so how
+//! they are combined is up to the `MultiplierStacking` of the currency.
+
+use anyhow::{ anyhow, Result };
+use serde::{ Deserialize, Serialize };
+use serenity::all::{ ChannelId, RoleId };
+
+use crate::db::uniques::{ DbChannelId, DbRoleId };
+
+/// A multiplier on what is earned, keyed by the role or channel it applies to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "Type", rename_all_fields = "PascalCase")]
+pub enum EarnMultiplier {
+    /// Applies to members that have the role.
+    Role {
+        role_id: DbRoleId,
+        multiplier: f64,
+    },
+    /// Applies to messages sent in the channel.
+    Channel {
+        channel_id: DbChannelId,
+        multiplier: f64,
+    },
+}
+
+impl EarnMultiplier {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn multiplier(&self) -> f64 {
+        match self {
+            Self::Role { multiplier, .. } | Self::Channel { multiplier, .. } => *multiplier,
+        }
+    }
+
+    /// Whether both multipliers are for the same role or channel.
+    #[allow(clippy::must_use_candidate)]
+    pub fn same_target(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Role { role_id: a, .. }, Self::Role { role_id: b, .. }) => a == b,
+            (Self::Channel { channel_id: a, .. }, Self::Channel { channel_id: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Whether the multiplier applies to a member with these roles chatting in a channel with
+    /// this lineage, so a multiplier for a category or channel also covers what is inside of it.
+    #[allow(clippy::must_use_candidate)]
+    pub fn applies_to(&self, roles: &[RoleId], channel_lineage: &[ChannelId]) -> bool {
+        match self {
+            Self::Role { role_id, .. } => roles.contains(&RoleId::from(*role_id)),
+            Self::Channel { channel_id, .. } => {
+                channel_lineage.contains(&ChannelId::from(*channel_id))
+            }
+        }
+    }
+
+    /// Checks that the multiplier is a number that makes sense to multiply amounts by.
+    ///
+    /// # Errors
+    /// - The multiplier is negative, infinite or not a number.
+    pub fn validate(&self) -> Result<()> {
+        let multiplier = self.multiplier();
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            return Err(anyhow!("Multiplier must be a number that is not negative."));
+        }
+        Ok(())
+    }
+}
+
+/// How the multipliers that apply to a member are combined.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiplierStacking {
+    /// Only the highest multiplier counts.
+    #[default]
+    Max,
+    /// All of the multipliers are multiplied together.
+    Multiplicative,
+}
+
+impl MultiplierStacking {
+    /// Combines multipliers into one. If there are none the result is 1.
+    #[allow(clippy::must_use_candidate)]
+    pub fn combine(self, multipliers: impl IntoIterator<Item = f64>) -> f64 {
+        let multipliers = multipliers.into_iter();
+        match self {
+            Self::Max => multipliers.reduce(f64::max).unwrap_or(1.0),
+            Self::Multiplicative => multipliers.product(),
+        }
+    }
+
+    /// The name of the stacking mode, which is also how it is stored in the database.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Max => "max",
+            Self::Multiplicative => "multiplicative",
+        }
+    }
+}
+
+impl std::str::FromStr for MultiplierStacking {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "max" => Ok(Self::Max),
+            "multiplicative" => Ok(Self::Multiplicative),
+            _ => Err(anyhow!("Unknown multiplier stacking {s}, use max or multiplicative.")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_combine() {
+        assert!((MultiplierStacking::Max.combine([]) - 1.0).abs() < f64::EPSILON);
+        assert!((MultiplierStacking::Multiplicative.combine([]) - 1.0).abs() < f64::EPSILON);
+        assert!((MultiplierStacking::Max.combine([1.5, 2.0, 0.5]) - 2.0).abs() < f64::EPSILON);
+        assert!(
+            (MultiplierStacking::Multiplicative.combine([1.5, 2.0, 0.5]) - 1.5).abs() < f64::EPSILON
+        );
+        // A channel that pays less still counts if it is the only multiplier.
+        assert!((MultiplierStacking::Max.combine([0.5]) - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let role = EarnMultiplier::Role { role_id: DbRoleId::from(1_u64), multiplier: 2.0 };
+        let channel = EarnMultiplier::Channel {
+            channel_id: DbChannelId::from(2_u64),
+            multiplier: 2.0,
+        };
+        let roles = [RoleId::new(1)];
+        assert!(role.applies_to(&roles, &[ChannelId::new(3)]));
+        assert!(!role.applies_to(&[], &[ChannelId::new(3)]));
+        assert!(channel.applies_to(&[], &[ChannelId::new(2)]));
+        // A thread in the channel.
+        assert!(channel.applies_to(&[], &[ChannelId::new(4), ChannelId::new(2)]));
+        assert!(!channel.applies_to(&roles, &[ChannelId::new(1)]));
+        assert!(!role.same_target(&channel));
+    }
+}
@@ -1,7 +1,25 @@
 use anyhow::Result;
 use mongodb::ClientSession;
 
-use crate::db::{ models::{ store::Store, Balances, DropTable, Item }, uniques::DbGuildId };
+use crate::{
+    db::{
+        models::{
+            activity::Activity,
+            blocked_earns::BlockedEarns,
+            claim::ClaimConfig,
+            earn_cooldown::EarnCooldown,
+            rob::{ RobConfig, RobCooldown },
+            role_income::RoleIncome,
+            store::Store,
+            transaction::Transaction,
+            Balances,
+            DropTable,
+            Item,
+        },
+        uniques::DbGuildId,
+    },
+    mechanics::{ interest, pending_earnings },
+};
 
 pub async fn handle_name_updates(
     guild_id: DbGuildId,
@@ -18,5 +36,15 @@ pub async fn handle_name_updates(
     ).await?;
     Item::bulk_update_currency_value_name(guild_id, before, after.clone(), Some(session)).await?;
     Store::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    RoleIncome::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    ClaimConfig::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    EarnCooldown::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    BlockedEarns::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    RobConfig::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    RobCooldown::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    Activity::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    Transaction::bulk_update_currency_name(guild_id, before, &after, Some(session)).await?;
+    interest::rename_job(guild_id, before, &after, session).await?;
+    pending_earnings::rename_currency(guild_id, before, &after);
     Ok(())
 }
@@ -460,6 +460,11 @@ impl DropTable {
         drop(cache);
     }
 
+    /// Invalidates the cache of every drop table of a guild, like `invalidate_cache` does for one.
+    pub async fn invalidate_guild_cache(guild_id: DbGuildId) {
+        crate::db::uncache_rw(&DROP_TABLES_CACHE, |(g, _)| *g == guild_id).await;
+    }
+
     /// Invalidates the cache of this drop table. This means that it will set its value
     /// to None before removing it from the cache. Useful when you want to dispose of
     /// a drop table properly.
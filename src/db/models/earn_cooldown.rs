@@ -0,0 +1,257 @@
+//! This module contains the `EarnCooldown` struct, which remembers when a member last earned a
+//! currency by chatting.
+//!
+//! Cooldowns live in the `earnCooldowns` collection as the time the member last earned, so they
+//! survive restarts and are shared between processes. Whether the member is still on cooldown is
+//! worked out by comparing that against the `earn_timeout` of the currency.
+//!
+//! Documents are removed by a TTL index once the cooldown they were made with is over, so the
+//! collection only ever holds cooldowns that might still be running.
+//!
+//! Cooldowns this process started are also remembered in memory, so that messages from members
+//! who are still on cooldown, which is most messages on a busy guild, never reach the database.
+
+use std::{ num::NonZeroUsize, sync::Mutex };
+
+use anyhow::Result;
+use chrono::{ DateTime, Duration, Utc };
+use lazy_static::lazy_static;
+use lru::LruCache;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime },
+    error::{ ErrorKind, WriteFailure },
+    options::{ IndexOptions, UpdateOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+
+use crate::db::{ uniques::{ DbGuildId, DbUserId }, CLIENT };
+
+/// The error code `MongoDB` returns when a unique index is violated.
+const DUPLICATE_KEY: i32 = 11000;
+
+lazy_static! {
+    /// When the cooldowns started by this process end, by member and currency.
+    static ref LOCAL_ENDS: Mutex<LruCache<(DbGuildId, DbUserId, String), DateTime<Utc>>> =
+        Mutex::new(LruCache::new(NonZeroUsize::new(100_000).unwrap()));
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct EarnCooldown {
+    guild_id: DbGuildId,
+    user_id: DbUserId,
+    curr_name: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    last_earned: DateTime<Utc>,
+    /// When the TTL index may remove this document.
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    expires_at: DateTime<Utc>,
+}
+
+impl EarnCooldown {
+    /// Checks whether a member is on a cooldown for a currency that this process started and that
+    /// is still running at `now`, without asking the database.
+    ///
+    /// If this is false the member may still be on a cooldown started somewhere else, which
+    /// `try_start` takes care of.
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_known_active(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        curr_name: &str,
+        now: DateTime<Utc>
+    ) -> bool {
+        local_end(&(guild_id, user_id, curr_name.to_owned())).is_some_and(|end| now < end)
+    }
+
+    /// Starts a cooldown for a member if they are not already on one. Returns whether it was
+    /// started, in which case the member may earn.
+    ///
+    /// This is a single upsert that only matches a cooldown which is already over, so if the
+    /// member is still on cooldown it tries to insert a second document and the unique index turns
+    /// it away. That way only one of any number of messages, even across processes, gets through.
+    ///
+    /// If this process started a cooldown that is still running, the database is not asked at all.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error other than the duplicate key occurs.
+    pub async fn try_start(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        curr_name: &str,
+        timeout: Duration,
+        now: DateTime<Utc>
+    ) -> Result<bool> {
+        if Self::is_known_active(guild_id, user_id, curr_name, now) {
+            return Ok(false);
+        }
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("earnCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "CurrName": curr_name,
+            "LastEarned": { "$lte": mongodb::bson::DateTime::from_chrono(now - timeout) },
+        };
+        let updatedoc =
+            doc! {
+            "$set": {
+                "LastEarned": mongodb::bson::DateTime::from_chrono(now),
+                "ExpiresAt": mongodb::bson::DateTime::from_chrono(now + timeout),
+            },
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        match coll.update_one(filterdoc, updatedoc, options).await {
+            Ok(_) => {
+                if let Ok(mut local_ends) = LOCAL_ENDS.lock() {
+                    local_ends.put((guild_id, user_id, curr_name.to_owned()), now + timeout);
+                }
+                Ok(true)
+            }
+            Err(e) => {
+                if let ErrorKind::Write(WriteFailure::WriteError(ref write_error)) = *e.kind {
+                    if write_error.code == DUPLICATE_KEY {
+                        return Ok(false);
+                    }
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Updates the currency name of every cooldown in a guild for the old currency name.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        Self::forget_local(guild_id, old_name);
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("earnCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": old_name,
+        };
+        let updatedoc = doc! {
+            "$set": {
+                "CurrName": new_name,
+            }
+        };
+        if let Some(s) = session {
+            coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_many(filterdoc, updatedoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the unique index that `try_start` relies on and the TTL index that cleans up
+    /// cooldowns that are over.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("earnCooldowns");
+
+        let unique = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "UserId": 1, "CurrName": 1 })
+            .options(
+                IndexOptions::builder().name("GuildUserCurrency".to_owned()).unique(true).build()
+            )
+            .build();
+        let ttl = IndexModel::builder()
+            .keys(doc! { "ExpiresAt": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name("ExpiresAt".to_owned())
+                    .expire_after(std::time::Duration::ZERO)
+                    .build()
+            )
+            .build();
+        coll.create_indexes([unique, ttl], None).await?;
+        Ok(())
+    }
+
+    /// Forgets the cooldowns this process remembers for a currency, for when its name or timeout
+    /// changes.
+    pub fn forget_local(guild_id: DbGuildId, curr_name: &str) {
+        let Ok(mut local_ends) = LOCAL_ENDS.lock() else {
+            return;
+        };
+        let keys = local_ends
+            .iter()
+            .filter(|((g, _, c), _)| *g == guild_id && c == curr_name)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in keys {
+            local_ends.pop(&key);
+        }
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn guild_id(&self) -> DbGuildId {
+        self.guild_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn user_id(&self) -> DbUserId {
+        self.user_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn curr_name(&self) -> &str {
+        &self.curr_name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn last_earned(&self) -> DateTime<Utc> {
+        self.last_earned
+    }
+}
+
+fn local_end(key: &(DbGuildId, DbUserId, String)) -> Option<DateTime<Utc>> {
+    LOCAL_ENDS.lock().ok()?.get(key).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_try_start() {
+        crate::init_env().await;
+        let guild_id = DbGuildId::from(123_456_789_u64);
+        let user_id = DbUserId::from(3_u64);
+        let curr_name = "cooldown test";
+        let timeout = Duration::seconds(30);
+        let now = Utc::now();
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<EarnCooldown> = db.collection("earnCooldowns");
+        coll.delete_many(doc! { "CurrName": curr_name }, None).await.unwrap();
+
+        let try_start = |at| EarnCooldown::try_start(guild_id, user_id, curr_name, timeout, at);
+
+        let (a, b) = tokio::join!(try_start(now), try_start(now));
+        assert!(a.unwrap() ^ b.unwrap()); // Exactly one gets through.
+        assert!(!try_start(now).await.unwrap());
+        assert!(!try_start(now + timeout - Duration::seconds(1)).await.unwrap());
+
+        let later = now + timeout;
+        assert!(try_start(later).await.unwrap());
+        assert!(!try_start(later).await.unwrap());
+    }
+}
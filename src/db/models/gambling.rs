@@ -0,0 +1,429 @@
+//! This module contains the structs behind the gambling commands.
+//!
+//! `GameConfig` is how a guild set up each game, and lives in the `gameConfigs` collection. There
+//! is at most one per guild for each game, and a game without one is turned off.
+//!
+//! `GameCooldown` remembers when a member last played each game, in the `gameCooldowns`
+//! collection, so cooldowns survive restarts like claim streaks do.
+//!
+//! `GameRecord` is one game that was played, with what was bet, what came up and what was paid
+//! out, in the `gameRecords` collection. It is written in the same transaction that settles the
+//! bet, so staff can always check an outcome against the ledger.
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Duration, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime },
+    options::{ FindOptions, IndexOptions, ReplaceOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+use serde_with::{ serde_as, DurationSeconds };
+
+use crate::{
+    db::{ uniques::{ DbGuildId, DbUserId }, CLIENT },
+    util::money::Money,
+};
+
+/// One of the gambling games.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Game {
+    Coinflip,
+    Dice,
+    Slots,
+}
+
+impl Game {
+    pub const ALL: [Self; 3] = [Self::Coinflip, Self::Dice, Self::Slots];
+
+    /// The name of the game, which is also its command and how it is stored in the database.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Coinflip => "coinflip",
+            Self::Dice => "dice",
+            Self::Slots => "slots",
+        }
+    }
+}
+
+impl std::str::FromStr for Game {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "coinflip" => Ok(Self::Coinflip),
+            "dice" => Ok(Self::Dice),
+            "slots" => Ok(Self::Slots),
+            _ => Err(anyhow!("Unknown game {s}.")),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct GameConfig {
+    guild_id: DbGuildId,
+    game: Game,
+    enabled: bool,
+    /// The least that can be bet at once, in whichever currency.
+    min_bet: Money,
+    /// The most that can be bet at once, in whichever currency. `None` for no limit.
+    max_bet: Option<Money>,
+    /// The fraction of every bet the house keeps on average. With `0.02` members get back 98% of
+    /// what they bet in the long run.
+    house_edge: f64,
+    /// How long a member has to wait between games.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    cooldown: Duration,
+}
+
+impl GameConfig {
+    /// How a game is set up in a guild that never configured it. It is turned off.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn default_for(guild_id: DbGuildId, game: Game) -> Self {
+        Self {
+            guild_id,
+            game,
+            enabled: false,
+            min_bet: Money::from_minor(1),
+            max_bet: None,
+            house_edge: 0.02,
+            cooldown: Duration::seconds(5),
+        }
+    }
+
+    /// Sets up a game in a guild, replacing how it was set up before.
+    ///
+    /// # Errors
+    /// - The minimum bet is not positive, or is more than the maximum bet.
+    /// - The house edge is not a fraction from 0 up to, but not including, 1.
+    /// - The cooldown is negative.
+    /// - Any `MongoDB` error occurs.
+    pub async fn set(
+        guild_id: DbGuildId,
+        game: Game,
+        enabled: bool,
+        min_bet: Money,
+        max_bet: Option<Money>,
+        house_edge: f64,
+        cooldown: Duration
+    ) -> Result<Self> {
+        if min_bet <= Money::ZERO {
+            bail!("The minimum bet must be more than 0.");
+        }
+        if max_bet.is_some_and(|max| max < min_bet) {
+            bail!("The minimum bet cannot be more than the maximum bet.");
+        }
+        if !house_edge.is_finite() || !(0.0..1.0).contains(&house_edge) {
+            bail!("The house edge must be at least 0% and less than 100%.");
+        }
+        if cooldown < Duration::zero() {
+            bail!("The cooldown cannot be negative.");
+        }
+        let new_self = Self {
+            guild_id,
+            game,
+            enabled,
+            min_bet,
+            max_bet,
+            house_edge,
+            cooldown,
+        };
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameConfigs");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Game": game.as_str() };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one(filterdoc, &new_self, options).await?;
+        Ok(new_self)
+    }
+
+    /// Gets how a game is set up in a guild, or `default_for` if it never was.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_game(guild_id: DbGuildId, game: Game) -> Result<Self> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameConfigs");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "Game": game.as_str() };
+        let config = coll.find_one(filterdoc, None).await?;
+        Ok(config.unwrap_or_else(|| Self::default_for(guild_id, game)))
+    }
+
+    /// Gets how every game is set up in a guild, in the order of `Game::ALL`.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_guild(guild_id: DbGuildId) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameConfigs");
+
+        let res = coll.find(doc! { "GuildId": guild_id.as_i64() }, None).await?;
+        let configs: Vec<Self> = res.try_collect().await?;
+        Ok(
+            Game::ALL.iter()
+                .map(|game| {
+                    configs
+                        .iter()
+                        .find(|c| c.game == *game)
+                        .cloned()
+                        .unwrap_or_else(|| Self::default_for(guild_id, *game))
+                })
+                .collect()
+        )
+    }
+
+    /// Checks that a bet is within the limits of the game.
+    ///
+    /// # Errors
+    /// - The bet is less than the minimum or more than the maximum.
+    pub fn check_bet(&self, bet: Money) -> Result<()> {
+        if bet < self.min_bet {
+            bail!("You have to bet at least {}.", self.min_bet);
+        }
+        if let Some(max_bet) = self.max_bet {
+            if bet > max_bet {
+                bail!("You can bet at most {max_bet}.");
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn guild_id(&self) -> DbGuildId {
+        self.guild_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn game(&self) -> Game {
+        self.game
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn min_bet(&self) -> Money {
+        self.min_bet
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn max_bet(&self) -> Option<Money> {
+        self.max_bet
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn house_edge(&self) -> f64 {
+        self.house_edge
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct GameCooldown {
+    guild_id: DbGuildId,
+    user_id: DbUserId,
+    game: Game,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    last_played: DateTime<Utc>,
+}
+
+impl GameCooldown {
+    /// Starts the cooldown of a game for a member at `now`, unless they are still on cooldown.
+    ///
+    /// This is meant to be done in the same transaction as settling the bet. Two games started at
+    /// the same time then conflict in the database, so only one of them goes through.
+    ///
+    /// # Errors
+    /// - The member is still on cooldown, with the time it is over.
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_start(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        game: Game,
+        cooldown: Duration,
+        now: DateTime<Utc>,
+        session: &mut ClientSession
+    ) -> Result<Result<(), DateTime<Utc>>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "Game": game.as_str(),
+        };
+        if let Some(prev) = coll.find_one_with_session(filterdoc.clone(), None, session).await? {
+            let available = prev.last_played + cooldown;
+            if now < available {
+                return Ok(Err(available));
+            }
+        }
+        let new_self = Self { guild_id, user_id, game, last_played: now };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one_with_session(filterdoc, &new_self, options, session).await?;
+        Ok(Ok(()))
+    }
+
+    /// Creates the index that keeps one cooldown per member per game.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameCooldowns");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "UserId": 1, "Game": 1 })
+            .options(
+                IndexOptions::builder().name("GuildUserGame".to_owned()).unique(true).build()
+            )
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+}
+
+/// A game that was played.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct GameRecord {
+    guild_id: DbGuildId,
+    user_id: DbUserId,
+    game: Game,
+    curr_name: String,
+    bet: Money,
+    /// What the member got back, including the bet. Zero if they lost.
+    payout: Money,
+    /// What came up, like `heads` or the symbols on the reels.
+    outcome: String,
+    /// The house edge the game had when it was played.
+    house_edge: f64,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    timestamp: DateTime<Utc>,
+}
+
+impl GameRecord {
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::must_use_candidate)]
+    pub const fn new(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        game: Game,
+        curr_name: String,
+        bet: Money,
+        payout: Money,
+        outcome: String,
+        house_edge: f64,
+        timestamp: DateTime<Utc>
+    ) -> Self {
+        Self { guild_id, user_id, game, curr_name, bet, payout, outcome, house_edge, timestamp }
+    }
+
+    /// Writes the record, in the session that settles the bet.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn insert(&self, session: &mut ClientSession) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameRecords");
+        coll.insert_one_with_session(self, None, session).await?;
+        Ok(())
+    }
+
+    /// Gets the most recent games played in a guild, newest first, of only one member if one is
+    /// given.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn recent(
+        guild_id: DbGuildId,
+        user_id: Option<DbUserId>,
+        limit: i64
+    ) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameRecords");
+
+        let mut filterdoc = doc! { "GuildId": guild_id.as_i64() };
+        if let Some(user_id) = user_id {
+            filterdoc.insert("UserId", user_id.as_i64());
+        }
+        let options = FindOptions::builder()
+            .sort(doc! { "Timestamp": -1 })
+            .limit(limit)
+            .build();
+        let res = coll.find(filterdoc, options).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Creates the indexes that `recent` goes through, with and without a member.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("gameRecords");
+
+        let guild = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "Timestamp": -1 })
+            .options(IndexOptions::builder().name("GuildTimestamp".to_owned()).build())
+            .build();
+        let member = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "UserId": 1, "Timestamp": -1 })
+            .options(IndexOptions::builder().name("GuildUserTimestamp".to_owned()).build())
+            .build();
+        coll.create_indexes([guild, member], None).await?;
+        Ok(())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn user_id(&self) -> DbUserId {
+        self.user_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn game(&self) -> Game {
+        self.game
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn curr_name(&self) -> &str {
+        &self.curr_name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn bet(&self) -> Money {
+        self.bet
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn payout(&self) -> Money {
+        self.payout
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn outcome(&self) -> &str {
+        &self.outcome
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
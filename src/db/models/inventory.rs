@@ -7,14 +7,21 @@ use async_recursion::async_recursion;
 use futures::{ StreamExt, TryStreamExt };
 use lazy_static::lazy_static;
 use lru::LruCache;
-use mongodb::{ bson::doc, ClientSession, Collection };
+use mongodb::{
+    bson::doc,
+    options::{ FindOneAndUpdateOptions, FindOptions, ReturnDocument },
+    ClientSession,
+    Collection,
+    Cursor,
+};
 use serde::{ Deserialize, Serialize };
 use serenity::client::Context;
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{ Mutex, MutexGuard };
 
 use crate::{
     db::{
+        inc_bounds,
         uniques::{ DbGuildId, DbUserId },
         ArcTokioMutexOption,
         ArcTokioRwLockOption,
@@ -23,7 +30,7 @@ use crate::{
     mechanics::item_action_handler::use_item,
 };
 
-use super::Item;
+use super::{ transaction::{ Transaction, TransactionChange, TransactionReason }, Item };
 
 pub const INVENTORY_RECURSION_DEPTH_LIMIT: u8 = 35;
 
@@ -226,6 +233,7 @@ impl Inventory {
         item: ArcTokioRwLockOption<Item>, // Clone on write. Neat little performance improvement.
         // It more serves as a signal that "This function may or may not clone the str."
         amount: i64,
+        reason: TransactionReason,
         session: Option<&'async_recursion mut ClientSession>,
         rec_depth: u8,
         http: &Context
@@ -253,7 +261,7 @@ impl Inventory {
         }
         //                                                      VVVVVVVVVV get the &str out of the Cow<'_,str>.
         if let Some(entry) = self.get_item(item__.name()) {
-            entry.add_amount(amount, session).await.map_err(Into::into)
+            entry.add_amount(amount, reason, session).await.map_err(Into::into)
         } else {
             let entry = InventoryEntry::new(
                 self.guild_id,
@@ -261,6 +269,7 @@ impl Inventory {
                 // Otherwise clone it and get an entire owned string.
                 item__.name().to_owned(),
                 amount,
+                reason,
                 session
             ).await?;
             drop(item_);
@@ -290,20 +299,23 @@ impl Inventory {
         &mut self,
         item_name: &str,
         count: i64,
+        reason: TransactionReason,
         mut session: Option<&mut ClientSession>
     ) -> Result<()> {
         if let Some(entry) = self.get_item(item_name) {
-            if entry.amount < count {
-                bail!("User does not have enough of the item.");
-            }
-            entry.sub_amount(
+            let res = entry.sub_amount(
                 count,
+                reason,
                 // Since the option itself is owned, passing it would move it. Calling as_mut() on it
                 // will return a Option<&mut &mut ClientSession>, but sub_amount() expects a Option<&mut ClientSession>.
                 // No they are not the same thing apparently. So I need to map the &mut &mut ClientSession to &mut ClientSession
                 // with the thing below by casting it. *** W O W ***.
                 session.as_mut().map(|r| r as &mut ClientSession)
-            ).await?;
+            ).await;
+            if matches!(res, Err(InventoryError::AmountUnderflow)) {
+                bail!("User does not have enough of the item.");
+            }
+            res?;
             if entry.amount == 0 {
                 self.delete_item(item_name, session).await?;
             }
@@ -392,6 +404,159 @@ impl Inventory {
 
         Ok(())
     }
+
+    /// Gives a different amount of an item to each of many members at once inside of a session,
+    /// creating the inventory entries that are missing and writing a ledger entry for each member.
+    /// Cached inventories are not touched, so the caller has to invalidate them.
+    ///
+    /// Instant items are not used up, so callers should not give them this way.
+    ///
+    /// # Errors
+    /// - Any of the amounts is negative, or a resulting amount would overflow.
+    /// - Any `MongoDB` error occurs.
+    pub(crate) async fn bulk_give_item_in_session(
+        guild_id: DbGuildId,
+        item_name: &str,
+        amounts: &HashMap<DbUserId, i64>,
+        reason: TransactionReason,
+        session: &mut ClientSession
+    ) -> Result<()> {
+        if amounts.values().any(|amount| *amount < 0) {
+            bail!("Cannot give a negative amount of an item.");
+        }
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<InventoryEntry> = db.collection("inventories");
+        let ids = amounts
+            .iter()
+            .filter(|(_, amount)| **amount != 0)
+            .map(|(id, _)| id.as_i64())
+            .collect::<Vec<_>>();
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "ItemName": item_name,
+            "UserId": { "$in": &ids },
+        };
+        let existing = coll
+            .find_with_session(filterdoc, None, session).await?
+            .stream(session)
+            .map_ok(|e| (e.user_id, e.amount))
+            .try_collect::<HashMap<_, _>>().await?;
+
+        let mut changes = Vec::with_capacity(ids.len());
+        let mut missing = Vec::new();
+        let mut to_update = Vec::new();
+        for (user_id, amount) in amounts.iter().filter(|(_, amount)| **amount != 0) {
+            let before = existing.get(user_id).copied().unwrap_or(0);
+            let resulting = before
+                .checked_add(*amount)
+                .ok_or_else(|| anyhow!("The amount of {item_name} would overflow."))?;
+            if existing.contains_key(user_id) {
+                to_update.push(*user_id);
+            } else {
+                missing.push(InventoryEntry {
+                    guild_id,
+                    user_id: *user_id,
+                    item_name: item_name.to_owned(),
+                    amount: resulting,
+                });
+            }
+            let change = TransactionChange::Item {
+                item_name: item_name.to_owned(),
+                delta: *amount,
+                resulting,
+            };
+            changes.push((*user_id, change));
+        }
+
+        if !missing.is_empty() {
+            coll.insert_many_with_session(missing, None, session).await?;
+        }
+        for chunk in to_update.chunks(super::balances::BULK_CHUNK_SIZE) {
+            let branches = chunk
+                .iter()
+                .map(|id| {
+                    doc! {
+                        "case": { "$eq": ["$UserId", id.as_i64()] },
+                        "then": amounts[id],
+                    }
+                })
+                .collect::<Vec<_>>();
+            let chunk_ids = chunk
+                .iter()
+                .map(|id| id.as_i64())
+                .collect::<Vec<_>>();
+            let chunk_filter =
+                doc! {
+                "GuildId": guild_id.as_i64(),
+                "ItemName": item_name,
+                "UserId": { "$in": chunk_ids },
+            };
+            let updatedoc =
+                vec![
+                doc! {
+                "$set": {
+                    "Amount": {
+                        "$add": [
+                            "$Amount",
+                            { "$switch": { "branches": branches, "default": 0_i64 } },
+                        ],
+                    },
+                },
+            }
+            ];
+            coll.update_many_with_session(chunk_filter, updatedoc, None, session).await?;
+        }
+
+        Transaction::record_many(guild_id, reason, changes, Some(session)).await?;
+        Ok(())
+    }
+
+    /// Locks the inventories of two different members, always the one with the lower id first,
+    /// for the same reason as `Balances::lock_pair`. The guards are returned in the order the
+    /// members were given.
+    ///
+    /// # Warning
+    /// The members must not be the same, and neither of their inventories may already be locked by
+    /// the caller, or this ***WILL*** deadlock.
+    pub async fn lock_pair<'a>(
+        first: (&'a ArcTokioMutexOption<Self>, DbUserId),
+        second: (&'a ArcTokioMutexOption<Self>, DbUserId)
+    ) -> (MutexGuard<'a, Option<Self>>, MutexGuard<'a, Option<Self>>) {
+        if first.1 < second.1 {
+            let first = first.0.lock().await;
+            let second = second.0.lock().await;
+            (first, second)
+        } else {
+            let second = second.0.lock().await;
+            let first = first.0.lock().await;
+            (first, second)
+        }
+    }
+
+    /// Drops the inventories of every member of a guild from the cache, like `invalidate_cache`
+    /// does for one.
+    pub async fn invalidate_guild_cache(guild_id: DbGuildId) {
+        crate::db::uncache_mutex(&CACHE_INVENTORY, |(g, _)| *g == guild_id).await;
+    }
+
+    /// Consumes a `MutexGuard` to an inventory and removes it from the cache, so that it is
+    /// fetched again from the database next time.
+    ///
+    /// Use this in case a transaction fails and you want to invalidate the cache.
+    ///
+    /// # Errors
+    /// - The inventory is being used in a breaking operation.
+    pub async fn invalidate_cache(mut self_: MutexGuard<'_, Option<Self>>) -> Result<()> {
+        let Some(self__) = self_.take() else {
+            bail!("Inventory is being used in a breaking operation.");
+        };
+        let mut cache = CACHE_INVENTORY.lock().await;
+        cache.pop(&(self__.guild_id, self__.user_id));
+        drop(cache);
+        drop(self_);
+        Ok(())
+    }
 }
 
 impl InventoryEntry {
@@ -405,6 +570,7 @@ impl InventoryEntry {
         user_id: DbUserId,
         item_name: String,
         amount: i64,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<Self> {
         let db = crate::db::CLIENT.get().await.database("conebot");
@@ -428,10 +594,18 @@ impl InventoryEntry {
             amount,
         };
 
+        let change = TransactionChange::Item {
+            item_name: new_self.item_name.clone(),
+            delta: amount,
+            resulting: amount,
+        };
+
         if let Some(s) = session {
             coll.insert_one_with_session(&new_self, None, s).await?;
+            Transaction::record(guild_id, user_id, reason, change, Some(s)).await?;
         } else {
             coll.insert_one(&new_self, None).await?;
+            Transaction::record(guild_id, user_id, reason, change, None).await?;
         }
 
         Ok(new_self)
@@ -489,6 +663,31 @@ impl InventoryEntry {
         Ok(users_with_inventories)
     }
 
+    /// Gets a cursor over the inventory entries of a guild, of only one item if a name is given,
+    /// sorted by item and then by member. Like `Balances::cursor_of_guild`, nothing goes through
+    /// the cache.
+    ///
+    /// # Errors
+    /// - Any mongodb error occurs.
+    pub async fn cursor_of_guild(
+        guild_id: DbGuildId,
+        item_name: Option<&str>
+    ) -> Result<Cursor<Self>> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("inventories");
+
+        let mut filterdoc = doc! {
+            "GuildId": guild_id.as_i64(),
+        };
+        if let Some(item_name) = item_name {
+            filterdoc.insert("ItemName", item_name);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! { "ItemName": 1, "UserId": 1 })
+            .build();
+        Ok(coll.find(filterdoc, options).await?)
+    }
+
     // I do not understand how this can be a const Fn because DbGuildId just holds a string, and
     // string borrows are not allowed in const fns.
     pub const fn guild_id(&self) -> DbGuildId {
@@ -543,15 +742,20 @@ impl InventoryEntry {
         Ok(())
     }
 
-    /// Sets the amount of the item in the inventory.
+    /// Sets the amount of the item in the inventory and writes the change to the ledger.
+    ///
+    /// The change written to the ledger is worked out from the amount in the database right
+    /// before the update, not the amount here.
     ///
     /// # Errors
     /// - The amount is negative.
+    /// - The entry does not exist in the database.
     /// - Any mongodb error occurs.
     pub async fn set_amount(
         &mut self,
         amount: i64,
-        session: Option<&mut ClientSession>
+        reason: TransactionReason,
+        mut session: Option<&mut ClientSession>
     ) -> Result<(), InventoryError> {
         // The negative check. The one I said I need earlier.
         if amount < 0 {
@@ -572,59 +776,140 @@ impl InventoryEntry {
                 "Amount": amount,
             }
         };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::Before)
+            .build();
 
-        if let Some(s) = session {
+        let before = if let Some(s) = session.as_deref_mut() {
             coll
-                .update_one_with_session(filterdoc, updatedoc, None, s).await
-                .map_err(|e| InventoryError::Other(e.into()))?;
+                .find_one_and_update_with_session(filterdoc, updatedoc, options, s).await
+                .map_err(|e| InventoryError::Other(e.into()))?
         } else {
             coll
-                .update_one(filterdoc, updatedoc, None).await
-                .map_err(|e| InventoryError::Other(e.into()))?;
-        }
+                .find_one_and_update(filterdoc, updatedoc, options).await
+                .map_err(|e| InventoryError::Other(e.into()))?
+        };
+        let before = before.ok_or(InventoryError::ZeroOrNotExists)?;
 
+        let change = TransactionChange::Item {
+            item_name: self.item_name.clone(),
+            delta: amount - before.amount, // Both are non-negative so this can't overflow.
+            resulting: amount,
+        };
+        Transaction::record(self.guild_id, self.user_id, reason, change, session).await?;
         self.amount = amount;
 
         Ok(())
     }
 
-    /// Subtracts the specified amount to the item in the inventory.
+    /// Subtracts the specified amount from the item in the inventory.
     ///
-    /// Just an alias to `set_amount(self.amount - amount)`.
+    /// Whether there is enough is decided by the database rather than the amount here, so two
+    /// subtractions at once can never both take the same items.
     ///
     /// # Errors
-    /// - The amount is negative.
     /// - Any mongodb error occurs.
-    /// - The amount underflows.
+    /// - There is less of the item than the amount.
+    /// - The amount overflows.
     #[inline]
     pub async fn sub_amount(
         &mut self,
         amount: i64,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<(), InventoryError> {
-        self.set_amount(
-            self.amount.checked_sub(amount).ok_or(InventoryError::AmountUnderflow)?,
-            session
-        ).await
+        let delta = amount.checked_neg().ok_or(InventoryError::AmountOverflow)?;
+        self.inc_amount(delta, reason, session).await
     }
 
     /// Adds the specified amount to the item in the inventory.
     ///
-    /// Just an alias to `set_amount(self.amount + amount)`.
-    ///
     /// # Errors
-    /// - The amount is negative.
+    /// - The amount would go below zero.
     /// - Any mongodb error occurs.
     /// - The amount overflows.
     #[inline]
     pub async fn add_amount(
         &mut self,
         amount: i64,
+        reason: TransactionReason,
         session: Option<&mut ClientSession>
     ) -> Result<(), InventoryError> {
-        self.set_amount(
-            self.amount.checked_add(amount).ok_or(InventoryError::AmountOverflow)?,
-            session
-        ).await
+        self.inc_amount(amount, reason, session).await
+    }
+
+    /// Changes the amount by `delta` with one conditional `$inc` that only matches if the amount
+    /// stays between zero and the most there can be, so the result is right no matter how out of
+    /// date the amount here is. Either way the amount here is refreshed from the database.
+    ///
+    /// # Errors
+    /// - Any mongodb error occurs.
+    /// - The entry does not exist in the database.
+    /// - The amount would go below zero or overflow.
+    async fn inc_amount(
+        &mut self,
+        delta: i64,
+        reason: TransactionReason,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<(), InventoryError> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("inventories");
+
+        let filterdoc =
+            doc! {
+            "GuildId": self.guild_id.as_i64(),
+            "UserId": self.user_id.as_i64(),
+            "ItemName": &self.item_name,
+        };
+        let mut conditional = filterdoc.clone();
+        conditional.insert("Amount", inc_bounds(delta, Some(0)));
+        let updatedoc =
+            doc! {
+            "$inc": {
+                "Amount": delta,
+            }
+        };
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let updated = if let Some(s) = session.as_deref_mut() {
+            coll
+                .find_one_and_update_with_session(conditional, updatedoc, options, s).await
+                .map_err(|e| InventoryError::Other(e.into()))?
+        } else {
+            coll
+                .find_one_and_update(conditional, updatedoc, options).await
+                .map_err(|e| InventoryError::Other(e.into()))?
+        };
+        let Some(updated) = updated else {
+            // Find out why it did not match, and bring the amount here up to date while at it.
+            let current = if let Some(s) = session {
+                coll.find_one_with_session(filterdoc, None, s).await
+            } else {
+                coll.find_one(filterdoc, None).await
+            };
+            let current = current
+                .map_err(|e| InventoryError::Other(e.into()))?
+                .ok_or(InventoryError::ZeroOrNotExists)?;
+            self.amount = current.amount;
+            return Err(
+                if delta < 0 {
+                    InventoryError::AmountUnderflow
+                } else {
+                    InventoryError::AmountOverflow
+                }
+            );
+        };
+
+        let change = TransactionChange::Item {
+            item_name: self.item_name.clone(),
+            delta,
+            resulting: updated.amount,
+        };
+        Transaction::record(self.guild_id, self.user_id, reason, change, session).await?;
+        self.amount = updated.amount;
+
+        Ok(())
     }
 }
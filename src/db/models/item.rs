@@ -26,11 +26,12 @@ use thiserror::Error;
 use tokio::sync::{ Mutex, RwLock, RwLockWriteGuard };
 
 use super::ToKVs;
+use crate::util::money::Money;
 
 /// Represents an item a user can hold in their inventory. May or may not
 /// be worth something or be used in return for something.
 #[allow(clippy::unsafe_derive_deserialize)]
-#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd)]
 #[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
 pub struct Item {
     /// The guild id of the guild this item belongs to.
@@ -46,7 +47,7 @@ pub struct Item {
     /// The currency the item corresponds to.
     currency: String,
     /// The value of the item in the currency it corresponds to.
-    value: f64,
+    value: Money,
     // Serde flatten is here so the item does not end up like
     /*
     {
@@ -502,7 +503,7 @@ impl Item {
         &self.currency
     }
 
-    pub const fn value(&self) -> f64 {
+    pub const fn value(&self) -> Money {
         self.value
     }
 
@@ -736,7 +737,7 @@ impl Item {
 
     pub async fn update_value(
         &mut self,
-        new_value: f64,
+        new_value: Money,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
         let db = crate::db::CLIENT.get().await.database("conebot");
@@ -923,6 +924,11 @@ impl Item {
         Ok(())
     }
 
+    /// Drops every item of a guild from the cache, like `invalidate_cache` does for one.
+    pub async fn invalidate_guild_cache(guild_id: DbGuildId) {
+        crate::db::uncache_rw(&CACHE_ITEM, |(g, _)| *g == guild_id).await;
+    }
+
     pub async fn invalidate_cache(mut self_: RwLockWriteGuard<'_, Option<Self>>) -> Result<()> {
         let mut cache = CACHE_ITEM.lock().await;
         let item = self_
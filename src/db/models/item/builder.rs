@@ -7,6 +7,7 @@ use super::{
     ItemType,
 };
 use crate::db::{ uniques::{ DbGuildId, DbRoleId }, ArcTokioRwLockOption };
+use crate::util::money::Money;
 use anyhow::{ anyhow, bail, Result };
 use mongodb::bson::doc;
 use serde::{ Deserialize, Serialize };
@@ -18,7 +19,7 @@ pub struct Builder {
     sellable: Option<bool>,
     tradeable: Option<bool>,
     currency_value: Option<String>,
-    value: Option<f64>,
+    value: Option<Money>,
     item_type: Option<ItemType>,
 }
 
@@ -105,7 +106,7 @@ impl Builder {
         self
     }
 
-    pub fn value(&mut self, value: Option<f64>) -> &mut Self {
+    pub fn value(&mut self, value: Option<Money>) -> &mut Self {
         self.value = value;
         self
     }
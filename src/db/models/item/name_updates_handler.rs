@@ -2,7 +2,10 @@ use anyhow::Result;
 
 use mongodb::ClientSession;
 
-use crate::db::{ models::{ store::Store, DropTable, Inventory }, uniques::DbGuildId };
+use crate::db::{
+    models::{ claim::ClaimConfig, store::Store, DropTable, Inventory },
+    uniques::DbGuildId,
+};
 
 /// Handles the name updates for items.
 ///
@@ -10,6 +13,7 @@ use crate::db::{ models::{ store::Store, DropTable, Inventory }, uniques::DbGuil
 /// - [`Inventory`] (write)
 /// - [`DropTable`] (write)
 /// - [`StoreEntry`] (write)
+/// - [`ClaimConfig`] (write)
 pub async fn handle_name_updates(
     guild_id: DbGuildId,
     before: String,
@@ -25,5 +29,8 @@ pub async fn handle_name_updates(
     // STORE ENTRIES
     Store::bulk_update_item_name(guild_id, &before, &after, Some(session)).await?;
 
+    // CLAIM REWARDS
+    ClaimConfig::bulk_update_item_name(guild_id, &before, &after, Some(session)).await?;
+
     Ok(())
 }
@@ -0,0 +1,411 @@
+//! This module contains the structs behind the rob command.
+//!
+//! `RobConfig` is how a guild set up robbing for one currency, and lives in the `robConfigs`
+//! collection. A currency without one can not be robbed.
+//!
+//! `RobCooldown` is when a member can next rob, or be robbed, in a currency. They live in the
+//! `robCooldowns` collection and are written in the same transaction that moves the money, so two
+//! robberies at the same time can not both get through.
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Duration, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime },
+    options::{ IndexOptions, ReplaceOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+use serde_with::{ serde_as, DurationSeconds };
+
+use crate::db::{ uniques::{ DbGuildId, DbUserId }, CLIENT };
+
+/// Where the fine of a failed robbery goes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FineTarget {
+    /// To the member who was almost robbed.
+    Victim,
+    /// Nowhere, it is taken out of the economy.
+    Burn,
+}
+
+impl FineTarget {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Victim => "victim",
+            Self::Burn => "burn",
+        }
+    }
+}
+
+impl std::str::FromStr for FineTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "victim" => Ok(Self::Victim),
+            "burn" => Ok(Self::Burn),
+            _ => Err(anyhow!("Fines either go to the victim or are burned.")),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct RobConfig {
+    guild_id: DbGuildId,
+    curr_name: String,
+    enabled: bool,
+    /// The chance a robbery works is never less than this, however rich the robber is.
+    min_chance: f64,
+    /// The chance a robbery works is never more than this, however rich the victim is.
+    max_chance: f64,
+    /// The most of the victim's balance that can be stolen at once, as a fraction.
+    max_steal: f64,
+    /// What a failed robbery costs, as a fraction of the robber's balance.
+    fine: f64,
+    fine_target: FineTarget,
+    /// How long a member has to wait before robbing again, whether it worked or not.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    robber_cooldown: Duration,
+    /// How long a member can not be robbed again after being robbed.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    victim_cooldown: Duration,
+}
+
+impl RobConfig {
+    /// How robbing is set up for a currency that never configured it. It is turned off.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn default_for(guild_id: DbGuildId, curr_name: String) -> Self {
+        Self {
+            guild_id,
+            curr_name,
+            enabled: false,
+            min_chance: 0.2,
+            max_chance: 0.8,
+            max_steal: 0.25,
+            fine: 0.1,
+            fine_target: FineTarget::Victim,
+            robber_cooldown: Duration::hours(1),
+            victim_cooldown: Duration::minutes(30),
+        }
+    }
+
+    /// Sets up robbing for a currency, replacing how it was set up before.
+    ///
+    /// # Errors
+    /// - A chance or fraction is not from 0 to 1.
+    /// - The minimum chance is more than the maximum chance.
+    /// - A cooldown is negative.
+    /// - Any `MongoDB` error occurs.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn set(
+        guild_id: DbGuildId,
+        curr_name: String,
+        enabled: bool,
+        min_chance: f64,
+        max_chance: f64,
+        max_steal: f64,
+        fine: f64,
+        fine_target: FineTarget,
+        robber_cooldown: Duration,
+        victim_cooldown: Duration
+    ) -> Result<Self> {
+        for (name, fraction) in [
+            ("minimum chance", min_chance),
+            ("maximum chance", max_chance),
+            ("most stolen", max_steal),
+            ("fine", fine),
+        ] {
+            if !(0.0..=1.0).contains(&fraction) {
+                bail!("The {name} must be from 0% to 100%.");
+            }
+        }
+        if min_chance > max_chance {
+            bail!("The minimum chance cannot be more than the maximum chance.");
+        }
+        if robber_cooldown < Duration::zero() || victim_cooldown < Duration::zero() {
+            bail!("Cooldowns cannot be negative.");
+        }
+        let new_self = Self {
+            guild_id,
+            curr_name,
+            enabled,
+            min_chance,
+            max_chance,
+            max_steal,
+            fine,
+            fine_target,
+            robber_cooldown,
+            victim_cooldown,
+        };
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robConfigs");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "CurrName": &new_self.curr_name };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one(filterdoc, &new_self, options).await?;
+        Ok(new_self)
+    }
+
+    /// Gets how robbing is set up for a currency, or `default_for` if it never was.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_currency(guild_id: DbGuildId, curr_name: &str) -> Result<Self> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robConfigs");
+
+        let filterdoc = doc! { "GuildId": guild_id.as_i64(), "CurrName": curr_name };
+        let config = coll.find_one(filterdoc, None).await?;
+        Ok(config.unwrap_or_else(|| Self::default_for(guild_id, curr_name.to_owned())))
+    }
+
+    /// Gets every currency of a guild that robbing was set up for.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_guild(guild_id: DbGuildId) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robConfigs");
+
+        let res = coll.find(doc! { "GuildId": guild_id.as_i64() }, None).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Updates the currency name of the rob config of a renamed currency.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robConfigs");
+        bulk_update_currency_name(coll, guild_id, old_name, new_name, session).await
+    }
+
+    /// The chance that a robbery works. It is the victim's share of what the two of them have
+    /// together, so robbing someone richer than you is easier, kept between the minimum and
+    /// maximum chance.
+    #[allow(clippy::must_use_candidate)]
+    pub fn success_chance(&self, robber_amount: f64, victim_amount: f64) -> f64 {
+        let total = robber_amount.max(0.0) + victim_amount.max(0.0);
+        let chance = if total > 0.0 { victim_amount.max(0.0) / total } else { 0.0 };
+        chance.clamp(self.min_chance, self.max_chance)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn curr_name(&self) -> &str {
+        &self.curr_name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn min_chance(&self) -> f64 {
+        self.min_chance
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn max_chance(&self) -> f64 {
+        self.max_chance
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn max_steal(&self) -> f64 {
+        self.max_steal
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn fine(&self) -> f64 {
+        self.fine
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn fine_target(&self) -> FineTarget {
+        self.fine_target
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn robber_cooldown(&self) -> Duration {
+        self.robber_cooldown
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn victim_cooldown(&self) -> Duration {
+        self.victim_cooldown
+    }
+}
+
+/// Whether a cooldown is on robbing or on being robbed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RobRole {
+    Robber,
+    Victim,
+}
+
+impl RobRole {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Robber => "robber",
+            Self::Victim => "victim",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct RobCooldown {
+    guild_id: DbGuildId,
+    user_id: DbUserId,
+    curr_name: String,
+    role: RobRole,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    ends_at: DateTime<Utc>,
+}
+
+impl RobCooldown {
+    /// Gets when the cooldown of a member in a role ends, if it has not ended by `now`. Read in
+    /// the session so that the cooldown is part of the transaction that starts the next one.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn active_until(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        curr_name: &str,
+        role: RobRole,
+        now: DateTime<Utc>,
+        session: &mut ClientSession
+    ) -> Result<Option<DateTime<Utc>>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "CurrName": curr_name,
+            "Role": role.as_str(),
+        };
+        let cooldown = coll.find_one_with_session(filterdoc, None, session).await?;
+        Ok(cooldown.map(|c| c.ends_at).filter(|ends_at| now < *ends_at))
+    }
+
+    /// Starts the cooldown of a member in a role, replacing the one before.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn start(
+        guild_id: DbGuildId,
+        user_id: DbUserId,
+        curr_name: &str,
+        role: RobRole,
+        ends_at: DateTime<Utc>,
+        session: &mut ClientSession
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robCooldowns");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "UserId": user_id.as_i64(),
+            "CurrName": curr_name,
+            "Role": role.as_str(),
+        };
+        let new_self = Self { guild_id, user_id, curr_name: curr_name.to_owned(), role, ends_at };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one_with_session(filterdoc, &new_self, options, session).await?;
+        Ok(())
+    }
+
+    /// Updates the currency name of every rob cooldown in a renamed currency.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robCooldowns");
+        bulk_update_currency_name(coll, guild_id, old_name, new_name, session).await
+    }
+
+    /// Creates the index that keeps one cooldown per member, currency and role.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("robCooldowns");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "UserId": 1, "CurrName": 1, "Role": 1 })
+            .options(
+                IndexOptions::builder().name("GuildUserCurrRole".to_owned()).unique(true).build()
+            )
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+}
+
+/// Both collections are keyed by the currency name, so they are renamed the same way.
+async fn bulk_update_currency_name<T: Send + Sync>(
+    coll: Collection<T>,
+    guild_id: DbGuildId,
+    old_name: &str,
+    new_name: &str,
+    session: Option<&mut ClientSession>
+) -> Result<()> {
+    let filterdoc = doc! {
+        "GuildId": guild_id.as_i64(),
+        "CurrName": old_name,
+    };
+    let updatedoc = doc! {
+        "$set": {
+            "CurrName": new_name,
+        }
+    };
+    if let Some(s) = session {
+        coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+    } else {
+        coll.update_many(filterdoc, updatedoc, None).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_success_chance() {
+        let config = RobConfig::default_for(DbGuildId::from(1_i64), "Coins".to_owned());
+        assert!((config.success_chance(50.0, 50.0) - 0.5).abs() < f64::EPSILON);
+        assert!((config.success_chance(25.0, 75.0) - 0.75).abs() < f64::EPSILON);
+        // Kept between the minimum and maximum chance.
+        assert!((config.success_chance(0.0, 100.0) - 0.8).abs() < f64::EPSILON);
+        assert!((config.success_chance(1000.0, 1.0) - 0.2).abs() < f64::EPSILON);
+        assert!((config.success_chance(0.0, 0.0) - 0.2).abs() < f64::EPSILON);
+    }
+}
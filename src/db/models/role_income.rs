@@ -0,0 +1,368 @@
+//! This module contains the `RoleIncome` struct and its methods.
+//!
+//! A role income is a rule that says "members with this role earn this much of this currency every
+//! so often", like a salary. Each rule has a scheduled job of its own that pays everyone holding the
+//! role, see `crate::mechanics::role_income`.
+//!
+//! Rules are identified by their role and currency together, so a role can earn several currencies
+//! but only one amount of each. Each rule also has an `ObjectId` which the scheduled job refers to,
+//! so that renaming the currency does not break the job.
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ Duration, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, oid::ObjectId },
+    error::{ ErrorKind, WriteFailure },
+    options::IndexOptions,
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+use serde_with::{ serde_as, DurationSeconds };
+
+use crate::{
+    db::{ uniques::{ DbGuildId, DbRoleId }, CLIENT },
+    mechanics::role_income::JOB_KIND,
+    util::money::Money,
+};
+
+use super::scheduled_job::{ Schedule, ScheduledJob };
+
+/// The shortest interval a role income may be paid out at.
+pub const MIN_INTERVAL_SECONDS: i64 = 60;
+/// The error code `MongoDB` returns when a unique index is violated.
+const DUPLICATE_KEY: i32 = 11000;
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct RoleIncome {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    guild_id: DbGuildId,
+    role_id: DbRoleId,
+    curr_name: String,
+    /// How much every member with the role gets per payout.
+    amount: Money,
+    /// How long between payouts.
+    #[serde_as(as = "DurationSeconds<i64>")]
+    interval: Duration,
+}
+
+impl RoleIncome {
+    /// Creates a new role income and schedules its first payout one interval from now.
+    ///
+    /// # Errors
+    /// - The role already earns that currency.
+    /// - The amount is not positive or the interval is too short.
+    /// - Any `MongoDB` error occurs.
+    pub async fn new(
+        guild_id: DbGuildId,
+        role_id: DbRoleId,
+        curr_name: String,
+        amount: Money,
+        interval: Duration
+    ) -> Result<Self> {
+        validate(amount, interval)?;
+        if Self::try_from_role(guild_id, role_id, &curr_name).await?.is_some() {
+            bail!("That role already earns that currency.");
+        }
+        let new_self = Self {
+            id: ObjectId::new(),
+            guild_id,
+            role_id,
+            curr_name,
+            amount,
+            interval,
+        };
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res: Result<()> = async {
+            coll.insert_one_with_session(&new_self, None, &mut session).await?;
+            new_self.schedule(Some(&mut session)).await?;
+            Ok(())
+        }.await;
+        if let Err(e) = res {
+            session.abort_transaction().await?;
+            // The check above is only for a nicer error, the unique index is what keeps two
+            // creates at the same time from both getting through.
+            if is_duplicate_key(&e) {
+                bail!("That role already earns that currency.");
+            }
+            return Err(e);
+        }
+        session.commit_transaction().await?;
+        Ok(new_self)
+    }
+
+    /// Gets the role income of a role for a currency.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_from_role(
+        guild_id: DbGuildId,
+        role_id: DbRoleId,
+        curr_name: &str
+    ) -> Result<Option<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "RoleId": role_id.as_i64(),
+            "CurrName": curr_name,
+        };
+        Ok(coll.find_one(filterdoc, None).await?)
+    }
+
+    /// Gets a role income by its id.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_from_id(id: ObjectId) -> Result<Option<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        Ok(coll.find_one(doc! { "_id": id }, None).await?)
+    }
+
+    /// Gets all of the role incomes of a guild.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn from_guild(guild_id: DbGuildId) -> Result<Vec<Self>> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let res = coll.find(doc! { "GuildId": guild_id.as_i64() }, None).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Changes the amount and/or the interval. Changing the interval reschedules the next payout
+    /// to one new interval from now.
+    ///
+    /// # Errors
+    /// - The amount is not positive or the interval is too short.
+    /// - Any `MongoDB` error occurs.
+    pub async fn update(&mut self, amount: Option<Money>, interval: Option<Duration>) -> Result<()> {
+        let new_amount = amount.unwrap_or(self.amount);
+        let new_interval = interval.unwrap_or(self.interval);
+        validate(new_amount, new_interval)?;
+
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let updatedoc =
+            doc! {
+            "$set": {
+                "Amount": new_amount,
+                "Interval": new_interval.num_seconds(),
+            }
+        };
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res: Result<()> = async {
+            coll.update_one_with_session(
+                doc! { "_id": self.id },
+                updatedoc,
+                None,
+                &mut session
+            ).await?;
+            if new_interval != self.interval {
+                Self { interval: new_interval, ..self.clone() }.schedule(
+                    Some(&mut session)
+                ).await?;
+            }
+            Ok(())
+        }.await;
+        if let Err(e) = res {
+            session.abort_transaction().await?;
+            return Err(e);
+        }
+        session.commit_transaction().await?;
+
+        self.amount = new_amount;
+        self.interval = new_interval;
+        Ok(())
+    }
+
+    /// Deletes the role income and cancels its payouts.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn delete(self) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res: Result<()> = async {
+            coll.delete_one_with_session(doc! { "_id": self.id }, None, &mut session).await?;
+            ScheduledJob::cancel(&self.job_name(), Some(&mut session)).await?;
+            Ok(())
+        }.await;
+        if let Err(e) = res {
+            session.abort_transaction().await?;
+            return Err(e);
+        }
+        session.commit_transaction().await?;
+        Ok(())
+    }
+
+    /// Deletes every role income of a deleted currency and cancels their payouts.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn delete_for_currency(
+        guild_id: DbGuildId,
+        curr_name: &str,
+        mut session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let filterdoc = doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": curr_name,
+        };
+        let incomes: Vec<Self> = if let Some(s) = session.as_deref_mut() {
+            let mut cursor = coll.find_with_session(filterdoc.clone(), None, &mut *s).await?;
+            cursor.stream(s).try_collect().await?
+        } else {
+            coll.find(filterdoc.clone(), None).await?.try_collect().await?
+        };
+        for income in &incomes {
+            ScheduledJob::cancel(&income.job_name(), session.as_deref_mut()).await?;
+        }
+        if let Some(s) = session {
+            coll.delete_many_with_session(filterdoc, None, s).await?;
+        } else {
+            coll.delete_many(filterdoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the index that keeps one role income per role and currency, which `new` relies
+    /// on.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "RoleId": 1, "CurrName": 1 })
+            .options(
+                IndexOptions::builder().name("GuildRoleCurr".to_owned()).unique(true).build()
+            )
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+
+    /// Updates the currency name of every role income in a guild paying the old currency name.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("roleIncomes");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "CurrName": old_name,
+        };
+        let updatedoc = doc! {
+            "$set": {
+                "CurrName": new_name,
+            }
+        };
+        if let Some(s) = session {
+            coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_many(filterdoc, updatedoc, None).await?;
+        }
+        Ok(())
+    }
+
+    async fn schedule(&self, session: Option<&mut ClientSession>) -> Result<()> {
+        ScheduledJob::upsert(
+            self.job_name(),
+            JOB_KIND,
+            doc! { "Id": self.id },
+            Schedule::Interval { seconds: self.interval.num_seconds() },
+            Utc::now() + self.interval,
+            session
+        ).await?;
+        Ok(())
+    }
+
+    /// The name of the scheduled job paying out this role income.
+    pub fn job_name(&self) -> String {
+        format!("{JOB_KIND}:{}", self.id.to_hex())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn guild_id(&self) -> DbGuildId {
+        self.guild_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn role_id(&self) -> DbRoleId {
+        self.role_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn curr_name(&self) -> &str {
+        &self.curr_name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn amount(&self) -> Money {
+        self.amount
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+fn is_duplicate_key(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<mongodb::error::Error>().is_some_and(|e| {
+        matches!(
+            *e.kind,
+            ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                if write_error.code == DUPLICATE_KEY
+        )
+    })
+}
+
+fn validate(amount: Money, interval: Duration) -> Result<()> {
+    if amount <= Money::ZERO {
+        return Err(anyhow!("The amount must be more than 0."));
+    }
+    if interval.num_seconds() < MIN_INTERVAL_SECONDS {
+        return Err(anyhow!("The interval must be at least {MIN_INTERVAL_SECONDS} seconds."));
+    }
+    Ok(())
+}
@@ -0,0 +1,372 @@
+//! This module contains the `ScheduledJob` struct and the `Schedule` enum.
+//!
+//! Scheduled jobs are stored in the `scheduledJobs` collection so that they survive restarts. They do
+//! not know how to run themselves, they only store what kind of job they are, an arbitrary payload and
+//! when they should run next. The scheduler in `crate::scheduler` is what looks up the handler for the
+//! kind and runs it.
+//!
+//! Every job is identified by a unique name which is also its `_id`. Features that need a job should
+//! pick a name that they can rebuild later, such as `interest:<guild id>:<currency name>`, so they can
+//! reschedule or cancel it without having to remember anything else.
+
+use std::str::FromStr;
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Datelike, Duration, NaiveTime, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime, Document },
+    options::{ FindOptions, ReplaceOptions },
+    ClientSession,
+    Collection,
+};
+use serde::{ Deserialize, Serialize };
+
+/// When a job runs. All times are in UTC.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "Type", rename_all_fields = "PascalCase")]
+pub enum Schedule {
+    /// Runs a single time and is then removed.
+    Once,
+    /// Runs every so many seconds.
+    Interval {
+        seconds: i64,
+    },
+    /// Runs every day at the given time.
+    Daily {
+        hour: u32,
+        minute: u32,
+    },
+    /// Runs every week on the given day at the given time. Days start at 0 for Monday.
+    Weekly {
+        weekday: u32,
+        hour: u32,
+        minute: u32,
+    },
+    /// Runs whenever a cron expression matches, such as `30 8 * * Mon-Fri`. The expression has
+    /// five fields (minute, hour, day of the month, month and day of the week), or six if it starts
+    /// with the second.
+    Cron {
+        expression: String,
+    },
+}
+
+impl Schedule {
+    /// Checks that the schedule makes sense.
+    ///
+    /// # Errors
+    /// - The interval is not positive.
+    /// - The hour, minute or weekday is out of range.
+    /// - The cron expression cannot be parsed.
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            Self::Once => {}
+            Self::Interval { seconds } => {
+                if seconds <= 0 {
+                    bail!("Interval must be positive.");
+                }
+            }
+            Self::Daily { hour, minute } => {
+                if NaiveTime::from_hms_opt(hour, minute, 0).is_none() {
+                    bail!("Invalid time of day.");
+                }
+            }
+            Self::Weekly { weekday, hour, minute } => {
+                if weekday > 6 {
+                    bail!("Weekday must be between 0 (Monday) and 6 (Sunday).");
+                }
+                if NaiveTime::from_hms_opt(hour, minute, 0).is_none() {
+                    bail!("Invalid time of day.");
+                }
+            }
+            Self::Cron { ref expression } => {
+                parse_cron(expression)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Works out when the job should run next after having been due at `prev`, given that it is
+    /// currently `now`. Occurrences that were missed, for example because the bot was offline, are
+    /// skipped, so the result is always after `now`.
+    ///
+    /// Returns `None` for `Once`, since it never runs again, and for a cron expression that is
+    /// invalid or never matches again.
+    pub fn next_run(&self, prev: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match *self {
+            Self::Once => None,
+            Self::Interval { seconds } => {
+                let seconds = seconds.max(1);
+                let behind = (now - prev).num_seconds().max(0);
+                Some(prev + Duration::seconds((behind / seconds + 1) * seconds))
+            }
+            Self::Daily { hour, minute } => {
+                let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+                let candidate = now.date_naive().and_time(time).and_utc();
+                if candidate > now {
+                    Some(candidate)
+                } else {
+                    Some(candidate + Duration::days(1))
+                }
+            }
+            Self::Weekly { weekday, hour, minute } => {
+                let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+                let days_ahead = (i64::from(weekday) -
+                    i64::from(now.weekday().num_days_from_monday())).rem_euclid(7);
+                let candidate =
+                    now.date_naive().and_time(time).and_utc() + Duration::days(days_ahead);
+                if candidate > now {
+                    Some(candidate)
+                } else {
+                    Some(candidate + Duration::weeks(1))
+                }
+            }
+            Self::Cron { ref expression } => parse_cron(expression).ok()?.after(&now).next(),
+        }
+    }
+}
+
+/// Parses a cron expression. The `cron` crate wants the seconds as well, so they are taken to be 0
+/// when the expression leaves them out.
+fn parse_cron(expression: &str) -> Result<cron::Schedule> {
+    let expression = expression.trim();
+    let res = if expression.split_whitespace().count() == 5 {
+        cron::Schedule::from_str(&format!("0 {expression}"))
+    } else {
+        cron::Schedule::from_str(expression)
+    };
+    res.map_err(|e| anyhow!("Invalid cron expression: {e}"))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct ScheduledJob {
+    /// The unique name of the job.
+    #[serde(rename = "_id")]
+    name: String,
+    /// Which handler runs this job.
+    kind: String,
+    /// Anything the handler needs to know to run the job.
+    payload: Document,
+    schedule: Schedule,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    next_run: DateTime<Utc>,
+    /// How many times this job has been claimed to run.
+    runs: i64,
+}
+
+impl ScheduledJob {
+    /// Schedules a job to first run at `first_run`. If a job with the same name already exists it
+    /// is replaced, so this can be called again to change the schedule of an existing job.
+    ///
+    /// # Errors
+    /// - The schedule is invalid.
+    /// - Any `MongoDB` error occurs.
+    pub async fn upsert(
+        name: String,
+        kind: &str,
+        payload: Document,
+        schedule: Schedule,
+        first_run: DateTime<Utc>,
+        session: Option<&mut ClientSession>
+    ) -> Result<Self> {
+        schedule.validate()?;
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("scheduledJobs");
+
+        let job = Self {
+            name,
+            kind: kind.to_owned(),
+            payload,
+            schedule,
+            next_run: first_run,
+            runs: 0,
+        };
+        let filterdoc = doc! { "_id": &job.name };
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        if let Some(s) = session {
+            coll.replace_one_with_session(filterdoc, &job, options, s).await?;
+        } else {
+            coll.replace_one(filterdoc, &job, options).await?;
+        }
+        Ok(job)
+    }
+
+    /// Removes a job so that it never runs again. Does nothing if the job does not exist.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn cancel(name: &str, session: Option<&mut ClientSession>) -> Result<()> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("scheduledJobs");
+
+        let filterdoc = doc! { "_id": name };
+        if let Some(s) = session {
+            coll.delete_one_with_session(filterdoc, None, s).await?;
+        } else {
+            coll.delete_one(filterdoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Gets a job by its name.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn try_from_name(name: &str) -> Result<Option<Self>> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("scheduledJobs");
+
+        Ok(coll.find_one(doc! { "_id": name }, None).await?)
+    }
+
+    /// Gets every job of one of the given kinds that is due to run at `now`, soonest first.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn due(kinds: &[&str], now: DateTime<Utc>) -> Result<Vec<Self>> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("scheduledJobs");
+
+        let filterdoc =
+            doc! {
+            "Kind": { "$in": kinds },
+            "NextRun": { "$lte": mongodb::bson::DateTime::from_chrono(now) },
+        };
+        let options = FindOptions::builder().sort(doc! { "NextRun": 1 }).build();
+        let res = coll.find(filterdoc, options).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Attempts to claim this occurrence of the job so that it can be run.
+    ///
+    /// Claiming moves the job on to its next run, or removes it if it only runs once, in a single
+    /// atomic operation that only succeeds if the job is still due at the time this struct was
+    /// fetched. This means that out of any number of schedulers racing for the same occurrence,
+    /// exactly one gets `true` back, and it will not be claimed again after a restart.
+    ///
+    /// A job that was claimed but whose handler failed is not retried.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn claim(&mut self, now: DateTime<Utc>) -> Result<bool> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("scheduledJobs");
+
+        let filterdoc =
+            doc! {
+            "_id": &self.name,
+            "NextRun": mongodb::bson::DateTime::from_chrono(self.next_run),
+        };
+
+        let Some(next_run) = self.schedule.next_run(self.next_run, now) else {
+            let res = coll.delete_one(filterdoc, None).await?;
+            return Ok(res.deleted_count == 1);
+        };
+        let updatedoc =
+            doc! {
+            "$set": { "NextRun": mongodb::bson::DateTime::from_chrono(next_run) },
+            "$inc": { "Runs": 1_i64 },
+        };
+        let res = coll.update_one(filterdoc, updatedoc, None).await?;
+        if res.modified_count == 0 {
+            return Ok(false);
+        }
+        self.next_run = next_run;
+        self.runs += 1;
+        Ok(true)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn payload(&self) -> &Document {
+        &self.payload
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn next_run(&self) -> DateTime<Utc> {
+        self.next_run
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn runs(&self) -> i64 {
+        self.runs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(d: u32, h: u32, m: u32) -> DateTime<Utc> {
+        // 2024-01-01 is a Monday.
+        Utc.with_ymd_and_hms(2024, 1, d, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_interval_skips_missed_runs() {
+        let schedule = Schedule::Interval { seconds: 3600 };
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(1, 0, 0)), Some(at(1, 1, 0)));
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(1, 0, 30)), Some(at(1, 1, 0)));
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(1, 5, 30)), Some(at(1, 6, 0)));
+    }
+
+    #[test]
+    fn test_daily() {
+        let schedule = Schedule::Daily { hour: 12, minute: 0 };
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(1, 11, 59)), Some(at(1, 12, 0)));
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(1, 12, 0)), Some(at(2, 12, 0)));
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(3, 18, 0)), Some(at(4, 12, 0)));
+    }
+
+    #[test]
+    fn test_weekly() {
+        let schedule = Schedule::Weekly { weekday: 2, hour: 8, minute: 30 };
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(1, 0, 0)), Some(at(3, 8, 30)));
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(3, 8, 30)), Some(at(10, 8, 30)));
+        assert_eq!(schedule.next_run(at(1, 0, 0), at(4, 0, 0)), Some(at(10, 8, 30)));
+    }
+
+    #[test]
+    fn test_once_and_validate() {
+        assert_eq!(Schedule::Once.next_run(at(1, 0, 0), at(2, 0, 0)), None);
+        assert!((Schedule::Interval { seconds: 0 }).validate().is_err());
+        assert!((Schedule::Daily { hour: 24, minute: 0 }).validate().is_err());
+        assert!((Schedule::Weekly { weekday: 7, hour: 0, minute: 0 }).validate().is_err());
+        assert!((Schedule::Weekly { weekday: 6, hour: 23, minute: 59 }).validate().is_ok());
+    }
+
+    #[test]
+    fn test_cron() {
+        let weekdays = Schedule::Cron { expression: "30 8 * * Mon-Fri".to_owned() };
+        assert!(weekdays.validate().is_ok());
+        assert_eq!(weekdays.next_run(at(1, 0, 0), at(1, 0, 0)), Some(at(1, 8, 30)));
+        assert_eq!(weekdays.next_run(at(1, 0, 0), at(1, 8, 30)), Some(at(2, 8, 30)));
+        // 2024-01-06 is a Saturday.
+        assert_eq!(weekdays.next_run(at(1, 0, 0), at(6, 0, 0)), Some(at(8, 8, 30)));
+
+        let with_seconds = Schedule::Cron { expression: "0 */15 * * * *".to_owned() };
+        assert_eq!(with_seconds.next_run(at(1, 0, 0), at(1, 0, 20)), Some(at(1, 0, 30)));
+
+        let invalid = Schedule::Cron { expression: "every day".to_owned() };
+        assert!(invalid.validate().is_err());
+        assert_eq!(invalid.next_run(at(1, 0, 0), at(1, 0, 0)), None);
+    }
+}
@@ -9,9 +9,10 @@ use serde::{ Deserialize, Serialize };
 use tokio::sync::{ Mutex, RwLock };
 
 use crate::db::{ uniques::DbGuildId, ArcTokioRwLockOption, TokioMutexCache, CLIENT };
+use crate::util::money::Money;
 
 /// The store of a guild, formed from all the store entries.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Store {
     guild_id: DbGuildId,
@@ -19,7 +20,7 @@ pub struct Store {
 }
 
 /// Represents an entry in the store of a guild.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct StoreEntry {
     /// The guild this entry belongs to.
@@ -29,7 +30,7 @@ pub struct StoreEntry {
     /// The name of the currency being used to buy the item.
     curr_name: String,
     /// The value of the item in the currency.
-    value: f64,
+    value: Money,
     /// Amount of items you get for the price.
     amount: i64, // Should not go into negatives. Enforce at runtime.
 }
@@ -60,11 +61,16 @@ impl Store {
         Ok(self_)
     }
 
+    /// Drops the store of a guild from the cache, so it is read again next time.
+    pub async fn invalidate_guild_cache(guild_id: DbGuildId) {
+        crate::db::uncache_rw(&CACHE_STORE, |g| *g == guild_id).await;
+    }
+
     pub async fn add_entry(
         &mut self,
         item_name: String,
         curr_name: String,
-        value: f64,
+        value: Money,
         amount: i64,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
@@ -94,7 +100,7 @@ impl Store {
         &mut self,
         item_name: &str,
         curr_name: &str,
-        value: f64,
+        value: Money,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
         let mut index = None;
@@ -216,7 +222,7 @@ impl StoreEntry {
         guild_id: DbGuildId,
         item_name: String,
         curr_name: String,
-        value: f64,
+        value: Money,
         amount: i64,
         session: Option<&mut ClientSession>
     ) -> Result<Self> {
@@ -305,7 +311,7 @@ impl StoreEntry {
         &self.curr_name
     }
 
-    pub const fn value(&self) -> f64 {
+    pub const fn value(&self) -> Money {
         self.value
     }
 
@@ -381,7 +387,7 @@ impl StoreEntry {
 
     pub async fn set_value(
         &mut self,
-        value: f64,
+        value: Money,
         session: Option<&mut ClientSession>
     ) -> Result<()> {
         let db = CLIENT.get().await.database("conebot");
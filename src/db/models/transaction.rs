@@ -0,0 +1,390 @@
+//! This module contains the Transaction struct, which is a single entry in the ledger.
+//!
+//! Every time a balance or an inventory entry changes, a transaction is written to the `transactions`
+//! collection describing who did it, who it happened to, what changed and why. Nothing in the bot
+//! ever reads the ledger to decide what a member owns, that is still the job of balances and
+//! inventories. The ledger exists so staff can look back and figure out where things came from
+//! when members dispute something.
+//!
+//! Entries are written by the methods that perform the mutation themselves, in the same session
+//! as the mutation if one was provided, so that if the transaction gets aborted the ledger entry
+//! goes away with it.
+
+use anyhow::Result;
+use chrono::{ DateTime, Utc };
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{ doc, serde_helpers::chrono_datetime_as_bson_datetime },
+    options::{ FindOptions, IndexOptions },
+    ClientSession,
+    Collection,
+    IndexModel,
+};
+use serde::{ Deserialize, Serialize };
+
+use crate::{ db::uniques::{ DbGuildId, DbUserId }, util::money::Money };
+
+/// Why a balance or inventory changed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    /// Currency earned by chatting.
+    ChatEarn,
+    /// Staff gave currency or items to a member.
+    Give,
+    /// Staff took currency from a member.
+    Take,
+    /// A member paid another member.
+    Pay,
+    /// A member bought an item from the store.
+    Buy,
+    /// A member sold an item.
+    Sell,
+    /// A member exchanged one currency for another.
+    Exchange,
+    /// Currency or items dropped from a lootbox.
+    LootboxDrop,
+    /// A member used an item.
+    ItemUse,
+    /// Income paid to members with a certain role.
+    RoleIncome,
+    /// Interest paid on, or wealth tax taken from, a balance.
+    Interest,
+    /// A daily or weekly reward.
+    Claim,
+    /// Currency earned by spending time in voice channels.
+    VoiceEarn,
+    /// Balances or items carried over from another bot.
+    Import,
+    /// A bet in one of the gambling games, or what it paid out.
+    Gamble,
+    /// A member robbed another, or was fined for trying.
+    Rob,
+    /// Something a member got or lost doing an activity staff set up, like `/work`.
+    Activity,
+    /// A member moved currency between their wallet and their bank.
+    Bank,
+    /// Two members swapped items or currency with a trade.
+    Trade,
+    /// A member gave some of their items to another.
+    Gift,
+}
+
+impl TransactionKind {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ChatEarn => "Chat earn",
+            Self::Give => "Give",
+            Self::Take => "Take",
+            Self::Pay => "Pay",
+            Self::Buy => "Buy",
+            Self::Sell => "Sell",
+            Self::Exchange => "Exchange",
+            Self::LootboxDrop => "Lootbox drop",
+            Self::ItemUse => "Item use",
+            Self::RoleIncome => "Role income",
+            Self::Interest => "Interest",
+            Self::Claim => "Claim",
+            Self::VoiceEarn => "Voice earn",
+            Self::Import => "Import",
+            Self::Gamble => "Gamble",
+            Self::Rob => "Rob",
+            Self::Activity => "Activity",
+            Self::Bank => "Bank",
+            Self::Trade => "Trade",
+            Self::Gift => "Gift",
+        }
+    }
+}
+
+/// Who caused a mutation and why. This gets passed to every method that changes a balance or an
+/// inventory so that the ledger entry can be written alongside the change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionReason {
+    actor: Option<DbUserId>,
+    kind: TransactionKind,
+}
+
+impl TransactionReason {
+    /// A mutation caused by a member, such as a purchase or a staff member giving currency.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn new(kind: TransactionKind, actor: DbUserId) -> Self {
+        Self { actor: Some(actor), kind }
+    }
+
+    /// A mutation that no member directly caused.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn system(kind: TransactionKind) -> Self {
+        Self { actor: None, kind }
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn actor(&self) -> Option<DbUserId> {
+        self.actor
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn kind(&self) -> TransactionKind {
+        self.kind
+    }
+}
+
+/// What changed in a transaction.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "Type", rename_all_fields = "PascalCase")]
+pub enum TransactionChange {
+    Currency {
+        curr_name: String,
+        delta: Money,
+        resulting: Money,
+    },
+    Item {
+        item_name: String,
+        delta: i64,
+        resulting: i64,
+    },
+    /// A change to the part of a balance that is kept in the bank.
+    Bank {
+        curr_name: String,
+        delta: Money,
+        resulting: Money,
+    },
+}
+
+/// A single entry in the ledger.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all(serialize = "PascalCase", deserialize = "PascalCase"))]
+pub struct Transaction {
+    guild_id: DbGuildId,
+    /// The member that caused the change, `None` if it was the bot itself.
+    actor: Option<DbUserId>,
+    /// The member whose balance or inventory changed.
+    target: DbUserId,
+    change: TransactionChange,
+    kind: TransactionKind,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    timestamp: DateTime<Utc>,
+}
+
+impl Transaction {
+    /// Writes a ledger entry for a change that just happened.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn record(
+        guild_id: DbGuildId,
+        target: DbUserId,
+        reason: TransactionReason,
+        change: TransactionChange,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("transactions");
+
+        let transaction = Self {
+            guild_id,
+            actor: reason.actor,
+            target,
+            change,
+            kind: reason.kind,
+            timestamp: Utc::now(),
+        };
+
+        if let Some(s) = session {
+            coll.insert_one_with_session(&transaction, None, s).await?;
+        } else {
+            coll.insert_one(&transaction, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes ledger entries for many members at once, all for the same reason.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn record_many(
+        guild_id: DbGuildId,
+        reason: TransactionReason,
+        changes: Vec<(DbUserId, TransactionChange)>,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("transactions");
+
+        let timestamp = Utc::now();
+        let transactions = changes.into_iter().map(|(target, change)| Self {
+            guild_id,
+            actor: reason.actor,
+            target,
+            change,
+            kind: reason.kind,
+            timestamp,
+        });
+
+        if let Some(s) = session {
+            coll.insert_many_with_session(transactions, None, s).await?;
+        } else {
+            coll.insert_many(transactions, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Fetches the most recent transactions of a member, newest first.
+    ///
+    /// If a currency name or an item name is given, only transactions touching that currency, in
+    /// the wallet or the bank, or that item are returned. If both are given, transactions touching
+    /// either of them are returned.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn history(
+        guild_id: DbGuildId,
+        target: DbUserId,
+        curr_name: Option<&str>,
+        item_name: Option<&str>,
+        limit: i64
+    ) -> Result<Vec<Self>> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("transactions");
+
+        let mut subjects = Vec::new();
+        if let Some(curr_name) = curr_name {
+            subjects.push(
+                doc! {
+                    "Change.Type": { "$in": ["Currency", "Bank"] },
+                    "Change.CurrName": curr_name,
+                }
+            );
+        }
+        if let Some(item_name) = item_name {
+            subjects.push(doc! { "Change.Type": "Item", "Change.ItemName": item_name });
+        }
+        let mut filterdoc = doc! {
+            "GuildId": guild_id.as_i64(),
+            "Target": target.as_i64(),
+        };
+        if !subjects.is_empty() {
+            filterdoc.insert("$or", subjects);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! { "Timestamp": -1 })
+            .limit(limit)
+            .build();
+
+        let res = coll.find(filterdoc, options).await?;
+        Ok(res.try_collect().await?)
+    }
+
+    /// Updates the currency name of every ledger entry in a guild about the old currency, in the
+    /// wallet or the bank, so that a member's history still shows it after a rename.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn bulk_update_currency_name(
+        guild_id: DbGuildId,
+        old_name: &str,
+        new_name: &str,
+        session: Option<&mut ClientSession>
+    ) -> Result<()> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("transactions");
+
+        let filterdoc =
+            doc! {
+            "GuildId": guild_id.as_i64(),
+            "Change.Type": { "$in": ["Currency", "Bank"] },
+            "Change.CurrName": old_name,
+        };
+        let updatedoc = doc! {
+            "$set": {
+                "Change.CurrName": new_name,
+            }
+        };
+        if let Some(s) = session {
+            coll.update_many_with_session(filterdoc, updatedoc, None, s).await?;
+        } else {
+            coll.update_many(filterdoc, updatedoc, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates the index used to look up a member's transactions, newest first.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs.
+    pub async fn create_indexes() -> Result<()> {
+        let db = crate::db::CLIENT.get().await.database("conebot");
+        let coll: Collection<Self> = db.collection("transactions");
+
+        let index = IndexModel::builder()
+            .keys(doc! { "GuildId": 1, "Target": 1, "Timestamp": -1 })
+            .options(IndexOptions::builder().name("GuildTargetTimestamp".to_owned()).build())
+            .build();
+        coll.create_index(index, None).await?;
+        Ok(())
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn guild_id(&self) -> DbGuildId {
+        self.guild_id
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn actor(&self) -> Option<DbUserId> {
+        self.actor
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn target(&self) -> DbUserId {
+        self.target
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn change(&self) -> &TransactionChange {
+        &self.change
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn kind(&self) -> TransactionKind {
+        self.kind
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mongodb::bson::{ from_document, to_document };
+
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let transaction = Transaction {
+            guild_id: 1.into(),
+            actor: Some(2.into()),
+            target: 3.into(),
+            change: TransactionChange::Currency {
+                curr_name: "Coins".to_owned(),
+                delta: Money::from_minor(-15_000),
+                resulting: Money::from_minor(85_000),
+            },
+            kind: TransactionKind::Pay,
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        };
+        let document = to_document(&transaction).unwrap();
+        let change = document.get_document("Change").unwrap();
+        assert_eq!(change.get_str("Type").unwrap(), "Currency");
+        assert_eq!(change.get_i64("Delta").unwrap(), -15_000);
+        assert_eq!(document.get_str("Kind").unwrap(), "Pay");
+        assert!(document.get_datetime("Timestamp").is_ok());
+        let back: Transaction = from_document(document).unwrap();
+        assert_eq!(back, transaction);
+    }
+}
@@ -1,8 +1,11 @@
 pub mod command_handler;
-mod message;
+pub mod message;
+mod voice;
 
 use crate::commands;
-use anyhow::anyhow;
+use crate::db::models::claim::ClaimKind;
+use crate::db::models::gambling::Game;
+use crate::mechanics::bank::BankAction;
 use anyhow::Result;
 use serenity::all::Command;
 use serenity::all::CommandInteraction;
@@ -13,7 +16,7 @@ use serenity::builder::EditInteractionResponse;
 use crate::event_handler::message::message;
 use serenity::async_trait;
 use serenity::client::EventHandler;
-use serenity::model::prelude::{ Message, Ready };
+use serenity::model::prelude::{ Message, Ready, VoiceState };
 use serenity::prelude::Context;
 use tracing::{ error, info, instrument };
 
@@ -34,13 +37,39 @@ impl Handler {
             "inv" => commands::inv::run(options, command, ctx).await?,
             "buy" => commands::buy::run(options, command, ctx).await?,
             "sell" => commands::sell::run(options, command, ctx).await?,
+            // These are boxed, like the activities below, to keep this function's future small.
+            "pay" => Box::pin(commands::pay::run(options, command, ctx)).await?,
+            "rob" => Box::pin(commands::rob::run(options, command, ctx)).await?,
+            "deposit" =>
+                Box::pin(commands::bank::run(BankAction::Deposit, options, command, ctx)).await?,
+            "withdraw" =>
+                Box::pin(commands::bank::run(BankAction::Withdraw, options, command, ctx)).await?,
+            "trade" => Box::pin(commands::trade::run(options, command, ctx)).await?,
+            "gift" => Box::pin(commands::gift::run(options, command, ctx)).await?,
+            "config_activity" =>
+                Box::pin(commands::config_activity::run(options, command, ctx)).await?,
+            "leaderboard" => commands::leaderboard::run(options, command, ctx).await?,
+            "history" => commands::history::run(options, command, ctx).await?,
+            "backup" => commands::backup::run(options, command, ctx).await?,
+            "import" => commands::import::run(options, command, ctx).await?,
+            "economy" => commands::economy::run(options, command, ctx).await?,
+            "daily" => commands::claim::run(ClaimKind::Daily, command, ctx).await?,
+            "weekly" => commands::claim::run(ClaimKind::Weekly, command, ctx).await?,
+            "coinflip" => commands::gamble::run(Game::Coinflip, options, command, ctx).await?,
+            "dice" => commands::gamble::run(Game::Dice, options, command, ctx).await?,
+            "slots" => commands::gamble::run(Game::Slots, options, command, ctx).await?,
+            "config_claim" => commands::config_claim::run(options, command, ctx).await?,
             "config_currency" => commands::config_currency::run(options, command, ctx).await?,
             "config_drop_table" => commands::config_drop_table::run(options, command, ctx).await?,
             "config_item" => commands::config_item::run(options, command, ctx).await?,
+            "config_gambling" => commands::config_gambling::run(options, command, ctx).await?,
+            "config_rob" => commands::config_rob::run(options, command, ctx).await?,
             "config_store" => commands::config_store::run(options, command, ctx).await?,
-            _ => {
-                return Err(anyhow!("Unknown command: {}", command.data.name));
-            }
+            "config_role_income" =>
+                commands::config_role_income::run(options, command, ctx).await?,
+            // Anything else may be an activity that staff set up in the guild. Boxed since handing
+            // out drops makes for a future much bigger than the other commands.
+            _ => Box::pin(commands::activity::run(command, ctx)).await?,
         }
         Ok(())
     }
@@ -56,6 +85,13 @@ impl EventHandler for Handler {
         // e
     }
 
+    #[instrument(skip(self, ctx), level = "debug")]
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        if let Err(e) = voice::voice_state_update(ctx, old, new).await {
+            error!("Error handling voice state update: {}", e);
+        }
+    }
+
     /// This function is responsible for initializing the global application commands.
     ///
     /// # Panics
@@ -64,6 +100,8 @@ impl EventHandler for Handler {
     #[instrument(skip_all)] // Required for tracing, since this would fill the output with a lot of junk if arguments were to be logged.
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("{} is running!", ready.user.name);
+        crate::scheduler::start(&ctx);
+        crate::mechanics::pending_earnings::start();
         if
             let Err(e) = Command::set_global_commands(
                 &ctx.http,
@@ -77,10 +115,31 @@ impl EventHandler for Handler {
                     commands::config_drop_table::command(),
                     commands::config_item::command(),
                     commands::config_store::command(),
+                    commands::config_role_income::command(),
+                    commands::config_claim::command(),
+                    commands::config_activity::command(),
+                    commands::config_gambling::command(),
+                    commands::config_rob::command(),
                     commands::use_item::command(),
                     commands::inv::command(),
                     commands::buy::command(),
-                    commands::sell::command()
+                    commands::sell::command(),
+                    commands::pay::command(),
+                    commands::rob::command(),
+                    commands::bank::deposit_command(),
+                    commands::bank::withdraw_command(),
+                    commands::trade::command(),
+                    commands::gift::command(),
+                    commands::leaderboard::command(),
+                    commands::history::command(),
+                    commands::backup::command(),
+                    commands::import::command(),
+                    commands::economy::command(),
+                    commands::claim::daily_command(),
+                    commands::claim::weekly_command(),
+                    commands::gamble::coinflip_command(),
+                    commands::gamble::dice_command(),
+                    commands::gamble::slots_command()
                 ]
             ).await
         {
@@ -1,6 +1,7 @@
 #![allow(clippy::must_use_candidate)]
 use anyhow::{ anyhow, Result };
 use serenity::all::{
+    AttachmentId,
     ChannelId,
     CommandDataOption,
     CommandDataOptionValue,
@@ -174,6 +175,23 @@ impl CommandOptions {
         }
     }
 
+    /// Given the name of the parameter, returns the value of the argument
+    /// as provided by the user if it exists as an attachment. The attachment itself is in the
+    /// resolved data of the interaction.
+    ///
+    /// # Errors
+    /// - If the option specified is not an attachment with `Some(Err)`.
+    /// - If the option does not exist with `None`.
+    /// - If the option is optional and there is no value with `None`.
+    pub fn get_attachment_value(&self, name: &str) -> Option<Result<AttachmentId>> {
+        let t = self.get_value_by_name(name)?;
+        if let CommandDataOptionValue::Attachment(a) = t {
+            Some(Ok(a))
+        } else {
+            Some(Err(anyhow!("Option {} is not an attachment.", name)))
+        }
+    }
+
     /// Returns the name of the subcommand or subcommand group and its options.
     ///
     /// # Errors
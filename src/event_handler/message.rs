@@ -1,28 +1,34 @@
-use crate::db::models::{ Balances, Currency };
+//! Earning currencies by chatting.
+//!
+//! This runs for every message the bot can see, so the common cases do not wait on Discord or the
+//! database. The currencies of the guild are cached (see `Currency::try_from_guild`), the member
+//! and channel come from the message and the serenity cache, members who are still on cooldown
+//! are turned away in memory, and earnings are queued in `pending_earnings` to be written in bulk.
+//! Only a message that starts a cooldown has to go to the database.
+
+use crate::db::models::{ blocked_earns::BlockedEarns, earn_cooldown::EarnCooldown, Currency };
 use crate::db::uniques::{ DbChannelId, DbRoleId };
-use crate::util::currency::truncate_2dp;
+use crate::db::ArcTokioRwLockOption;
+use crate::mechanics::anti_farming::{ self, MessageFacts };
+use crate::mechanics::pending_earnings;
+use crate::util::{ channel, money::Money };
 use anyhow::Result;
-use lazy_static::lazy_static;
+use chrono::{ DateTime, Utc };
 use rand::prelude::*;
-use serenity::all::ChannelId;
+use serenity::all::{ ChannelId, Timestamp };
 use serenity::client::Context;
 use serenity::model::channel::Message;
-use serenity::model::prelude::{ Channel, GuildId, Member, RoleId, UserId };
-use std::borrow::Cow;
-use std::collections::HashSet;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{ debug, error, warn };
-
-#[derive(Debug, Hash, Eq, PartialEq, Clone)]
-pub struct Timeout {
-    pub user: UserId,
-    pub guild: GuildId,
-    pub currency: String,
-}
-
-lazy_static! {
-    static ref TIMEOUTS: Arc<Mutex<HashSet<Timeout>>> = Arc::new(Mutex::new(HashSet::new()));
+use serenity::model::prelude::{ GuildId, RoleId, UserId };
+use tracing::{ debug, warn };
+
+/// Who sent a message and where, which is everything earning by chatting needs to know.
+pub struct Chatter<'a> {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub roles: &'a [RoleId],
+    /// The channel the message was sent in, followed by its parents (see `channel::lineage`).
+    pub channel_lineage: &'a [ChannelId],
+    pub facts: MessageFacts<'a>,
 }
 
 pub async fn message(ctx: Context, new_message: Message) -> Result<()> {
@@ -30,7 +36,6 @@ pub async fn message(ctx: Context, new_message: Message) -> Result<()> {
     if new_message.author.bot {
         return Ok(());
     }
-    let mut rand = rand::rngs::OsRng;
     let user: UserId = new_message.author.id; // this instead of Db<Whatever> because these implement `Copy`.
     let guild_id: GuildId = if let Some(g) = new_message.guild_id {
         g
@@ -38,14 +43,77 @@ pub async fn message(ctx: Context, new_message: Message) -> Result<()> {
         return Ok(());
     };
 
-    let balances = Balances::try_from_user(guild_id.into(), user.into()).await?;
-    let mut balances = balances.lock().await;
-    let Some(balances_) = balances.as_mut() else {
+    // Every message is remembered, even ones that do not earn, so that spam is always caught.
+    let repeated = anti_farming::remember_message(guild_id, user, &new_message.content);
+
+    let currencies = Currency::try_from_guild(guild_id.into()).await?;
+    if currencies.is_empty() {
         return Ok(());
+    }
+    let Some((roles, joined_at)) = author_member(&ctx, &new_message, guild_id).await else {
+        return Ok(());
+    };
+    let channel_lineage = channel::lineage(&ctx.cache, guild_id, new_message.channel_id);
+
+    let chatter = Chatter {
+        guild_id,
+        user_id: user,
+        roles: &roles,
+        channel_lineage: &channel_lineage,
+        facts: MessageFacts {
+            content: &new_message.content,
+            repeated,
+            account_created: to_chrono(user.created_at()).unwrap_or_default(),
+            joined_at: joined_at.and_then(to_chrono),
+        },
     };
+    earn(&chatter, &currencies, Utc::now()).await
+}
 
-    let currencies = Currency::try_from_guild(guild_id.into()).await?;
-    // giant for loop moment
+/// Gets the roles of the author of a message and when they joined. Messages from the gateway
+/// carry these already, otherwise they come from the cache, and only then from Discord.
+async fn author_member(
+    ctx: &Context,
+    message: &Message,
+    guild_id: GuildId
+) -> Option<(Vec<RoleId>, Option<Timestamp>)> {
+    if let Some(member) = &message.member {
+        return Some((member.roles.clone(), member.joined_at));
+    }
+    let cached = ctx.cache.guild(guild_id).and_then(|guild| {
+        guild.members
+            .get(&message.author.id)
+            .map(|member| (member.roles.clone(), member.joined_at))
+    });
+    if cached.is_some() {
+        return cached;
+    }
+    match guild_id.member(&ctx.http, message.author.id).await {
+        Ok(member) => Some((member.roles, member.joined_at)),
+        Err(e) => {
+            warn!("Failed to get member: {}", e);
+            None
+        }
+    }
+}
+
+fn to_chrono(timestamp: Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp.unix_timestamp(), 0)
+}
+
+/// Lets a message earn every currency of its guild that it can, at `now`. Earnings are queued in
+/// `pending_earnings` rather than added to balances right away.
+///
+/// # Errors
+/// - Any `MongoDB` error occurs while starting a cooldown.
+/// - An earning multiplied by the multipliers of the member does not fit in an amount.
+pub async fn earn(
+    chatter: &Chatter<'_>,
+    currencies: &[ArcTokioRwLockOption<Currency>],
+    now: DateTime<Utc>
+) -> Result<()> {
+    let guild_id = chatter.guild_id;
+    let user = chatter.user_id;
     for curr in currencies {
         let currency = curr.read().await;
         let Some(currency_) = currency.as_ref() else {
@@ -54,101 +122,89 @@ pub async fn message(ctx: Context, new_message: Message) -> Result<()> {
         if !currency_.earn_by_chat() {
             continue;
         }
-        let timeout_duration = currency_.earn_timeout();
-        let currency_name = currency_.curr_name().to_owned();
-        let earn_min = currency_.earn_min();
-        let earn_max = currency_.earn_max();
-
-        let timeout = Timeout {
-            user,
-            guild: guild_id,
-            currency: currency_name.clone().into_string(),
-        };
-
-        let mut timeouts = TIMEOUTS.lock().await;
-
-        if timeouts.contains(&timeout) {
+        // Members on cooldown are most messages, so they are turned away before anything else.
+        if
+            EarnCooldown::is_known_active(
+                guild_id.into(),
+                user.into(),
+                currency_.curr_name().as_str(),
+                now
+            )
+        {
             continue;
         }
-
-        let member = match new_message.member(&ctx.http).await {
-            Ok(m) => m,
-            Err(e) => {
-                warn!("Failed to get member: {}", e);
-                continue;
-            }
-        };
-
-        let channel = match new_message.channel(&ctx.http).await {
-            Ok(c) => c,
-            Err(e) => {
-                warn!("Failed to get channel: {}", e);
-                continue;
+        if !check_can_earn(guild_id, chatter.roles, chatter.channel_lineage, currency_) {
+            continue;
+        }
+        let currency_name = currency_.curr_name().as_str().to_owned();
+        if let Some(rule) = anti_farming::check(currency_, &chatter.facts, now) {
+            drop(currency);
+            debug!("Message blocked from earning currency: {}", rule.as_str());
+            if let Err(e) = BlockedEarns::increment(guild_id.into(), &currency_name, rule).await {
+                warn!("Failed to count blocked earn: {}", e);
             }
-        };
-
-        if !check_can_earn(guild_id, &member, channel.clone(), currency_) {
             continue;
         }
-
+        let multiplier = currency_.earn_multiplier(chatter.roles, chatter.channel_lineage);
+        let timeout_duration = currency_.earn_timeout();
+        let earn_min = currency_.earn_min();
+        let earn_max = currency_.earn_max();
+        let precision = currency_.precision();
         drop(currency);
 
-        let balance = balances_.ensure_has_currency(
-            Cow::from(currency_name.as_ref().as_str())
-        ).await?;
-        // get a number between earn_min and earn_max
-        let amount = truncate_2dp(rand.gen_range(earn_min..=earn_max));
-        balance.add_amount(amount, None).await?;
-
-        timeouts.insert(timeout.clone());
-        drop(timeouts);
+        if
+            !EarnCooldown::try_start(
+                guild_id.into(),
+                user.into(),
+                &currency_name,
+                timeout_duration,
+                now
+            ).await?
+        {
+            continue; // Another message got there first.
+        }
 
-        tokio::spawn(async move {
-            let Ok(std_duration) = timeout_duration.to_std() else {
-                error!("Failed to convert chrono duration {:?} to std duration", timeout_duration);
-                return;
-            };
-            debug!("Sleeping for {:?}", std_duration);
-            tokio::time::sleep(std_duration).await;
-            debug!("Done sleeping for {:?}", std_duration);
-            let mut timeouts = TIMEOUTS.lock().await;
-            timeouts.remove(&timeout);
-            drop(timeouts);
-        });
+        // get a number between earn_min and earn_max
+        let amount = Money::from_minor(
+            rand::rngs::OsRng.gen_range(earn_min.as_minor()..=earn_max.as_minor())
+        ).checked_mul_rate(multiplier, precision)?;
+        pending_earnings::add(guild_id.into(), &currency_name, user.into(), amount);
     }
-    drop(balances); // please the linter
     Ok(())
 }
 
+/// Checks the role and channel restrictions of a currency against a member's roles and the
+/// lineage of the channel they are in (see `util::channel::lineage`), so that a thread is covered
+/// by its channel and a channel by its category.
 #[allow(clippy::useless_let_if_seq)]
-fn check_can_earn(
+pub(super) fn check_can_earn(
     guild_id: GuildId,
-    member: &Member,
-    channel: Channel,
+    member_roles: &[RoleId],
+    channel_lineage: &[ChannelId],
     currency: &Currency
 ) -> bool {
     let mut can_earn = true;
     if currency.roles_is_whitelist() {
         let roles = currency.roles_whitelist();
-        if check_contains_role(guild_id, &member.roles, roles) {
+        if check_contains_role(guild_id, member_roles, roles) {
             return true;
         }
         can_earn = false;
     } else {
         let roles = currency.roles_blacklist();
-        if check_contains_role(guild_id, &member.roles, roles) {
+        if check_contains_role(guild_id, member_roles, roles) {
             return false;
         }
     }
     if currency.channels_is_whitelist() {
         let channels = currency.channels_whitelist();
-        if check_contains_channel(guild_id, channel, channels) {
+        if check_contains_channel(guild_id, channel_lineage, channels) {
             return true;
         }
         can_earn = false;
     } else {
         let channels = currency.channels_blacklist();
-        if check_contains_channel(guild_id, channel, channels) {
+        if check_contains_channel(guild_id, channel_lineage, channels) {
             return false;
         }
     }
@@ -157,12 +213,11 @@ fn check_can_earn(
 
 fn check_contains_channel(
     _guild_id: GuildId,
-    current_channel: impl Into<ChannelId>,
+    channel_lineage: &[ChannelId],
     channels: &[DbChannelId]
 ) -> bool {
-    let id = current_channel.into();
-    for db_channel in channels {
-        if DbChannelId::from(id) == *db_channel {
+    for id in channel_lineage.iter().copied() {
+        if channels.contains(&id.into()) {
             return true;
         }
     }
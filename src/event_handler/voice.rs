@@ -0,0 +1,248 @@
+//! Earning currencies by spending time in voice channels.
+//!
+//! Whenever the voice state of someone changes, everyone in the channels they left and joined is
+//! looked at again, since someone joining or leaving can make another member stop or start being
+//! alone. For every currency that can be earned by voice, a member that is eligible has a session
+//! that started when they became eligible. Once they stop being eligible they get paid the voice
+//! rate of the currency for every whole minute of the session.
+//!
+//! Sessions are kept in memory, so time spent in voice channels while the bot is down is not paid.
+
+use std::{ borrow::Cow, collections::HashMap, sync::Mutex };
+
+use anyhow::{ anyhow, Result };
+use chrono::{ DateTime, Utc };
+use lazy_static::lazy_static;
+use serenity::{
+    all::{ ChannelId, GuildId, RoleId, UserId, VoiceState },
+    client::Context,
+};
+use tracing::{ debug, warn };
+
+use crate::{
+    db::models::{ Balances, Currency, TransactionKind, TransactionReason },
+    util::{ channel::lineage_in, money::Money },
+};
+
+use super::message::check_can_earn;
+
+/// When each member became eligible to earn each currency, keyed by currency name.
+type Sessions = HashMap<(GuildId, UserId), HashMap<String, DateTime<Utc>>>;
+
+lazy_static! {
+    static ref SESSIONS: Mutex<Sessions> = Mutex::new(HashMap::new());
+}
+
+/// What is needed from the cache about a member to tell whether they can earn by voice.
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+struct VoiceMember {
+    user_id: UserId,
+    channel_id: Option<ChannelId>,
+    /// The channel followed by its category, for the channel restrictions of currencies.
+    channel_lineage: Vec<ChannelId>,
+    roles: Vec<RoleId>,
+    muted: bool,
+    deafened: bool,
+    alone: bool,
+    afk: bool,
+}
+
+impl VoiceMember {
+    fn can_earn(&self, guild_id: GuildId, currency: &Currency) -> bool {
+        if self.channel_id.is_none() {
+            return false;
+        }
+        if !currency.earn_by_voice() || currency.voice_rate().is_zero() {
+            return false;
+        }
+        if
+            (currency.voice_exclude_muted() && self.muted) ||
+            (currency.voice_exclude_deafened() && self.deafened) ||
+            (currency.voice_exclude_alone() && self.alone) ||
+            (currency.voice_exclude_afk() && self.afk)
+        {
+            return false;
+        }
+        check_can_earn(guild_id, &self.roles, &self.channel_lineage, currency)
+    }
+}
+
+/// A session that is over and has to be paid.
+struct Payout {
+    user_id: UserId,
+    curr_name: String,
+    amount: Money,
+}
+
+pub async fn voice_state_update(
+    ctx: Context,
+    old: Option<VoiceState>,
+    new: VoiceState
+) -> Result<()> {
+    debug!("Got voice state update: {:?}", new);
+    let Some(guild_id) = new.guild_id else {
+        return Ok(());
+    };
+    let old_channel = old.and_then(|old| old.channel_id);
+    let Some(members) = affected_members(&ctx, guild_id, old_channel, &new) else {
+        return Ok(());
+    };
+
+    let currencies = Currency::try_from_guild(guild_id.into()).await?;
+    let mut eligible: HashMap<String, Vec<bool>> = HashMap::new();
+    for curr in currencies.iter() {
+        let currency = curr.read().await;
+        let Some(currency_) = currency.as_ref() else {
+            continue;
+        };
+        let can_earn = members
+            .iter()
+            .map(|member| member.can_earn(guild_id, currency_))
+            .collect();
+        eligible.insert(currency_.curr_name().to_owned().into_string(), can_earn);
+        drop(currency);
+    }
+
+    let payouts = settle(guild_id, &members, &eligible, Utc::now()).await?;
+    for payout in payouts {
+        if let Err(e) = pay(guild_id, &payout).await {
+            warn!(
+                "Failed to pay {} {} for time in voice to {}: {}",
+                payout.amount,
+                payout.curr_name,
+                payout.user_id,
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reads the member whose voice state changed and everyone in the channels they left and joined
+/// from the cache. Returns `None` if the guild is not cached or the member is a bot.
+fn affected_members(
+    ctx: &Context,
+    guild_id: GuildId,
+    old_channel: Option<ChannelId>,
+    new: &VoiceState
+) -> Option<Vec<VoiceMember>> {
+    let guild = ctx.cache.guild(guild_id)?;
+    let is_bot = |user_id: &UserId| guild.members.get(user_id).is_some_and(|m| m.user.bot);
+    if is_bot(&new.user_id) || new.member.as_ref().is_some_and(|m| m.user.bot) {
+        return None;
+    }
+    let afk_channel = guild.afk_metadata.as_ref().map(|afk| afk.afk_channel_id);
+    let channels = [old_channel, new.channel_id];
+
+    let mut humans: HashMap<ChannelId, usize> = HashMap::new();
+    for state in guild.voice_states.values() {
+        if let Some(channel_id) = state.channel_id {
+            if !is_bot(&state.user_id) {
+                *humans.entry(channel_id).or_default() += 1;
+            }
+        }
+    }
+
+    let mut members: Vec<VoiceMember> = guild.voice_states
+        .values()
+        .filter(|state| channels.contains(&state.channel_id) && !is_bot(&state.user_id))
+        .map(|state| VoiceMember {
+            user_id: state.user_id,
+            channel_id: state.channel_id,
+            channel_lineage: state.channel_id.map_or_else(Vec::new, |c| lineage_in(&guild, c)),
+            roles: guild.members
+                .get(&state.user_id)
+                .map(|m| m.roles.clone())
+                .unwrap_or_default(),
+            muted: state.mute || state.self_mute,
+            deafened: state.deaf || state.self_deaf,
+            alone: state.channel_id.is_none_or(|c| humans.get(&c).copied().unwrap_or(0) <= 1),
+            afk: state.channel_id.is_some() && state.channel_id == afk_channel,
+        })
+        .collect();
+    // The member that left a voice channel is not in the voice states anymore.
+    if !members.iter().any(|m| m.user_id == new.user_id) {
+        members.push(VoiceMember {
+            user_id: new.user_id,
+            channel_id: None,
+            channel_lineage: Vec::new(),
+            roles: Vec::new(),
+            muted: false,
+            deafened: false,
+            alone: true,
+            afk: false,
+        });
+    }
+    drop(guild);
+    Some(members)
+}
+
+/// Starts sessions for members that became eligible and ends the ones of members that are not
+/// anymore, returning what the ended sessions earned. Sessions for currencies that do not exist
+/// anymore are dropped without paying.
+async fn settle(
+    guild_id: GuildId,
+    members: &[VoiceMember],
+    eligible: &HashMap<String, Vec<bool>>,
+    now: DateTime<Utc>
+) -> Result<Vec<Payout>> {
+    let mut ended = Vec::new();
+    {
+        let mut sessions = SESSIONS.lock().map_err(|_| anyhow!("Voice sessions are poisoned."))?;
+        for (i, member) in members.iter().enumerate() {
+            let key = (guild_id, member.user_id);
+            let user_sessions = sessions.entry(key).or_default();
+            user_sessions.retain(|curr_name, _| eligible.contains_key(curr_name));
+            for (curr_name, can_earn) in eligible {
+                if can_earn[i] {
+                    user_sessions.entry(curr_name.clone()).or_insert(now);
+                } else if let Some(since) = user_sessions.remove(curr_name) {
+                    ended.push((member.user_id, curr_name.clone(), since));
+                }
+            }
+            if user_sessions.is_empty() {
+                sessions.remove(&key);
+            }
+        }
+    }
+
+    let mut payouts = Vec::new();
+    for (user_id, curr_name, since) in ended {
+        let minutes = (now - since).num_minutes();
+        if minutes <= 0 {
+            continue;
+        }
+        let Some(currency) = Currency::try_from_name(guild_id.into(), curr_name.clone()).await? else {
+            continue;
+        };
+        let currency = currency.read().await;
+        let Some(currency_) = currency.as_ref() else {
+            continue;
+        };
+        let amount = currency_
+            .voice_rate()
+            .checked_mul(minutes)
+            .ok_or_else(|| anyhow!("Too much currency earned in voice."))?
+            .truncate(currency_.precision());
+        drop(currency);
+        payouts.push(Payout { user_id, curr_name, amount });
+    }
+    Ok(payouts)
+}
+
+async fn pay(guild_id: GuildId, payout: &Payout) -> Result<()> {
+    let balances = Balances::try_from_user(guild_id.into(), payout.user_id.into()).await?;
+    let mut balances = balances.lock().await;
+    let balances_ = balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Balances are being used in a breaking operation."))?;
+    let balance = balances_.ensure_has_currency(Cow::from(payout.curr_name.as_str())).await?;
+    balance.add_amount(
+        payout.amount,
+        TransactionReason::new(TransactionKind::VoiceEarn, payout.user_id.into()),
+        None
+    ).await?;
+    drop(balances);
+    Ok(())
+}
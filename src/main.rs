@@ -1,29 +1,12 @@
 #![warn(clippy::pedantic)]
-#![allow(clippy::must_use_candidate)] // and keep this off
 #![warn(clippy::nursery)]
-#![allow(clippy::module_name_repetitions)] // cant be asked
-#![allow(clippy::missing_errors_doc)] // cant be asked
-#![allow(clippy::missing_panics_doc)] // cant be asked
-#![deny(elided_lifetimes_in_paths)]
-#![cfg_attr(feature = "is-nightly", feature(const_trait_impl))]
 
-pub mod commands;
-pub mod db;
-pub mod event_handler;
-pub mod mechanics;
-pub mod util;
-
-#[macro_use]
-pub mod macros;
-
-use dotenv::dotenv;
+use conebot_rust::{ event_handler, init_env };
 use serenity::{ model::gateway::GatewayIntents, Client };
 use std::env;
 use tracing::{ span, warn };
 use tracing_subscriber::{ fmt, fmt::format, EnvFilter };
 
-const ACCENT_COLOUR: u32 = 0x0003_75b4;
-
 #[tokio::main]
 async fn main() {
     let filter = EnvFilter::from_default_env();
@@ -49,8 +32,3 @@ async fn main() {
         eprintln!("Client error: {why:#?}");
     }
 }
-
-async fn init_env() {
-    dotenv().ok();
-    db::init().await;
-}
@@ -0,0 +1,166 @@
+//! Doing one of the activities staff set up, like `/work` or `/crime`.
+//!
+//! The cooldown is started first, so a member spamming the command only gets through once. Then
+//! the activity either fails, taking a random amount of the penalty currency, or rolls its drop
+//! table and hands out the drops with `give_drops`.
+
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, Result };
+use chrono::{ DateTime, Utc };
+use rand::Rng;
+use serenity::{ all::{ GuildId, RoleId, UserId }, client::Context };
+use tracing::error;
+
+use crate::{
+    db::models::{
+        activity::{ Activity, ActivityCooldown },
+        Balances,
+        Currency,
+        DropTable,
+        Inventory,
+        TransactionKind,
+        TransactionReason,
+    },
+    mechanics::{ drop_generator::DropGenerator, item_action_handler::give_drops },
+    util::money::Money,
+};
+
+/// What happened when a member did an activity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivityOutcome {
+    /// The drop table was rolled, and the member got these currencies and items with how many of
+    /// each.
+    Done(Vec<(String, i64)>),
+    /// The activity failed and the member lost this much of the currency.
+    Failed(Option<(String, Money)>),
+    /// The member is on cooldown until the given time.
+    OnCooldown(DateTime<Utc>),
+}
+
+/// Does an activity for a member who has the given roles.
+///
+/// # Warning
+/// This function will attempt to lock the member's inventory and balances. It ***WILL*** cause a
+/// deadlock if either is already locked before this function is called.
+///
+/// # Errors
+/// - The member does not have the role the activity needs.
+/// - The drop table of the activity does not exist anymore.
+/// - The member's inventory or balances are being used in a breaking operation.
+/// - Any `MongoDB` error occurs.
+pub async fn do_activity(
+    guild_id: GuildId,
+    user_id: UserId,
+    roles: &[RoleId],
+    activity: &Activity,
+    http: &Context
+) -> Result<ActivityOutcome> {
+    if let Some(role) = activity.required_role() {
+        if !roles.contains(&role.into()) {
+            return Err(anyhow!("You need <@&{}> to {}.", role.as_u64(), activity.name()));
+        }
+    }
+
+    let now = Utc::now();
+    let started = ActivityCooldown::try_start(
+        guild_id.into(),
+        user_id.into(),
+        activity.name(),
+        activity.cooldown(),
+        now
+    ).await?;
+    if let Err(available) = started {
+        return Ok(ActivityOutcome::OnCooldown(available));
+    }
+
+    let failed = rand::rngs::OsRng.gen_bool(activity.fail_chance());
+    let res = if failed {
+        fail(guild_id, user_id, activity).await.map(ActivityOutcome::Failed)
+    } else {
+        succeed(guild_id, user_id, activity, http).await.map(ActivityOutcome::Done)
+    };
+    if res.is_err() {
+        // Nothing was handed out, so the member should be able to try again right away.
+        if
+            let Err(e) = ActivityCooldown::undo(
+                guild_id.into(),
+                user_id.into(),
+                activity.name(),
+                now
+            ).await
+        {
+            error!("Could not undo the cooldown of {}: {}", activity.name(), e);
+        }
+    }
+    res
+}
+
+async fn succeed(
+    guild_id: GuildId,
+    user_id: UserId,
+    activity: &Activity,
+    http: &Context
+) -> Result<Vec<(String, i64)>> {
+    let drop_table = DropTable::try_from_name(
+        guild_id.into(),
+        Cow::from(activity.drop_table_name()),
+        None
+    ).await?;
+    let drop_table = drop_table.read().await;
+    let drop_table_ = drop_table
+        .as_ref()
+        .ok_or_else(|| anyhow!("Drop table is being used in a breaking operation."))?;
+    let dropper = DropGenerator::from(drop_table_);
+    drop(drop_table);
+    let drops = dropper.generate(activity.count())?;
+
+    let inventory = Inventory::try_from_user(guild_id.into(), user_id.into()).await?;
+    let mut inventory = inventory.lock().await;
+    let inventory_ = inventory
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your inventory is being used in a breaking operation."))?;
+    let reason = TransactionReason::new(TransactionKind::Activity, user_id.into());
+    give_drops(guild_id, user_id, inventory_, drops.clone(), reason, 0, http).await?;
+    drop(inventory);
+
+    Ok(
+        drops
+            .into_iter()
+            .map(|d| (d.name().to_owned(), d.quantity))
+            .collect()
+    )
+}
+
+/// Takes a random amount of the penalty, or all the member has of the currency if that is less.
+async fn fail(
+    guild_id: GuildId,
+    user_id: UserId,
+    activity: &Activity
+) -> Result<Option<(String, Money)>> {
+    let Some(penalty) = activity.penalty() else {
+        return Ok(None);
+    };
+    let precision = Currency::precision_from_name(
+        guild_id.into(),
+        penalty.curr_name.clone()
+    ).await?;
+    let amount = Money::from_minor(
+        rand::rngs::OsRng.gen_range(penalty.min.as_minor()..=penalty.max.as_minor())
+    ).truncate(precision);
+
+    let balances = Balances::try_from_user(guild_id.into(), user_id.into()).await?;
+    let mut balances = balances.lock().await;
+    let balances_ = balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?;
+    let balance = balances_.ensure_has_currency(Cow::from(penalty.curr_name.as_str())).await?;
+    let amount = amount.min(balance.amount().max(Money::ZERO));
+    if !amount.is_zero() {
+        let reason = TransactionReason::new(TransactionKind::Activity, user_id.into());
+        balance.sub_amount(amount, reason, None).await?;
+    }
+    drop(balances);
+
+    Ok(Some((penalty.curr_name.clone(), amount)))
+}
@@ -0,0 +1,208 @@
+//! Rules that keep low effort messages from earning currency by chatting, like one character
+//! spam, the same message over and over, or members on fresh accounts made to farm.
+//!
+//! What rules are on is set per currency. Every time a rule stops a message from earning, it is
+//! counted in `BlockedEarns`.
+
+use std::{ num::NonZeroUsize, sync::Mutex };
+
+use chrono::{ DateTime, Utc };
+use lazy_static::lazy_static;
+use lru::LruCache;
+use serenity::all::{ GuildId, UserId };
+
+use crate::db::models::{ blocked_earns::FarmingRule, Currency };
+
+/// How similar two messages have to be, from 0 to 1, to count as the same message.
+const REPEAT_SIMILARITY: f64 = 0.9;
+
+/// Messages are only compared up to this many characters, since comparing is quadratic.
+const MAX_COMPARED_CHARS: usize = 256;
+
+lazy_static! {
+    /// The normalized previous message of each member.
+    static ref LAST_MESSAGES: Mutex<LruCache<(GuildId, UserId), String>> =
+        Mutex::new(LruCache::new(NonZeroUsize::new(10_000).unwrap()));
+}
+
+/// What the rules need to know about a message and who sent it.
+#[derive(Debug, Clone, Copy)]
+pub struct MessageFacts<'a> {
+    pub content: &'a str,
+    /// Whether the message is the same, or nearly the same, as the previous one of the member.
+    pub repeated: bool,
+    pub account_created: DateTime<Utc>,
+    pub joined_at: Option<DateTime<Utc>>,
+}
+
+/// Remembers a message as the previous message of a member, and returns whether it is the same,
+/// or nearly the same, as the one before it.
+///
+/// Only the previous message is kept, in memory, so this is forgotten on restart.
+pub fn remember_message(guild_id: GuildId, user_id: UserId, content: &str) -> bool {
+    let normalized = normalize(content);
+    let Ok(mut last_messages) = LAST_MESSAGES.lock() else {
+        return false;
+    };
+    let previous = last_messages.put((guild_id, user_id), normalized.clone());
+    drop(last_messages);
+    // Messages with no letters or numbers, like ones that are only emojis, all normalize to
+    // nothing, so there is nothing to compare them by.
+    !normalized.is_empty() &&
+        previous.is_some_and(|previous| is_near_duplicate(&previous, &normalized))
+}
+
+/// Finds the first rule of a currency that the message breaks, if any.
+#[allow(clippy::must_use_candidate)]
+pub fn check(
+    currency: &Currency,
+    message: &MessageFacts<'_>,
+    now: DateTime<Utc>
+) -> Option<FarmingRule> {
+    let length = message.content.trim().chars().count();
+    if length < (currency.min_message_length() as usize) {
+        return Some(FarmingRule::MinLength);
+    }
+    if currency.ignore_emoji_and_link_only() && is_emoji_or_link_only(message.content) {
+        return Some(FarmingRule::EmojiOrLinkOnly);
+    }
+    if now - message.account_created < currency.min_account_age() {
+        return Some(FarmingRule::AccountAge);
+    }
+    let min_member_age = currency.min_member_age();
+    // Members that are not known to have joined only count as too new if there is a minimum.
+    if !min_member_age.is_zero() && message.joined_at.is_none_or(|at| now - at < min_member_age) {
+        return Some(FarmingRule::MemberAge);
+    }
+    if currency.ignore_repeated_messages() && message.repeated {
+        return Some(FarmingRule::Repeated);
+    }
+    None
+}
+
+/// Whether a message is nothing but custom emojis, links, and characters that are not letters or
+/// numbers, such as unicode emojis, punctuation and whitespace.
+#[allow(clippy::must_use_candidate)]
+pub fn is_emoji_or_link_only(content: &str) -> bool {
+    !content
+        .split_whitespace()
+        .filter(|word| !is_link(word))
+        .any(|word| strip_custom_emojis(word).chars().any(char::is_alphanumeric))
+}
+
+fn is_link(word: &str) -> bool {
+    let word = word.trim_start_matches('<').trim_end_matches('>');
+    word.starts_with("http://") || word.starts_with("https://")
+}
+
+/// Removes every custom emoji, which look like `<:name:id>` or `<a:name:id>`, from a word.
+fn strip_custom_emojis(word: &str) -> String {
+    let mut stripped = String::with_capacity(word.len());
+    let mut rest = word;
+    while let Some(start) = rest.find('<') {
+        stripped.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        match candidate.find('>') {
+            Some(end) if is_custom_emoji(&candidate[..=end]) => {
+                rest = &candidate[end + 1..];
+            }
+            _ => {
+                stripped.push('<');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+fn is_custom_emoji(s: &str) -> bool {
+    let Some(inner) = s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) else {
+        return false;
+    };
+    let inner = inner.strip_prefix('a').unwrap_or(inner);
+    let mut parts = inner.split(':');
+    matches!(
+        (parts.next(), parts.next(), parts.next(), parts.next()),
+        (Some(""), Some(name), Some(id), None)
+            if !name.is_empty() && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+    )
+}
+
+/// Lowercases a message and keeps only its letters and numbers, so that messages that only
+/// differ in case, spacing or punctuation are the same.
+fn normalize(content: &str) -> String {
+    content
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .take(MAX_COMPARED_CHARS)
+        .collect()
+}
+
+/// Whether two normalized messages are similar enough to count as the same message.
+#[allow(clippy::cast_precision_loss)]
+fn is_near_duplicate(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let longest = a.len().max(b.len());
+    let similarity = 1.0 - (edit_distance(&a, &b) as f64) / (longest as f64);
+    similarity >= REPEAT_SIMILARITY
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_emoji_or_link_only() {
+        assert!(is_emoji_or_link_only("😂😂😂"));
+        assert!(is_emoji_or_link_only("<:pepe:123456> <a:dance:789>"));
+        assert!(is_emoji_or_link_only("https://example.com/a?b=c <https://example.com>"));
+        assert!(is_emoji_or_link_only("!!! ..."));
+        assert!(!is_emoji_or_link_only("look at this https://example.com"));
+        assert!(!is_emoji_or_link_only("<:pepe:123456>hi"));
+        assert!(!is_emoji_or_link_only("a < b > c"));
+    }
+
+    #[test]
+    fn test_is_near_duplicate() {
+        let same = |a, b| is_near_duplicate(&normalize(a), &normalize(b));
+        assert!(same("Hello there!", "hello there"));
+        assert!(
+            same("this is a long message about farming", "this is a long message about farmin")
+        );
+        assert!(!same("hello", "goodbye"));
+        assert!(!same("good morning", "good evening"));
+    }
+
+    #[test]
+    fn test_remember_message() {
+        let guild_id = GuildId::new(1);
+        let user_id = UserId::new(2);
+        assert!(!remember_message(guild_id, user_id, "first message"));
+        assert!(remember_message(guild_id, user_id, "First message!"));
+        assert!(!remember_message(guild_id, user_id, "something else entirely"));
+        assert!(!remember_message(guild_id, UserId::new(3), "something else entirely"));
+        assert!(!remember_message(guild_id, user_id, "😂😂😂"));
+        assert!(!remember_message(guild_id, user_id, "!!! 🎉"));
+    }
+}
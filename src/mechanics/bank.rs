@@ -0,0 +1,94 @@
+//! Moving currency between a member's wallet and their bank.
+//!
+//! Everything that spends currency, like buying, exchanging or staff taking it, only touches the
+//! wallet, so the bank is where members set aside what they want to keep. If the currency has
+//! interest, the bank is also the only part that earns it.
+
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, bail, Result };
+use serenity::model::prelude::Member;
+
+use crate::{
+    db::models::{ Balances, Currency, TransactionKind, TransactionReason },
+    util::money::Money,
+};
+
+/// Which way the currency goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankAction {
+    /// From the wallet into the bank.
+    Deposit,
+    /// From the bank back into the wallet.
+    Withdraw,
+}
+
+impl BankAction {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Deposit => "deposit",
+            Self::Withdraw => "withdraw",
+        }
+    }
+}
+
+/// How much was moved, and what the member has in each part afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Moved {
+    pub amount: Money,
+    pub wallet: Money,
+    pub bank: Money,
+}
+
+/// Moves an amount of a currency between a member's wallet and their bank.
+///
+/// Members can always withdraw, so that turning the bank off for a currency does not lock away
+/// what is in it, but can only deposit while the currency has a bank.
+///
+/// # Warning
+/// This function will attempt to lock the member's balances. It ***WILL*** cause a deadlock if
+/// they are already locked before this function is called.
+///
+/// # Errors
+/// - The currency has no bank and the member is depositing.
+/// - The amount is not a positive finite number after truncating it to the currency's precision.
+/// - There is not enough in the part the amount comes from.
+/// - The bank would end up holding more than the currency allows.
+/// - Any `MongoDB` error occurs.
+pub async fn move_money(
+    currency: &Currency,
+    action: BankAction,
+    amount: f64,
+    member: &Member
+) -> Result<Moved> {
+    let curr_name = currency.curr_name();
+    let curr_name = curr_name.as_str();
+    if action == BankAction::Deposit && !currency.bank() {
+        bail!("{curr_name} cannot be kept in the bank.");
+    }
+    let amount = currency.amount_from_f64(amount)?;
+    if amount <= Money::ZERO {
+        bail!("You must {} more than 0.", action.as_str());
+    }
+
+    let user_id = member.user.id.into();
+    let balances = Balances::try_from_user(member.guild_id.into(), user_id).await?;
+    let mut balances = balances.lock().await;
+    let balances_ = balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?;
+    let balance = balances_.ensure_has_currency(Cow::from(curr_name)).await?;
+
+    let reason = TransactionReason::new(TransactionKind::Bank, user_id);
+    match action {
+        BankAction::Deposit => {
+            balance.deposit(amount, currency.bank_capacity(), reason, None).await?;
+        }
+        BankAction::Withdraw => balance.withdraw(amount, reason, None).await?,
+    }
+    let moved = Moved { amount, wallet: balance.amount(), bank: balance.bank() };
+    drop(balances);
+
+    Ok(moved)
+}
@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, Result };
+use chrono::{ DateTime, Utc };
+use mongodb::ClientSession;
+use rand::Rng;
+use serenity::{ all::{ GuildId, UserId }, client::Context };
+
+use crate::{
+    db::{
+        models::{
+            claim::{ ClaimConfig, ClaimKind, ClaimPayout, ClaimStreak },
+            Balances,
+            Currency,
+            DropTable,
+            Inventory,
+            Item,
+            TransactionKind,
+            TransactionReason,
+        },
+        CLIENT,
+    },
+    mechanics::drop_generator::{ DropGenerator, DropResultKind },
+    util::money::Money,
+};
+
+/// What happened when a member tried to claim.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClaimOutcome {
+    Claimed {
+        streak: i64,
+        multiplier: f64,
+        rewards: Vec<Reward>,
+    },
+    /// The member is on cooldown until the given time.
+    OnCooldown(DateTime<Utc>),
+}
+
+/// One thing a member got from a claim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reward {
+    Currency(String, Money),
+    Item(String, i64),
+}
+
+/// The streak and rewards of a claim that went through, or when the cooldown is over.
+type Attempt = Result<(i64, Vec<Reward>), DateTime<Utc>>;
+
+/// Claims the reward of a kind of claim for a member, giving them the payout multiplied by their
+/// streak. Recording the claim and giving the payout happen in one transaction.
+///
+/// # Warning
+/// This function will attempt to lock the member's inventory and balances. It ***WILL*** cause
+/// a deadlock if either is already locked before this function is called.
+///
+/// # Errors
+/// - There is no reward set up for the kind of claim.
+/// - Whatever is paid out does not exist anymore.
+/// - The member's inventory or balances are being used in a breaking operation.
+/// - Any `MongoDB` error occurs.
+pub async fn claim(
+    guild_id: GuildId,
+    user_id: UserId,
+    kind: ClaimKind,
+    http: &Context
+) -> Result<ClaimOutcome> {
+    let config = ClaimConfig::try_from_kind(guild_id.into(), kind).await?.ok_or_else(||
+        anyhow!("There is no {} reward in this server.", kind.as_str())
+    )?;
+    let now = Utc::now();
+
+    // Check the cooldown before locking anything, most attempts will be turned away here.
+    let prev = ClaimStreak::try_from_user(guild_id.into(), user_id.into(), kind, None).await?;
+    if let Err(available) = ClaimStreak::next_streak(prev.as_ref(), kind, config.grace(), now) {
+        return Ok(ClaimOutcome::OnCooldown(available));
+    }
+
+    let inventory = Inventory::try_from_user(guild_id.into(), user_id.into()).await?;
+    let mut inventory = inventory.lock().await;
+    let inventory_ = inventory
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your inventory is being used in a breaking operation."))?;
+    let balances = Balances::try_from_user(guild_id.into(), user_id.into()).await?;
+
+    let mut session = CLIENT.get().await.start_session(None).await?;
+    session.start_transaction(None).await?;
+
+    // The streak is read again inside the transaction so that it can not change under us.
+    let res: Result<Attempt> = async {
+        let prev = ClaimStreak::try_from_user(
+            guild_id.into(),
+            user_id.into(),
+            kind,
+            Some(&mut session)
+        ).await?;
+        let streak = match ClaimStreak::next_streak(prev.as_ref(), kind, config.grace(), now) {
+            Ok(streak) => streak,
+            Err(available) => {
+                return Ok(Err(available));
+            }
+        };
+        ClaimStreak::record(
+            guild_id.into(),
+            user_id.into(),
+            kind,
+            prev.as_ref(),
+            streak,
+            now,
+            Some(&mut session)
+        ).await?;
+        let rewards = roll_rewards(guild_id, config.payout(), config.multiplier(streak)).await?;
+
+        let reason = TransactionReason::new(TransactionKind::Claim, user_id.into());
+        // Items go first because instant items may lock the balances themselves.
+        for reward in &rewards {
+            if let Reward::Item(item_name, amount) = reward {
+                let item = Item::try_from_name(guild_id.into(), item_name.clone()).await?;
+                inventory_.give_item(item, *amount, reason, Some(&mut session), 0, http).await?;
+            }
+        }
+        give_currencies(&balances, &rewards, reason, &mut session).await?;
+        Ok(Ok((streak, rewards)))
+    }.await;
+
+    let (streak, rewards) = match res {
+        Ok(Ok(claimed)) => claimed,
+        Ok(Err(available)) => {
+            session.abort_transaction().await?;
+            return Ok(ClaimOutcome::OnCooldown(available));
+        }
+        Err(e) => {
+            Inventory::invalidate_cache(inventory).await.ok();
+            Balances::invalidate_cache(balances.lock().await).await.ok();
+            session.abort_transaction().await?;
+            return Err(e);
+        }
+    };
+    if let Err(e) = session.commit_transaction().await {
+        Inventory::invalidate_cache(inventory).await.ok();
+        Balances::invalidate_cache(balances.lock().await).await.ok();
+        return Err(e.into());
+    }
+    drop(inventory);
+
+    Ok(ClaimOutcome::Claimed {
+        streak,
+        multiplier: config.multiplier(streak),
+        rewards,
+    })
+}
+
+/// Works out what a payout gives with the multiplier applied, rolling the drop table or the
+/// random amount if there is one.
+async fn roll_rewards(
+    guild_id: GuildId,
+    payout: &ClaimPayout,
+    multiplier: f64
+) -> Result<Vec<Reward>> {
+    match payout {
+        ClaimPayout::Currency { curr_name, min, max } => {
+            let precision = Currency::precision_from_name(
+                guild_id.into(),
+                curr_name.clone()
+            ).await?;
+            let amount = Money::from_minor(
+                rand::rngs::OsRng.gen_range(min.as_minor()..=max.as_minor())
+            ).checked_mul_rate(multiplier, precision)?;
+            Ok(vec![Reward::Currency(curr_name.clone(), amount)])
+        }
+        ClaimPayout::Item { item_name, amount } => {
+            Ok(vec![Reward::Item(item_name.clone(), scale(*amount, multiplier))])
+        }
+        ClaimPayout::DropTable { drop_table_name, count } => {
+            let drop_table = DropTable::try_from_name(
+                guild_id.into(),
+                Cow::from(drop_table_name),
+                None
+            ).await?;
+            let drop_table = drop_table.read().await;
+            let drop_table_ = drop_table
+                .as_ref()
+                .ok_or_else(|| anyhow!("Drop table is being used in a breaking operation."))?;
+            let dropper = DropGenerator::from(drop_table_);
+            drop(drop_table);
+            dropper
+                .generate(scale(*count, multiplier))?
+                .into_iter()
+                .map(|drop| {
+                    Ok(match drop.result {
+                        DropResultKind::Currency(name) => {
+                            let amount = Money::from_whole(drop.quantity).ok_or_else(||
+                                anyhow!("Too much currency dropped.")
+                            )?;
+                            Reward::Currency(name.to_owned(), amount)
+                        }
+                        DropResultKind::Item(name) => Reward::Item(name.to_owned(), drop.quantity),
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+async fn give_currencies(
+    balances: &crate::db::ArcTokioMutexOption<Balances>,
+    rewards: &[Reward],
+    reason: TransactionReason,
+    session: &mut ClientSession
+) -> Result<()> {
+    let mut balances = balances.lock().await;
+    let balances_ = balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?;
+    for reward in rewards {
+        if let Reward::Currency(curr_name, amount) = reward {
+            let balance = balances_.ensure_has_currency(Cow::from(curr_name.as_str())).await?;
+            balance.add_amount(*amount, reason, Some(session)).await?;
+        }
+    }
+    drop(balances);
+    Ok(())
+}
+
+/// Multiplies a count by a multiplier, rounding down.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn scale(count: i64, multiplier: f64) -> i64 {
+    ((count as f64) * multiplier).floor() as i64
+}
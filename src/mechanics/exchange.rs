@@ -5,7 +5,10 @@ use serenity::model::prelude::Member;
 use tracing::error;
 
 use crate::db::{ ArcTokioRwLockOption, CLIENT };
-use crate::{ db::models::{ Balance, Balances, Currency }, util::currency::truncate_2dp };
+use crate::{
+    db::models::{ Balance, Balances, Currency, TransactionKind, TransactionReason },
+    util::money::Money,
+};
 
 /// Exchanges one currency for another.
 /// Returns the amount of the output currency that was given.
@@ -23,18 +26,21 @@ use crate::{ db::models::{ Balance, Balances, Currency }, util::currency::trunca
 /// * If the output currency is not a base currency and does not have a base value.
 /// * If the user does not have enough of the input currency.
 /// * If any of the currencies cannot be exchanged.
-/// * If the exchange amount is infinite or NaN.
-/// * If the exchange would lead to the user an infinite or NaN amount of the output currency.
+/// * If the exchange rate is infinite or NaN.
+/// * If the exchange would overflow the user's balance of the output currency.
 /// * Any ``MongoDB`` errors.
 pub async fn exchange(
     input: &Currency,
     output: &Currency,
-    amount: f64,
+    amount: Money,
     member: &Member
-) -> Result<f64> {
+) -> Result<Money> {
     if input.curr_name() == output.curr_name() {
         bail!("You cannot exchange {} for itself.", input.curr_name().as_str());
     }
+    if amount <= Money::ZERO {
+        bail!("You must exchange more than 0.");
+    }
     let input_base_value = if let Some(f) = input.base_value() {
         f
     } else if !input.base() {
@@ -52,11 +58,13 @@ pub async fn exchange(
 
     let currencies = Currency::try_from_guild(member.guild_id.into()).await?;
 
-    get_base_currency(currencies).await?;
+    get_base_currency(&currencies).await?;
 
-    let to_give = (amount * input_base_value) / output_base_value;
+    // Only the rate is a float. It is applied to the amount once and the result truncated, so
+    // nothing gets lost along the way no matter how many exchanges happen.
+    let rate = input_base_value / output_base_value;
 
-    if to_give.is_infinite() || to_give.is_nan() {
+    if rate.is_infinite() || rate.is_nan() {
         bail!("Invalid exchange rate.");
     }
 
@@ -95,13 +103,9 @@ pub async fn exchange(
         bail!("You don't have enough {}.", input.curr_name().as_str());
     }
 
-    let to_give = truncate_2dp((input_base_value * amount) / output_base_value);
-    if to_give.is_infinite() || to_give.is_nan() {
-        bail!("Invalid exchange rate result.");
-    }
+    let to_give = amount.checked_mul_rate(rate, output.precision())?;
 
-    let amount_after = balance_out.amount() + to_give;
-    if amount_after.is_infinite() || amount_after.is_nan() {
+    if balance_out.amount().checked_add(to_give).is_none() {
         bail!("Invalid exchange rate result.");
     }
 
@@ -114,7 +118,8 @@ pub async fn exchange(
             balance_in,
             amount,
             balance_out,
-            to_give
+            to_give,
+            TransactionReason::new(TransactionKind::Exchange, member.user.id.into())
         ).await
     {
         error!("Error when exchanging: {}", e);
@@ -134,17 +139,18 @@ pub async fn exchange(
 async fn transaction_function(
     mut session: &mut mongodb::ClientSession,
     balance_in: &mut Balance,
-    amount: f64,
+    amount: Money,
     balance_out: &mut Balance,
-    to_give: f64
+    to_give: Money,
+    reason: TransactionReason
 ) -> Result<()> {
-    balance_in.sub_amount_unchecked(amount, Some(&mut session)).await?;
-    balance_out.add_amount_unchecked(to_give, Some(&mut session)).await?;
+    balance_in.sub_amount_unchecked(amount, reason, Some(&mut session)).await?;
+    balance_out.add_amount_unchecked(to_give, reason, Some(&mut session)).await?;
     Ok(())
 }
 
 async fn get_base_currency(
-    currencies: Vec<ArcTokioRwLockOption<Currency>>
+    currencies: &[ArcTokioRwLockOption<Currency>]
 ) -> Result<ArcTokioRwLockOption<Currency>> {
     let mut base_currency = None;
 
@@ -156,7 +162,7 @@ async fn get_base_currency(
         let is_base = curr_.base();
         drop(curr);
         if is_base {
-            base_currency = Some(currency);
+            base_currency = Some(currency.clone());
             break;
         }
     }
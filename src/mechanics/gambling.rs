@@ -0,0 +1,334 @@
+//! The gambling games, where members bet an amount of a currency and get back some multiple of
+//! it depending on what comes up.
+//!
+//! Every game is fair before the house edge: on average it pays back exactly what was bet. What it
+//! pays is then multiplied by `1 - house_edge`, so the edge is exactly what the house keeps in the
+//! long run no matter the game.
+
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Utc };
+use rand::Rng;
+use serenity::all::{ GuildId, UserId };
+
+use crate::{
+    db::{
+        models::{
+            gambling::{ Game, GameConfig, GameCooldown, GameRecord },
+            Balances,
+            Currency,
+            TransactionKind,
+            TransactionReason,
+        },
+        CLIENT,
+    },
+    util::money::Money,
+};
+
+/// How many sides the die has.
+pub const DICE_SIDES: u8 = 6;
+
+/// The symbols on each reel of the slot machine, with how many times each is on the reel.
+const REEL: [(&str, u32); 5] = [("🍒", 6), ("🍋", 5), ("🔔", 3), ("⭐", 2), ("💎", 1)];
+/// What three of each symbol of `REEL` pays compared to each other. Scaled by `slots_scale`.
+const THREE_OF_A_KIND: [f64; 5] = [5.0, 10.0, 40.0, 100.0, 500.0];
+/// What two of the same symbol pay compared to three of a kind. Scaled by `slots_scale`.
+const TWO_OF_A_KIND: f64 = 1.0;
+
+/// A side of a coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoinSide {
+    Heads,
+    Tails,
+}
+
+impl CoinSide {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Heads => "heads",
+            Self::Tails => "tails",
+        }
+    }
+}
+
+impl std::str::FromStr for CoinSide {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "heads" => Ok(Self::Heads),
+            "tails" => Ok(Self::Tails),
+            _ => Err(anyhow!("A coin only has heads and tails.")),
+        }
+    }
+}
+
+/// What a member bets on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bet {
+    /// The side the coin lands on.
+    Coinflip(CoinSide),
+    /// The number the die rolls, from 1 to `DICE_SIDES`.
+    Dice(u8),
+    /// Nothing to pick, the reels decide.
+    Slots,
+}
+
+impl Bet {
+    #[allow(clippy::must_use_candidate)]
+    pub const fn game(self) -> Game {
+        match self {
+            Self::Coinflip(_) => Game::Coinflip,
+            Self::Dice(_) => Game::Dice,
+            Self::Slots => Game::Slots,
+        }
+    }
+}
+
+/// What came up, and how many times the bet it pays back before the house edge.
+#[derive(Debug, Clone, PartialEq)]
+struct Roll {
+    outcome: String,
+    multiplier: f64,
+}
+
+/// A game that was played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Played {
+    pub bet: Money,
+    /// What the member got back, including the bet. Zero if they lost.
+    pub payout: Money,
+    pub outcome: String,
+}
+
+/// What happened when a member tried to play.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayOutcome {
+    Played(Played),
+    /// The member is on cooldown for the game until the given time.
+    OnCooldown(DateTime<Utc>),
+}
+
+/// Plays a game for a member, betting an amount of a currency. Taking the bet, paying out, starting
+/// the cooldown and recording the game all happen in one transaction.
+///
+/// # Warning
+/// This function will attempt to lock the member's balances. It ***WILL*** cause a deadlock if
+/// they are already locked before this function is called.
+///
+/// # Errors
+/// - The currency does not exist or can not be gambled.
+/// - The game is turned off, or the bet is not within its limits.
+/// - The member does not have enough of the currency.
+/// - Any `MongoDB` error occurs.
+pub async fn play(
+    guild_id: GuildId,
+    user_id: UserId,
+    curr_name: &str,
+    amount: f64,
+    bet: Bet
+) -> Result<PlayOutcome> {
+    let game = bet.game();
+    if let Bet::Dice(number) = bet {
+        if !(1..=DICE_SIDES).contains(&number) {
+            bail!("Pick a number from 1 to {DICE_SIDES}.");
+        }
+    }
+    let currency = Currency::try_from_name(guild_id.into(), curr_name.to_owned()).await?;
+    let currency = currency.ok_or_else(|| anyhow!("Currency not found."))?;
+    let (gamble, precision) = currency
+        .read().await
+        .as_ref()
+        .map(|c| (c.gamble(), c.precision()))
+        .ok_or_else(|| anyhow!("Currency is being used in a breaking operation."))?;
+    if !gamble {
+        bail!("{curr_name} can not be gambled.");
+    }
+    let config = GameConfig::from_game(guild_id.into(), game).await?;
+    if !config.enabled() {
+        bail!("{} is turned off in this server.", game.as_str());
+    }
+    let amount = Money::from_f64(amount, precision)?;
+    config.check_bet(amount)?;
+
+    let roll = roll(bet, &mut rand::rngs::OsRng);
+    let payout = amount.checked_mul_rate(roll.multiplier * (1.0 - config.house_edge()), precision)?;
+    let now = Utc::now();
+
+    let balances = Balances::try_from_user(guild_id.into(), user_id.into()).await?;
+    let mut balances = balances.lock().await;
+    let balances_ = balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?;
+    let balance = balances_.ensure_has_currency(Cow::from(curr_name)).await?;
+    if balance.amount() < amount {
+        bail!("You don't have enough {curr_name}.");
+    }
+
+    let mut session = CLIENT.get().await.start_session(None).await?;
+    session.start_transaction(None).await?;
+    let res: Result<Result<(), DateTime<Utc>>> = async {
+        if !config.cooldown().is_zero() {
+            let started = GameCooldown::try_start(
+                guild_id.into(),
+                user_id.into(),
+                game,
+                config.cooldown(),
+                now,
+                &mut session
+            ).await?;
+            if let Err(available) = started {
+                return Ok(Err(available));
+            }
+        }
+        let reason = TransactionReason::new(TransactionKind::Gamble, user_id.into());
+        balance.sub_amount(amount, reason, Some(&mut session)).await?;
+        if !payout.is_zero() {
+            balance.add_amount(payout, reason, Some(&mut session)).await?;
+        }
+        GameRecord::new(
+            guild_id.into(),
+            user_id.into(),
+            game,
+            curr_name.to_owned(),
+            amount,
+            payout,
+            roll.outcome.clone(),
+            config.house_edge(),
+            now
+        ).insert(&mut session).await?;
+        Ok(Ok(()))
+    }.await;
+
+    match res {
+        Ok(Ok(())) => {}
+        Ok(Err(available)) => {
+            session.abort_transaction().await?;
+            return Ok(PlayOutcome::OnCooldown(available));
+        }
+        Err(e) => {
+            Balances::invalidate_cache(balances).await.ok();
+            session.abort_transaction().await?;
+            return Err(e);
+        }
+    }
+    if let Err(e) = session.commit_transaction().await {
+        Balances::invalidate_cache(balances).await.ok();
+        return Err(e.into());
+    }
+    drop(balances);
+
+    Ok(PlayOutcome::Played(Played { bet: amount, payout, outcome: roll.outcome }))
+}
+
+fn roll(bet: Bet, rng: &mut impl Rng) -> Roll {
+    match bet {
+        Bet::Coinflip(call) => {
+            let side = if rng.gen_bool(0.5) { CoinSide::Heads } else { CoinSide::Tails };
+            Roll {
+                outcome: side.as_str().to_owned(),
+                multiplier: if side == call { 2.0 } else { 0.0 },
+            }
+        }
+        Bet::Dice(guess) => {
+            let number = rng.gen_range(1..=DICE_SIDES);
+            Roll {
+                outcome: number.to_string(),
+                multiplier: if number == guess { f64::from(DICE_SIDES) } else { 0.0 },
+            }
+        }
+        Bet::Slots => {
+            let total = REEL.iter()
+                .map(|(_, count)| count)
+                .sum::<u32>();
+            let reels: [usize; 3] = std::array::from_fn(|_| {
+                let mut pick = rng.gen_range(0..total);
+                REEL.iter()
+                    .position(|(_, count)| {
+                        if pick < *count {
+                            return true;
+                        }
+                        pick -= count;
+                        false
+                    })
+                    .unwrap_or(0)
+            });
+            Roll {
+                outcome: reels
+                    .iter()
+                    .map(|i| REEL[*i].0)
+                    .collect::<String>(),
+                multiplier: slots_payout(reels) * slots_scale(),
+            }
+        }
+    }
+}
+
+/// What the symbols on the reels pay, before being scaled by `slots_scale`.
+const fn slots_payout([a, b, c]: [usize; 3]) -> f64 {
+    if a == b && b == c {
+        THREE_OF_A_KIND[a]
+    } else if a == b || b == c || a == c {
+        TWO_OF_A_KIND
+    } else {
+        0.0
+    }
+}
+
+/// What `slots_payout` is multiplied by so that the slot machine pays back exactly what is bet on
+/// average, worked out by going through every way the reels can land.
+fn slots_scale() -> f64 {
+    let total = f64::from(
+        REEL.iter()
+            .map(|(_, count)| count)
+            .sum::<u32>()
+    );
+    let chance = |i: usize| f64::from(REEL[i].1) / total;
+    let mut expected = 0.0;
+    for a in 0..REEL.len() {
+        for b in 0..REEL.len() {
+            for c in 0..REEL.len() {
+                expected += chance(a) * chance(b) * chance(c) * slots_payout([a, b, c]);
+            }
+        }
+    }
+    1.0 / expected
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{ rngs::StdRng, SeedableRng };
+
+    use super::*;
+
+    #[test]
+    fn test_games_are_fair() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for bet in [Bet::Coinflip(CoinSide::Tails), Bet::Dice(4), Bet::Slots] {
+            let rounds = 200_000;
+            let total = (0..rounds).map(|_| roll(bet, &mut rng).multiplier).sum::<f64>();
+            let average = total / f64::from(rounds);
+            assert!((average - 1.0).abs() < 0.05, "{bet:?} pays back {average} on average");
+        }
+    }
+
+    #[test]
+    fn test_roll() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for _ in 0..100 {
+            let roll = roll(Bet::Coinflip(CoinSide::Heads), &mut rng);
+            let won = roll.outcome == "heads";
+            assert!((roll.multiplier - if won { 2.0 } else { 0.0 }).abs() < f64::EPSILON);
+
+            let roll = super::roll(Bet::Dice(3), &mut rng);
+            let number: u8 = roll.outcome.parse().unwrap();
+            assert!((1..=DICE_SIDES).contains(&number));
+            assert_eq!(roll.multiplier > 0.0, number == 3);
+
+            let roll = super::roll(Bet::Slots, &mut rng);
+            assert_eq!(roll.outcome.chars().count(), 3);
+        }
+    }
+}
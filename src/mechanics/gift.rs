@@ -0,0 +1,139 @@
+//! Gifting, where a member gives some of their items to another.
+//!
+//! It is the one-sided version of a trade: the items are taken from the giver and given to the
+//! receiver in one transaction. Instant items are used by the receiver as soon as they get them,
+//! the same as any other way of getting them, but only once the transaction has been committed,
+//! since using them cannot be undone if it fails.
+
+use anyhow::{ anyhow, bail, Result };
+use mongodb::ClientSession;
+use serenity::{ client::Context, model::{ id::GuildId, user::User } };
+
+use crate::db::{
+    models::{ Inventory, Item, TransactionKind, TransactionReason },
+    uniques::DbUserId,
+    ArcTokioRwLockOption,
+    CLIENT,
+};
+
+/// Moves an amount of an item from one member's inventory to another's. Returns whether the
+/// item is instant, in which case the receiver has already used it.
+///
+/// # Warning
+/// This function will attempt to lock both members' inventories, and the receiver's balances if
+/// the item is instant. It ***WILL*** cause a deadlock if any of them is already locked before
+/// this function is called.
+///
+/// # Errors
+/// - The members are the same, or the receiver is a bot.
+/// - The amount is not positive.
+/// - The item does not exist or cannot be traded.
+/// - The giver does not have that many of the item.
+/// - Any error from using an instant item.
+/// - Any `MongoDB` error occurs.
+pub async fn gift(
+    guild_id: GuildId,
+    giver: &User,
+    receiver: &User,
+    item_name: &str,
+    amount: i64,
+    http: &Context
+) -> Result<bool> {
+    if giver.id == receiver.id {
+        bail!("You cannot gift items to yourself.");
+    }
+    if receiver.bot {
+        bail!("You cannot gift items to bots.");
+    }
+    if amount <= 0 {
+        bail!("You must gift at least 1.");
+    }
+    let item = Item::try_from_name(guild_id.into(), item_name.to_owned()).await?;
+    let item_ = item.read().await;
+    let item__ = item_
+        .as_ref()
+        .ok_or_else(|| anyhow!("Item is being used in a breaking operation."))?;
+    if !item__.tradeable() {
+        bail!("{item_name} cannot be gifted.");
+    }
+    let instant = item__.is_instant();
+    drop(item_);
+
+    let giver_id: DbUserId = giver.id.into();
+    let receiver_id: DbUserId = receiver.id.into();
+    let giver_inventory = Inventory::try_from_user(guild_id.into(), giver_id).await?;
+    let receiver_inventory = Inventory::try_from_user(guild_id.into(), receiver_id).await?;
+    let (mut giver_inventory, mut receiver_inventory) = Inventory::lock_pair(
+        (&giver_inventory, giver_id),
+        (&receiver_inventory, receiver_id)
+    ).await;
+
+    let giver_inventory_ = giver_inventory
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your inventory is being used in a breaking operation."))?;
+    let receiver_inventory_ = receiver_inventory
+        .as_mut()
+        .ok_or_else(||
+            anyhow!("{}'s inventory is being used in a breaking operation.", receiver.name)
+        )?;
+
+    let mut session = CLIENT.get().await.start_session(None).await?;
+    session.start_transaction(None).await?;
+    let reason = TransactionReason::new(TransactionKind::Gift, giver_id);
+    let res = transaction_function(
+        &mut session,
+        (giver_inventory_, &mut *receiver_inventory_),
+        item.clone(),
+        item_name,
+        amount,
+        reason,
+        instant,
+        http
+    ).await;
+    if let Err(e) = res {
+        // Invalidate before aborting so the cache is never left holding amounts that did not
+        // make it into the database.
+        Inventory::invalidate_cache(giver_inventory).await.ok();
+        Inventory::invalidate_cache(receiver_inventory).await.ok();
+        session.abort_transaction().await?;
+        bail!("Error when gifting: {e}");
+    }
+    if let Err(e) = session.commit_transaction().await {
+        Inventory::invalidate_cache(giver_inventory).await.ok();
+        Inventory::invalidate_cache(receiver_inventory).await.ok();
+        return Err(e.into());
+    }
+
+    if instant {
+        receiver_inventory_
+            .give_item(item, amount, reason, None, 0, http).await
+            .map_err(|e| {
+                anyhow!("{} got the items, but they could not be used: {e}", receiver.name)
+            })?;
+    }
+
+    drop(giver_inventory);
+    drop(receiver_inventory);
+
+    Ok(instant)
+}
+
+/// Takes the items from the giver and gives them to the receiver, unless they are instant, in which
+/// case they are only taken and the caller uses them once this has been committed.
+#[allow(clippy::too_many_arguments)]
+async fn transaction_function(
+    session: &mut ClientSession,
+    (giver_inventory, receiver_inventory): (&mut Inventory, &mut Inventory),
+    item: ArcTokioRwLockOption<Item>,
+    item_name: &str,
+    amount: i64,
+    reason: TransactionReason,
+    instant: bool,
+    http: &Context
+) -> Result<()> {
+    giver_inventory.take_item(item_name, amount, reason, Some(&mut *session)).await?;
+    if instant {
+        return Ok(());
+    }
+    receiver_inventory.give_item(item, amount, reason, Some(session), 0, http).await
+}
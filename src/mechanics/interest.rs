@@ -0,0 +1,147 @@
+use anyhow::{ anyhow, Result };
+use chrono::Utc;
+use mongodb::{ bson::{ doc, Document }, ClientSession };
+use tracing::{ info, warn };
+
+use crate::db::{
+    models::{
+        balances::Pocket,
+        scheduled_job::Schedule,
+        Balances,
+        Currency,
+        ScheduledJob,
+        TransactionKind,
+        TransactionReason,
+    },
+    uniques::DbGuildId,
+};
+
+/// The kind of the scheduled jobs that apply interest.
+pub const JOB_KIND: &str = "interest";
+
+/// The shortest interval interest may be applied at.
+pub const MIN_INTERVAL_SECONDS: i64 = 60;
+
+/// The name of the scheduled job applying interest to a currency.
+pub fn job_name(guild_id: DbGuildId, curr_name: &str) -> String {
+    format!("{JOB_KIND}:{}:{curr_name}", guild_id.as_u64())
+}
+
+fn payload(guild_id: DbGuildId, curr_name: &str) -> Document {
+    doc! { "GuildId": guild_id.as_i64(), "CurrName": curr_name }
+}
+
+/// Makes the interest job of a currency match its configuration, scheduling it one interval from
+/// now if the currency has an interest rate and cancelling it if it does not.
+///
+/// # Errors
+/// - Any `MongoDB` error occurs.
+pub async fn sync_job(currency: &Currency, session: Option<&mut ClientSession>) -> Result<()> {
+    let name = job_name(currency.guild_id(), currency.curr_name().as_str());
+    if currency.interest_rate().is_none() {
+        return ScheduledJob::cancel(&name, session).await;
+    }
+    let interval = currency.interest_interval();
+    ScheduledJob::upsert(
+        name,
+        JOB_KIND,
+        payload(currency.guild_id(), currency.curr_name().as_str()),
+        Schedule::Interval { seconds: interval.num_seconds() },
+        Utc::now() + interval,
+        session
+    ).await?;
+    Ok(())
+}
+
+/// Cancels the interest job of a currency, if it has one.
+///
+/// # Errors
+/// - Any `MongoDB` error occurs.
+pub async fn cancel_job(
+    guild_id: DbGuildId,
+    curr_name: &str,
+    session: Option<&mut ClientSession>
+) -> Result<()> {
+    ScheduledJob::cancel(&job_name(guild_id, curr_name), session).await
+}
+
+/// Moves the interest job of a currency over to its new name, keeping when it runs next.
+///
+/// # Errors
+/// - Any `MongoDB` error occurs.
+pub async fn rename_job(
+    guild_id: DbGuildId,
+    before: &str,
+    after: &str,
+    session: &mut ClientSession
+) -> Result<()> {
+    let Some(job) = ScheduledJob::try_from_name(&job_name(guild_id, before)).await? else {
+        return Ok(());
+    };
+    ScheduledJob::upsert(
+        job_name(guild_id, after),
+        JOB_KIND,
+        payload(guild_id, after),
+        job.schedule().clone(),
+        job.next_run(),
+        Some(session)
+    ).await?;
+    ScheduledJob::cancel(job.name(), Some(session)).await
+}
+
+/// Runs an interest job by applying the interest rate of the currency to every balance of it, or
+/// to what is in the bank if the currency has one.
+///
+/// If the currency no longer exists or no longer has an interest rate the job is cancelled.
+///
+/// # Errors
+/// - The job has no valid guild id or currency name.
+/// - The currency is being used in a breaking operation.
+/// - Any `MongoDB` error occurs.
+pub async fn run_job(job: ScheduledJob) -> Result<()> {
+    let guild_id: DbGuildId = job
+        .payload()
+        .get_i64("GuildId")
+        .map_err(|_| anyhow!("Interest job {} has no guild id.", job.name()))?
+        .into();
+    let curr_name = job
+        .payload()
+        .get_str("CurrName")
+        .map_err(|_| anyhow!("Interest job {} has no currency name.", job.name()))?;
+
+    let Some(currency) = Currency::try_from_name(guild_id, curr_name.to_owned()).await? else {
+        warn!("Currency {curr_name} no longer exists, cancelling its interest job.");
+        ScheduledJob::cancel(job.name(), None).await?;
+        return Ok(());
+    };
+    let currency = currency.read().await;
+    let currency_ = currency
+        .as_ref()
+        .ok_or_else(|| anyhow!("Currency {curr_name} is being used in a breaking operation."))?;
+    let (rate, threshold, precision) = (
+        currency_.interest_rate(),
+        currency_.interest_threshold(),
+        currency_.precision(),
+    );
+    // With a bank, only what members set aside in it earns interest.
+    let pocket = if currency_.bank() { Pocket::Bank } else { Pocket::Wallet };
+    drop(currency);
+    let Some(rate) = rate else {
+        ScheduledJob::cancel(job.name(), None).await?;
+        return Ok(());
+    };
+    let changed = Balances::apply_interest(
+        guild_id,
+        curr_name,
+        pocket,
+        rate / 100.0,
+        threshold,
+        precision,
+        TransactionReason::system(TransactionKind::Interest)
+    ).await?;
+    info!(
+        "Applied {rate}% interest to {changed} balances of {curr_name} in guild {}.",
+        guild_id.as_u64()
+    );
+    Ok(())
+}
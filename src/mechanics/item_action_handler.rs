@@ -19,11 +19,14 @@ use crate::{
             DropTable,
             Inventory,
             Item,
+            TransactionKind,
+            TransactionReason,
         },
         ArcTokioRwLockOption,
         CLIENT,
     },
     mechanics::drop_generator::DropGenerator,
+    util::money::Money,
 };
 
 use super::drop_generator::{ DropResult, DropResultKind };
@@ -52,6 +55,7 @@ pub enum UseResultContent {
 /// cause a deadlock if the balances are already locked before this function
 /// is called.
 #[async_recursion]
+#[allow(clippy::too_many_lines)]
 pub async fn use_item<'a>(
     user: UserId,
     user_inv: &mut Inventory,
@@ -119,7 +123,16 @@ pub async fn use_item<'a>(
             drop(item_);
             // DANGER don't delete this, or dead locks may occur.
 
-            give_drops(guild_id.into(), user, user_inv, drops.clone(), rec_depth + 1, http).await?;
+            let reason = TransactionReason::new(TransactionKind::LootboxDrop, user.into());
+            give_drops(
+                guild_id.into(),
+                user,
+                user_inv,
+                drops.clone(),
+                reason,
+                rec_depth + 1,
+                http
+            ).await?;
             // extract the string that is between %% in the message
             let mut message = message.unwrap_or_else(||
                 "Got %%*{{ITEM_CURRENCY_NAME}}*x{{AMOUNT}} %%".to_owned()
@@ -181,6 +194,7 @@ pub async fn give_drops(
     user: UserId,
     user_inv: &mut Inventory,
     drops: Vec<DropResult<'async_recursion>>,
+    reason: TransactionReason,
     rec_depth: u8,
     http: &Context
 ) -> Result<()> {
@@ -212,13 +226,13 @@ pub async fn give_drops(
                     anyhow!("Member's balances are being used in a breaking operation.")
                 })?;
             for currency in currency_drops {
-                give_currency(currency, balances_, &mut session).await?;
+                give_currency(currency, balances_, reason, &mut session).await?;
             }
             drop(balances);
         }
         if !item_drops.is_empty() {
             for item in item_drops {
-                give_items(item, user_inv, &mut session, rec_depth + 1, http).await?;
+                give_items(item, user_inv, reason, &mut session, rec_depth + 1, http).await?;
             }
         }
         Ok(())
@@ -236,6 +250,7 @@ pub async fn give_drops(
 pub async fn give_items(
     items: DropResult<'async_recursion>,
     inventory: &mut Inventory,
+    reason: TransactionReason,
     session: &mut ClientSession,
     rec_depth: u8,
     http: &Context
@@ -249,20 +264,23 @@ pub async fn give_items(
 
     let item = Item::try_from_name(inventory.guild_id(), items.name().to_owned()).await?;
 
-    inventory.give_item(item, items.quantity, Some(session), rec_depth + 1, http).await?;
+    inventory.give_item(item, items.quantity, reason, Some(session), rec_depth + 1, http).await?;
     Ok(())
 }
 
-#[allow(clippy::cast_precision_loss)]
 pub async fn give_currency(
     currency: DropResult<'_>,
     balances: &mut Balances,
+    reason: TransactionReason,
     session: &mut ClientSession
 ) -> Result<()> {
     if !matches!(currency.result, DropResultKind::Currency(_)) {
         anyhow::bail!("DropResult is not currency.");
     }
     let balance = balances.ensure_has_currency(Cow::from(currency.name())).await?;
-    balance.add_amount(currency.quantity as f64, Some(session)).await?;
+    let amount = Money::from_whole(currency.quantity).ok_or_else(||
+        anyhow!("Too much currency dropped.")
+    )?;
+    balance.add_amount(amount, reason, Some(session)).await?;
     Ok(())
 }
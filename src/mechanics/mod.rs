@@ -1,3 +1,15 @@
+pub mod activity;
+pub mod anti_farming;
+pub mod bank;
+pub mod claim;
 pub mod drop_generator;
 pub mod exchange;
+pub mod gambling;
+pub mod gift;
+pub mod interest;
 pub mod item_action_handler;
+pub mod pay;
+pub mod pending_earnings;
+pub mod rob;
+pub mod role_income;
+pub mod trade;
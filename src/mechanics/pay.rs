@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, bail, Result };
+use serenity::model::{ prelude::Member, user::User };
+use tracing::error;
+
+use crate::db::uniques::DbUserId;
+use crate::db::CLIENT;
+use crate::{
+    db::models::{ Balance, Balances, Currency, TransactionKind, TransactionReason },
+    util::money::Money,
+};
+
+/// Moves an amount of a currency from one member's balance to another's.
+/// Returns the amount that was actually transferred after truncation.
+///
+/// # Arguments
+///
+/// * `currency` - The currency to pay in.
+/// * `amount` - The amount of the currency to pay.
+/// * `sender` - The member paying, whose balance will be subtracted from.
+/// * `receiver` - The user receiving the payment. Must be a member of the same guild.
+///
+/// # Errors
+///
+/// * If the currency does not allow members to pay each other.
+/// * If the sender and the receiver are the same user.
+/// * If the receiver is a bot.
+/// * If the amount is not a positive finite number after truncating it to the currency's precision.
+/// * If the sender does not have enough of the currency.
+/// * Any ``MongoDB`` errors.
+pub async fn pay(
+    currency: &Currency,
+    amount: f64,
+    sender: &Member,
+    receiver: &User
+) -> Result<Money> {
+    if !currency.pay() {
+        bail!("{} cannot be paid to other members.", currency.curr_name().as_str());
+    }
+    if sender.user.id == receiver.id {
+        bail!("You cannot pay yourself.");
+    }
+    if receiver.bot {
+        bail!("You cannot pay bots.");
+    }
+    let amount = currency.amount_from_f64(amount)?;
+    if amount <= Money::ZERO {
+        bail!("You must pay more than 0.");
+    }
+
+    let guild_id = sender.guild_id.into();
+    let sender_id: DbUserId = sender.user.id.into();
+    let receiver_id: DbUserId = receiver.id.into();
+
+    let sender_balances = Balances::try_from_user(guild_id, sender_id).await?;
+    let receiver_balances = Balances::try_from_user(guild_id, receiver_id).await?;
+
+    let (mut sender_balances, mut receiver_balances) = Balances::lock_pair(
+        (&sender_balances, sender_id),
+        (&receiver_balances, receiver_id)
+    ).await;
+
+    let sender_balances_ = sender_balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?;
+    let receiver_balances_ = receiver_balances
+        .as_mut()
+        .ok_or_else(||
+            anyhow!("{}'s balances are being used in a breaking operation.", receiver.name)
+        )?;
+
+    let balance_in = sender_balances_.ensure_has_currency(
+        Cow::from(currency.curr_name().as_str())
+    ).await?;
+    let balance_out = receiver_balances_.ensure_has_currency(
+        Cow::from(currency.curr_name().as_str())
+    ).await?;
+
+    if balance_in.amount() < amount {
+        bail!("You don't have enough {}.", currency.curr_name().as_str());
+    }
+
+    let mut session = CLIENT.get().await.start_session(None).await?;
+    session.start_transaction(None).await?;
+    if
+        let Err(e) = transaction_function(
+            &mut session,
+            balance_in,
+            balance_out,
+            amount,
+            TransactionReason::new(TransactionKind::Pay, sender_id)
+        ).await
+    {
+        error!("Error when paying: {}", e);
+        // Same as with exchanging, invalidate before aborting so the cache is never left
+        // holding amounts that did not make it into the database.
+        Balances::invalidate_cache(sender_balances).await.ok();
+        Balances::invalidate_cache(receiver_balances).await.ok();
+        session.abort_transaction().await?;
+        bail!("Error when paying: {}", e);
+    }
+    if let Err(e) = session.commit_transaction().await {
+        Balances::invalidate_cache(sender_balances).await.ok();
+        Balances::invalidate_cache(receiver_balances).await.ok();
+        return Err(e.into());
+    }
+
+    drop(sender_balances);
+    drop(receiver_balances);
+
+    Ok(amount)
+}
+
+async fn transaction_function(
+    mut session: &mut mongodb::ClientSession,
+    balance_in: &mut Balance,
+    balance_out: &mut Balance,
+    amount: Money,
+    reason: TransactionReason
+) -> Result<()> {
+    balance_in.sub_amount(amount, reason, Some(&mut session)).await?;
+    balance_out.add_amount(amount, reason, Some(&mut session)).await?;
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! Earnings from chatting that have not been written to the database yet.
+//!
+//! Chatting is what happens the most by far on a busy guild, so rather than updating a balance for
+//! every message that earns, earnings are added up in memory and flushed every `FLUSH_INTERVAL`
+//! with one `Balances::bulk_add_amounts` per currency.
+//!
+//! Earnings that are still pending when the bot goes down are lost, which is at most a few seconds
+//! worth of chatting.
+
+use std::{ collections::HashMap, sync::{ atomic::{ AtomicBool, Ordering }, Mutex } };
+
+use lazy_static::lazy_static;
+use tracing::{ debug, warn };
+
+use crate::{
+    db::{
+        models::{ Balances, Currency, TransactionKind, TransactionReason },
+        uniques::{ DbGuildId, DbUserId },
+    },
+    util::money::Money,
+};
+
+/// How often pending earnings are written to the database.
+pub const FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// What each member has earned of a currency since the last flush.
+type Pending = HashMap<(DbGuildId, String), HashMap<DbUserId, Money>>;
+
+lazy_static! {
+    static ref PENDING: Mutex<Pending> = Mutex::new(HashMap::new());
+}
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Adds an amount to what a member has earned of a currency since the last flush.
+pub fn add(guild_id: DbGuildId, curr_name: &str, user_id: DbUserId, amount: Money) {
+    if amount.is_zero() {
+        return;
+    }
+    let Ok(mut pending) = PENDING.lock() else {
+        warn!("Pending earnings are poisoned, dropping an earning of {}.", curr_name);
+        return;
+    };
+    let earned = pending
+        .entry((guild_id, curr_name.to_owned()))
+        .or_default()
+        .entry(user_id)
+        .or_insert(Money::ZERO);
+    *earned = earned.saturating_add(amount);
+}
+
+/// Moves what is pending for a currency over to its new name.
+pub fn rename_currency(guild_id: DbGuildId, before: &str, after: &str) {
+    let Ok(mut pending) = PENDING.lock() else {
+        return;
+    };
+    let Some(earned) = pending.remove(&(guild_id, before.to_owned())) else {
+        return;
+    };
+    let merged = pending.entry((guild_id, after.to_owned())).or_default();
+    for (user_id, amount) in earned {
+        let total = merged.entry(user_id).or_insert(Money::ZERO);
+        *total = total.saturating_add(amount);
+    }
+}
+
+/// Forgets everything that is pending for a guild, for when its balances are replaced.
+pub fn forget_guild(guild_id: DbGuildId) {
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.retain(|(g, _), _| *g != guild_id);
+    }
+}
+
+/// Writes everything that is pending to the database. Returns how many balances were updated.
+///
+/// Earnings of a currency that failed to be written are put back, so they are tried again on the
+/// next flush. Earnings of currencies that no longer exist are dropped.
+pub async fn flush() -> usize {
+    let pending = match PENDING.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => {
+            return 0;
+        }
+    };
+    let mut updated = 0;
+    for ((guild_id, curr_name), amounts) in pending {
+        match Currency::try_from_name(guild_id, curr_name.clone()).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                debug!("Dropping pending earnings of deleted currency {}.", curr_name);
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to get currency {} to flush earnings: {}", curr_name, e);
+                put_back(guild_id, &curr_name, amounts);
+                continue;
+            }
+        }
+        let reason = TransactionReason::system(TransactionKind::ChatEarn);
+        match Balances::bulk_add_amounts(guild_id, &curr_name, &amounts, reason).await {
+            Ok(()) => {
+                updated += amounts.len();
+            }
+            Err(e) => {
+                warn!("Failed to flush earnings of {}: {}", curr_name, e);
+                put_back(guild_id, &curr_name, amounts);
+            }
+        }
+    }
+    updated
+}
+
+fn put_back(guild_id: DbGuildId, curr_name: &str, amounts: HashMap<DbUserId, Money>) {
+    for (user_id, amount) in amounts {
+        add(guild_id, curr_name, user_id, amount);
+    }
+}
+
+/// Spawns the task that flushes pending earnings every `FLUSH_INTERVAL`.
+///
+/// This is called from `ready`, which may fire more than once, so it only does anything the first
+/// time.
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let updated = flush().await;
+            if updated > 0 {
+                debug!("Flushed pending earnings of {} balances.", updated);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pending_of(guild_id: DbGuildId, curr_name: &str, user_id: DbUserId) -> Option<Money> {
+        PENDING.lock().unwrap().get(&(guild_id, curr_name.to_owned()))?.get(&user_id).copied()
+    }
+
+    #[test]
+    fn test_add_and_rename() {
+        let guild_id = DbGuildId::from(555_u64);
+        let user_id = DbUserId::from(1_u64);
+        let whole = |n| Money::from_whole(n).unwrap();
+        add(guild_id, "pending test", user_id, whole(2));
+        add(guild_id, "pending test", user_id, whole(3));
+        add(guild_id, "pending test", DbUserId::from(2_u64), Money::ZERO);
+        assert_eq!(pending_of(guild_id, "pending test", user_id), Some(whole(5)));
+        assert_eq!(pending_of(guild_id, "pending test", DbUserId::from(2_u64)), None);
+
+        add(guild_id, "pending renamed", user_id, whole(1));
+        rename_currency(guild_id, "pending test", "pending renamed");
+        assert_eq!(pending_of(guild_id, "pending test", user_id), None);
+        assert_eq!(pending_of(guild_id, "pending renamed", user_id), Some(whole(6)));
+        PENDING.lock().unwrap().remove(&(guild_id, "pending renamed".to_owned()));
+    }
+}
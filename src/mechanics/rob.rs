@@ -0,0 +1,223 @@
+//! Robbing, where a member tries to take some of another member's balance.
+//!
+//! If it works they take a random part of it, up to the most the currency allows. If it does not,
+//! they are fined part of their own balance, which goes to the victim or is burned.
+
+use std::borrow::Cow;
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Utc };
+use mongodb::ClientSession;
+use rand::Rng;
+use serenity::model::{ id::GuildId, user::User };
+
+use crate::{
+    db::{
+        models::{
+            rob::{ FineTarget, RobConfig, RobCooldown, RobRole },
+            Balance,
+            Balances,
+            Currency,
+            TransactionKind,
+            TransactionReason,
+        },
+        uniques::DbUserId,
+        CLIENT,
+    },
+    util::money::Money,
+};
+
+/// What happened when a member tried to rob another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RobOutcome {
+    /// The robbery worked and this much was taken.
+    Stole(Money),
+    /// The robbery failed and the robber was fined this much.
+    Fined(Money),
+    /// The robber has to wait until the given time to rob again.
+    RobberOnCooldown(DateTime<Utc>),
+    /// The victim can not be robbed again until the given time.
+    VictimProtected(DateTime<Utc>),
+}
+
+/// Has a member try to rob another in a currency.
+///
+/// Moving the money and starting the cooldowns all happen in one transaction.
+///
+/// # Warning
+/// This function will attempt to lock both members' balances. It ***WILL*** cause a deadlock if
+/// either of them is already locked before this function is called.
+///
+/// # Errors
+/// - The members are the same, or the victim is a bot.
+/// - The currency does not exist or robbing is turned off for it.
+/// - The victim has none of the currency.
+/// - Any `MongoDB` error occurs.
+pub async fn rob(
+    guild_id: GuildId,
+    robber: &User,
+    victim: &User,
+    curr_name: &str
+) -> Result<RobOutcome> {
+    if robber.id == victim.id {
+        bail!("You cannot rob yourself.");
+    }
+    if victim.bot {
+        bail!("You cannot rob bots.");
+    }
+    let precision = Currency::precision_from_name(guild_id.into(), curr_name.to_owned()).await?;
+    let config = RobConfig::from_currency(guild_id.into(), curr_name).await?;
+    if !config.enabled() {
+        bail!("{curr_name} cannot be robbed.");
+    }
+
+    let robber_id: DbUserId = robber.id.into();
+    let victim_id: DbUserId = victim.id.into();
+    let robber_balances = Balances::try_from_user(guild_id.into(), robber_id).await?;
+    let victim_balances = Balances::try_from_user(guild_id.into(), victim_id).await?;
+    let (mut robber_balances, mut victim_balances) = Balances::lock_pair(
+        (&robber_balances, robber_id),
+        (&victim_balances, victim_id)
+    ).await;
+
+    let robber_balances_ = robber_balances
+        .as_mut()
+        .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?;
+    let victim_balances_ = victim_balances
+        .as_mut()
+        .ok_or_else(||
+            anyhow!("{}'s balances are being used in a breaking operation.", victim.name)
+        )?;
+    let robber_balance = robber_balances_.ensure_has_currency(Cow::from(curr_name)).await?;
+    let victim_balance = victim_balances_.ensure_has_currency(Cow::from(curr_name)).await?;
+
+    if victim_balance.amount() <= Money::ZERO {
+        bail!("{} has no {curr_name} to steal.", victim.name);
+    }
+
+    let (success, share) = {
+        let mut rng = rand::rngs::OsRng;
+        let chance = config.success_chance(
+            robber_balance.amount().to_f64(),
+            victim_balance.amount().to_f64()
+        );
+        (rng.gen_bool(chance), rng.gen_range(0.0..=config.max_steal()))
+    };
+    let outcome = if success {
+        RobOutcome::Stole(victim_balance.amount().checked_mul_rate(share, precision)?)
+    } else {
+        RobOutcome::Fined(
+            robber_balance.amount().max(Money::ZERO).checked_mul_rate(config.fine(), precision)?
+        )
+    };
+    let now = Utc::now();
+
+    let mut session = CLIENT.get().await.start_session(None).await?;
+    session.start_transaction(None).await?;
+    let res = transaction_function(
+        &mut session,
+        (robber_balance, victim_balance),
+        &config,
+        outcome,
+        now,
+        TransactionReason::new(TransactionKind::Rob, robber_id)
+    ).await;
+
+    match res {
+        Ok(None) => {}
+        Ok(Some(on_cooldown)) => {
+            session.abort_transaction().await?;
+            return Ok(on_cooldown);
+        }
+        Err(e) => {
+            // Invalidate before aborting so the cache is never left holding amounts that did not
+            // make it into the database.
+            Balances::invalidate_cache(robber_balances).await.ok();
+            Balances::invalidate_cache(victim_balances).await.ok();
+            session.abort_transaction().await?;
+            bail!("Error when robbing: {e}");
+        }
+    }
+    if let Err(e) = session.commit_transaction().await {
+        Balances::invalidate_cache(robber_balances).await.ok();
+        Balances::invalidate_cache(victim_balances).await.ok();
+        return Err(e.into());
+    }
+
+    drop(robber_balances);
+    drop(victim_balances);
+
+    Ok(outcome)
+}
+
+/// Starts the cooldowns and moves the money, unless the robber or the victim is still on cooldown,
+/// in which case that is returned.
+async fn transaction_function(
+    session: &mut ClientSession,
+    (robber_balance, victim_balance): (&mut Balance, &mut Balance),
+    config: &RobConfig,
+    outcome: RobOutcome,
+    now: DateTime<Utc>,
+    reason: TransactionReason
+) -> Result<Option<RobOutcome>> {
+    let guild_id = robber_balance.guild_id();
+    let robber_id = robber_balance.user_id();
+    let victim_id = victim_balance.user_id();
+    let curr_name = config.curr_name();
+
+    let robber_cooldown = RobCooldown::active_until(
+        guild_id,
+        robber_id,
+        curr_name,
+        RobRole::Robber,
+        now,
+        session
+    ).await?;
+    if let Some(ends_at) = robber_cooldown {
+        return Ok(Some(RobOutcome::RobberOnCooldown(ends_at)));
+    }
+    let victim_cooldown = RobCooldown::active_until(
+        guild_id,
+        victim_id,
+        curr_name,
+        RobRole::Victim,
+        now,
+        session
+    ).await?;
+    if let Some(ends_at) = victim_cooldown {
+        return Ok(Some(RobOutcome::VictimProtected(ends_at)));
+    }
+
+    RobCooldown::start(
+        guild_id,
+        robber_id,
+        curr_name,
+        RobRole::Robber,
+        now + config.robber_cooldown(),
+        session
+    ).await?;
+    match outcome {
+        RobOutcome::Stole(amount) => {
+            RobCooldown::start(
+                guild_id,
+                victim_id,
+                curr_name,
+                RobRole::Victim,
+                now + config.victim_cooldown(),
+                session
+            ).await?;
+            if !amount.is_zero() {
+                victim_balance.sub_amount(amount, reason, Some(&mut *session)).await?;
+                robber_balance.add_amount(amount, reason, Some(&mut *session)).await?;
+            }
+        }
+        RobOutcome::Fined(amount) if !amount.is_zero() => {
+            robber_balance.sub_amount(amount, reason, Some(&mut *session)).await?;
+            if config.fine_target() == FineTarget::Victim {
+                victim_balance.add_amount(amount, reason, Some(&mut *session)).await?;
+            }
+        }
+        _ => {}
+    }
+    Ok(None)
+}
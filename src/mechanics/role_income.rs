@@ -0,0 +1,105 @@
+use anyhow::{ anyhow, Result };
+use mongodb::bson::oid::ObjectId;
+use serenity::{ all::{ GuildId, RoleId, UserId }, http::Http };
+use tracing::{ info, warn };
+
+use crate::db::{
+    models::{
+        role_income::RoleIncome,
+        Balances,
+        Currency,
+        ScheduledJob,
+        TransactionKind,
+        TransactionReason,
+    },
+    uniques::DbUserId,
+};
+
+/// The kind of the scheduled jobs that pay out role incomes.
+pub const JOB_KIND: &str = "role_income";
+
+/// How many members are fetched from Discord per request. This is the most Discord allows.
+const MEMBERS_PER_REQUEST: u64 = 1000;
+
+/// Runs a role income job by paying everyone with the role.
+///
+/// If the role income no longer exists the job is cancelled.
+///
+/// # Errors
+/// - The job has no valid role income id.
+/// - The currency of the role income does not exist anymore.
+/// - Any Discord or `MongoDB` error occurs.
+pub async fn run_job(http: &Http, job: ScheduledJob) -> Result<()> {
+    let id: ObjectId = job
+        .payload()
+        .get_object_id("Id")
+        .map_err(|_| anyhow!("Role income job {} has no role income id.", job.name()))?;
+    let Some(income) = RoleIncome::try_from_id(id).await? else {
+        warn!("Role income {} no longer exists, cancelling its job.", id);
+        ScheduledJob::cancel(job.name(), None).await?;
+        return Ok(());
+    };
+    pay_role_income(http, &income).await
+}
+
+/// Pays every member that has the role of a role income the amount of the role income in one go.
+///
+/// # Errors
+/// - The currency of the role income does not exist anymore.
+/// - Any Discord or `MongoDB` error occurs.
+pub async fn pay_role_income(http: &Http, income: &RoleIncome) -> Result<()> {
+    if Currency::try_from_name(income.guild_id(), income.curr_name().to_owned()).await?.is_none() {
+        return Err(
+            anyhow!(
+                "Currency {} of the role income of role {} does not exist.",
+                income.curr_name(),
+                income.role_id().as_u64()
+            )
+        );
+    }
+    let members = members_with_role(
+        http,
+        income.guild_id().into(),
+        income.role_id().into()
+    ).await?;
+    Balances::bulk_add_amount(
+        income.guild_id(),
+        income.curr_name(),
+        &members,
+        income.amount(),
+        TransactionReason::system(TransactionKind::RoleIncome)
+    ).await?;
+    info!(
+        "Paid {} {} to {} members with role {} in guild {}.",
+        income.amount(),
+        income.curr_name(),
+        members.len(),
+        income.role_id().as_u64(),
+        income.guild_id().as_u64()
+    );
+    Ok(())
+}
+
+/// Fetches every member of a guild that has the role, leaving out bots.
+async fn members_with_role(http: &Http, guild_id: GuildId, role_id: RoleId) -> Result<Vec<DbUserId>> {
+    let mut members = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = guild_id.members(http, Some(MEMBERS_PER_REQUEST), after).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = Some(last.user.id);
+        let len = page.len();
+        members.extend(
+            page
+                .into_iter()
+                .filter(|m| !m.user.bot && m.roles.contains(&role_id))
+                .map(|m| DbUserId::from(m.user.id))
+        );
+        if (len as u64) < MEMBERS_PER_REQUEST {
+            break;
+        }
+    }
+    Ok(members)
+}
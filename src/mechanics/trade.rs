@@ -0,0 +1,468 @@
+//! Trading, where two members swap items and currency with each other.
+//!
+//! A trade only lives in memory while the message it was offered with is open. Both members put in
+//! what they want to give, and once both of them confirm, everything is swapped in one transaction.
+//! Changing anything takes back both confirmations, so nobody can confirm one offer and end up
+//! with another.
+
+use std::{ borrow::Cow, collections::{ BTreeMap, HashMap } };
+
+use anyhow::{ anyhow, bail, Result };
+use chrono::{ DateTime, Duration, Utc };
+use mongodb::ClientSession;
+use serenity::{ client::Context, model::{ id::{ GuildId, UserId }, user::User } };
+
+use crate::{
+    db::{
+        models::{
+            Balances,
+            Currency,
+            Inventory,
+            InventoryEntry,
+            Item,
+            TransactionKind,
+            TransactionReason,
+        },
+        uniques::DbUserId,
+        ArcTokioRwLockOption,
+        CLIENT,
+    },
+    util::money::Money,
+};
+
+/// How long members have to agree on a trade before it expires.
+pub const EXPIRES_AFTER_MINUTES: i64 = 5;
+/// How many different items and currencies each member can put into a trade.
+pub const MAX_ENTRIES: usize = 10;
+
+/// What one member puts into a trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeSide {
+    pub user_id: UserId,
+    pub items: BTreeMap<String, i64>,
+    pub currencies: BTreeMap<String, Money>,
+    pub confirmed: bool,
+}
+
+impl TradeSide {
+    const fn new(user_id: UserId) -> Self {
+        Self { user_id, items: BTreeMap::new(), currencies: BTreeMap::new(), confirmed: false }
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.currencies.is_empty()
+    }
+
+    fn has_room_for(&self, is_new: bool) -> Result<()> {
+        if is_new && self.items.len() + self.currencies.len() >= MAX_ENTRIES {
+            bail!("You cannot put more than {MAX_ENTRIES} different things into a trade.");
+        }
+        Ok(())
+    }
+
+    /// Sets how many of an item this side gives. 0 takes the item back out.
+    fn put_item(&mut self, item_name: &str, amount: i64) -> Result<()> {
+        if amount == 0 {
+            self.items.remove(item_name);
+            return Ok(());
+        }
+        self.has_room_for(!self.items.contains_key(item_name))?;
+        self.items.insert(item_name.to_owned(), amount);
+        Ok(())
+    }
+
+    /// Sets how much of a currency this side gives. 0 takes the currency back out.
+    fn put_currency(&mut self, curr_name: &str, amount: Money) -> Result<()> {
+        if amount.is_zero() {
+            self.currencies.remove(curr_name);
+            return Ok(());
+        }
+        self.has_room_for(!self.currencies.contains_key(curr_name))?;
+        self.currencies.insert(curr_name.to_owned(), amount);
+        Ok(())
+    }
+}
+
+/// A trade between the member who offered it and their partner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trade {
+    guild_id: GuildId,
+    sides: [TradeSide; 2],
+    expires_at: DateTime<Utc>,
+}
+
+impl Trade {
+    /// Starts an empty trade that `initiator` offers to `partner`.
+    ///
+    /// # Errors
+    /// - The members are the same, or the partner is a bot.
+    pub fn new(guild_id: GuildId, initiator: &User, partner: &User) -> Result<Self> {
+        if initiator.id == partner.id {
+            bail!("You cannot trade with yourself.");
+        }
+        if partner.bot {
+            bail!("You cannot trade with bots.");
+        }
+        Ok(Self {
+            guild_id,
+            sides: [TradeSide::new(initiator.id), TradeSide::new(partner.id)],
+            expires_at: Utc::now() + Duration::minutes(EXPIRES_AFTER_MINUTES),
+        })
+    }
+
+    /// The initiator's side first, then the partner's.
+    #[allow(clippy::must_use_candidate)]
+    pub const fn sides(&self) -> &[TradeSide; 2] {
+        &self.sides
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub const fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_party(&self, user_id: UserId) -> bool {
+        self.sides.iter().any(|s| s.user_id == user_id)
+    }
+
+    fn side_mut(&mut self, user_id: UserId) -> Result<&mut TradeSide> {
+        self.sides
+            .iter_mut()
+            .find(|s| s.user_id == user_id)
+            .ok_or_else(|| anyhow!("This is not your trade."))
+    }
+
+    fn clear_confirmations(&mut self) {
+        for side in &mut self.sides {
+            side.confirmed = false;
+        }
+    }
+
+    /// Puts an amount of an item into the member's side, replacing what they put in of it before.
+    /// 0 takes the item back out.
+    ///
+    /// # Errors
+    /// - The member is not part of the trade.
+    /// - The amount is negative.
+    /// - The item does not exist, cannot be traded or is used as soon as it is received.
+    /// - The member does not have that many of the item.
+    /// - The member already put in as many different things as they can.
+    /// - Any `MongoDB` error occurs.
+    pub async fn offer_item(
+        &mut self,
+        user_id: UserId,
+        item_name: &str,
+        amount: i64
+    ) -> Result<()> {
+        if amount < 0 {
+            bail!("You cannot put in a negative amount.");
+        }
+        tradeable_item(self.guild_id, item_name).await?;
+        if amount > 0 {
+            let inventory = Inventory::try_from_user(self.guild_id.into(), user_id.into()).await?;
+            let inventory = inventory.lock().await;
+            let owned = inventory
+                .as_ref()
+                .ok_or_else(|| anyhow!("Your inventory is being used in a breaking operation."))?
+                .inventory()
+                .iter()
+                .find(|e| e.item_name() == item_name)
+                .map_or(0, InventoryEntry::amount);
+            drop(inventory);
+            if owned < amount {
+                bail!("You only have {owned} {item_name}.");
+            }
+        }
+
+        self.side_mut(user_id)?.put_item(item_name, amount)?;
+        self.clear_confirmations();
+        Ok(())
+    }
+
+    /// Puts an amount of a currency from the member's wallet into their side, replacing what they
+    /// put in of it before. 0 takes the currency back out. Returns the amount after truncating it
+    /// to the currency's precision.
+    ///
+    /// # Errors
+    /// - The member is not part of the trade.
+    /// - The currency does not exist or cannot be paid to other members.
+    /// - The amount is negative or not finite.
+    /// - The member does not have that much in their wallet.
+    /// - The member already put in as many different things as they can.
+    /// - Any `MongoDB` error occurs.
+    pub async fn offer_currency(
+        &mut self,
+        user_id: UserId,
+        curr_name: &str,
+        amount: f64
+    ) -> Result<Money> {
+        let currency = Currency::try_from_name(
+            self.guild_id.into(),
+            curr_name.to_owned()
+        ).await?.ok_or_else(|| anyhow!("Currency {curr_name} does not exist."))?;
+        let currency = currency.read().await;
+        let currency_ = currency
+            .as_ref()
+            .ok_or_else(|| anyhow!("Currency is being used in a breaking operation."))?;
+        if !currency_.pay() {
+            bail!("{curr_name} cannot be traded.");
+        }
+        let amount = currency_.amount_from_f64(amount)?;
+        drop(currency);
+        if amount.is_negative() {
+            bail!("You cannot put in a negative amount.");
+        }
+        if !amount.is_zero() {
+            let balances = Balances::try_from_user(self.guild_id.into(), user_id.into()).await?;
+            let mut balances = balances.lock().await;
+            let wallet = balances
+                .as_mut()
+                .ok_or_else(|| anyhow!("Your balances are being used in a breaking operation."))?
+                .ensure_has_currency(Cow::from(curr_name)).await?
+                .amount();
+            drop(balances);
+            if wallet < amount {
+                bail!("You only have {wallet} {curr_name} in your wallet.");
+            }
+        }
+
+        self.side_mut(user_id)?.put_currency(curr_name, amount)?;
+        self.clear_confirmations();
+        Ok(amount)
+    }
+
+    /// Confirms the trade for the member. Returns whether both members have confirmed it now.
+    ///
+    /// # Errors
+    /// - The member is not part of the trade.
+    /// - Nothing has been put into the trade yet.
+    pub fn confirm(&mut self, user_id: UserId) -> Result<bool> {
+        if self.sides.iter().all(TradeSide::is_empty) {
+            bail!("There is nothing in the trade yet.");
+        }
+        self.side_mut(user_id)?.confirmed = true;
+        Ok(self.sides.iter().all(|s| s.confirmed))
+    }
+
+    /// Swaps everything both members put into the trade, in one transaction over both of their
+    /// inventories and balances.
+    ///
+    /// # Warning
+    /// This function will attempt to lock both members' inventories and balances. It ***WILL***
+    /// cause a deadlock if any of them is already locked before this function is called.
+    ///
+    /// # Errors
+    /// - The trade expired or has not been confirmed by both members.
+    /// - An item or currency does not exist anymore or cannot be traded anymore.
+    /// - A member does not have what they put in anymore.
+    /// - Any `MongoDB` error occurs.
+    pub async fn execute(&self, http: &Context) -> Result<()> {
+        if Utc::now() > self.expires_at {
+            bail!("The trade expired.");
+        }
+        if !self.sides.iter().all(|s| s.confirmed) {
+            bail!("Both members need to confirm the trade.");
+        }
+        let [first, second] = &self.sides;
+
+        // Staff may have changed the items and currencies since they were put in, so check them
+        // again.
+        let mut items = HashMap::new();
+        for item_name in first.items.keys().chain(second.items.keys()) {
+            items.insert(item_name.clone(), tradeable_item(self.guild_id, item_name).await?);
+        }
+        for curr_name in first.currencies.keys().chain(second.currencies.keys()) {
+            ensure_tradeable_currency(self.guild_id, curr_name).await?;
+        }
+
+        let guild_id = self.guild_id.into();
+        let first_id: DbUserId = first.user_id.into();
+        let second_id: DbUserId = second.user_id.into();
+        let first_inventory = Inventory::try_from_user(guild_id, first_id).await?;
+        let second_inventory = Inventory::try_from_user(guild_id, second_id).await?;
+        let first_balances = Balances::try_from_user(guild_id, first_id).await?;
+        let second_balances = Balances::try_from_user(guild_id, second_id).await?;
+        let (mut first_inventory, mut second_inventory) = Inventory::lock_pair(
+            (&first_inventory, first_id),
+            (&second_inventory, second_id)
+        ).await;
+        let (mut first_balances, mut second_balances) = Balances::lock_pair(
+            (&first_balances, first_id),
+            (&second_balances, second_id)
+        ).await;
+
+        let busy = || {
+            anyhow!("An inventory or balance in the trade is being used in a breaking operation.")
+        };
+        let first_inventory_ = first_inventory.as_mut().ok_or_else(busy)?;
+        let second_inventory_ = second_inventory.as_mut().ok_or_else(busy)?;
+        let first_balances_ = first_balances.as_mut().ok_or_else(busy)?;
+        let second_balances_ = second_balances.as_mut().ok_or_else(busy)?;
+        // Creating a balance is not part of the transaction, so do it before it starts.
+        for curr_name in first.currencies.keys().chain(second.currencies.keys()) {
+            first_balances_.ensure_has_currency(Cow::from(curr_name.as_str())).await?;
+            second_balances_.ensure_has_currency(Cow::from(curr_name.as_str())).await?;
+        }
+
+        let mut session = CLIENT.get().await.start_session(None).await?;
+        session.start_transaction(None).await?;
+        let res = async {
+            give_side(
+                &mut session,
+                first,
+                (first_inventory_, first_balances_),
+                (second_inventory_, second_balances_),
+                &items,
+                http
+            ).await?;
+            give_side(
+                &mut session,
+                second,
+                (second_inventory_, second_balances_),
+                (first_inventory_, first_balances_),
+                &items,
+                http
+            ).await
+        }.await;
+
+        if let Err(e) = res {
+            // Invalidate before aborting so the cache is never left holding amounts that did not
+            // make it into the database.
+            Inventory::invalidate_cache(first_inventory).await.ok();
+            Inventory::invalidate_cache(second_inventory).await.ok();
+            Balances::invalidate_cache(first_balances).await.ok();
+            Balances::invalidate_cache(second_balances).await.ok();
+            session.abort_transaction().await?;
+            bail!("Error when trading: {e}");
+        }
+        if let Err(e) = session.commit_transaction().await {
+            Inventory::invalidate_cache(first_inventory).await.ok();
+            Inventory::invalidate_cache(second_inventory).await.ok();
+            Balances::invalidate_cache(first_balances).await.ok();
+            Balances::invalidate_cache(second_balances).await.ok();
+            return Err(e.into());
+        }
+
+        drop(first_inventory);
+        drop(second_inventory);
+        drop(first_balances);
+        drop(second_balances);
+
+        Ok(())
+    }
+}
+
+/// Gets an item, making sure it can be traded.
+async fn tradeable_item(guild_id: GuildId, item_name: &str) -> Result<ArcTokioRwLockOption<Item>> {
+    let item = Item::try_from_name(guild_id.into(), item_name.to_owned()).await?;
+    let item_ = item.read().await;
+    let item__ = item_
+        .as_ref()
+        .ok_or_else(|| anyhow!("Item is being used in a breaking operation."))?;
+    if !item__.tradeable() {
+        bail!("{item_name} cannot be traded.");
+    }
+    // These would be used up by whoever receives them, outside of the transaction.
+    if item__.is_instant() {
+        bail!("{item_name} is used as soon as it is received, so it cannot be traded.");
+    }
+    drop(item_);
+    Ok(item)
+}
+
+/// Makes sure a currency still exists and can be paid to other members.
+async fn ensure_tradeable_currency(guild_id: GuildId, curr_name: &str) -> Result<()> {
+    let currency = Currency::try_from_name(
+        guild_id.into(),
+        curr_name.to_owned()
+    ).await?.ok_or_else(|| anyhow!("Currency {curr_name} does not exist."))?;
+    let currency = currency.read().await;
+    let pay = currency
+        .as_ref()
+        .ok_or_else(|| anyhow!("Currency is being used in a breaking operation."))?
+        .pay();
+    drop(currency);
+    if !pay {
+        bail!("{curr_name} cannot be traded.");
+    }
+    Ok(())
+}
+
+/// Moves everything one side put into the trade from its member to the other.
+async fn give_side(
+    session: &mut ClientSession,
+    side: &TradeSide,
+    (from_inventory, from_balances): (&mut Inventory, &mut Balances),
+    (to_inventory, to_balances): (&mut Inventory, &mut Balances),
+    items: &HashMap<String, ArcTokioRwLockOption<Item>>,
+    http: &Context
+) -> Result<()> {
+    let reason = TransactionReason::new(TransactionKind::Trade, side.user_id.into());
+    for (item_name, amount) in &side.items {
+        from_inventory.take_item(item_name, *amount, reason, Some(&mut *session)).await?;
+        let item = items
+            .get(item_name)
+            .ok_or_else(|| anyhow!("Item {item_name} does not exist."))?
+            .clone();
+        to_inventory.give_item(item, *amount, reason, Some(&mut *session), 0, http).await?;
+    }
+    for (curr_name, amount) in &side.currencies {
+        from_balances
+            .ensure_has_currency(Cow::from(curr_name.as_str())).await?
+            .sub_amount(*amount, reason, Some(&mut *session)).await?;
+        to_balances
+            .ensure_has_currency(Cow::from(curr_name.as_str())).await?
+            .add_amount(*amount, reason, Some(&mut *session)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trade() -> Trade {
+        Trade {
+            guild_id: GuildId::new(1),
+            sides: [TradeSide::new(UserId::new(2)), TradeSide::new(UserId::new(3))],
+            expires_at: Utc::now() + Duration::minutes(EXPIRES_AFTER_MINUTES),
+        }
+    }
+
+    #[test]
+    fn test_put_entries() {
+        let mut side = TradeSide::new(UserId::new(2));
+        side.put_item("Sword", 2).unwrap();
+        side.put_item("Sword", 5).unwrap();
+        assert_eq!(side.items.get("Sword"), Some(&5));
+        side.put_item("Sword", 0).unwrap();
+        assert!(side.is_empty());
+
+        for i in 0..MAX_ENTRIES {
+            side.put_currency(&format!("Coin{i}"), Money::from_minor(100)).unwrap();
+        }
+        assert!(side.put_item("Sword", 1).is_err());
+        // Changing or removing something that is already in there is still fine.
+        side.put_currency("Coin0", Money::from_minor(200)).unwrap();
+        side.put_currency("Coin1", Money::ZERO).unwrap();
+        side.put_item("Sword", 1).unwrap();
+    }
+
+    #[test]
+    fn test_confirm() {
+        let mut trade = trade();
+        assert!(trade.confirm(UserId::new(2)).is_err());
+
+        trade.side_mut(UserId::new(2)).unwrap().put_item("Sword", 1).unwrap();
+        assert!(!trade.confirm(UserId::new(2)).unwrap());
+        assert!(trade.confirm(UserId::new(4)).is_err());
+        assert!(trade.confirm(UserId::new(3)).unwrap());
+
+        trade.clear_confirmations();
+        assert!(trade.sides().iter().all(|s| !s.confirmed));
+        assert!(trade.is_party(UserId::new(3)));
+        assert!(!trade.is_party(UserId::new(4)));
+    }
+}
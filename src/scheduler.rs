@@ -0,0 +1,213 @@
+//! The scheduler runs jobs stored in the `scheduledJobs` collection when they are due.
+//!
+//! Features register a handler for a kind of job with `Scheduler::register`, and schedule jobs of
+//! that kind with `ScheduledJob::upsert`. Since jobs live in the database, they are picked back up
+//! on startup, and any that came due while the bot was offline run on the first tick.
+//!
+//! Each tick looks for due jobs and claims them one by one before running them. Claiming is atomic
+//! (see `ScheduledJob::claim`) so a job never runs twice, even if several schedulers are running
+//! against the same database.
+pub mod clock;
+
+use std::{ collections::HashMap, future::Future, sync::{ atomic::{ AtomicBool, Ordering }, Arc } };
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serenity::client::Context;
+use tokio::task::JoinHandle;
+use tracing::{ error, info, warn };
+
+use crate::{ db::models::ScheduledJob, mechanics::{ interest, role_income } };
+
+use self::clock::{ Clock, SystemClock };
+
+/// How often the scheduler checks for due jobs by default.
+pub const DEFAULT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+type JobHandler = Arc<dyn (Fn(ScheduledJob) -> BoxFuture<'static, Result<()>>) + Send + Sync>;
+
+pub struct Scheduler<C: Clock = SystemClock> {
+    clock: C,
+    handlers: HashMap<String, JobHandler>,
+    poll_interval: std::time::Duration,
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            handlers: HashMap::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    #[must_use]
+    pub const fn poll_interval(mut self, poll_interval: std::time::Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Registers the handler that runs jobs of a certain kind. Registering a second handler
+    /// for the same kind replaces the first one.
+    ///
+    /// Jobs whose kind has no handler are left alone, not claimed.
+    pub fn register<F, Fut>(&mut self, kind: &str, handler: F)
+        where
+            F: (Fn(ScheduledJob) -> Fut) + Send + Sync + 'static,
+            Fut: Future<Output = Result<()>> + Send + 'static
+    {
+        self.handlers.insert(
+            kind.to_owned(),
+            Arc::new(move |job| Box::pin(handler(job)))
+        );
+    }
+
+    /// Runs every job that is due right now according to the clock.
+    /// Returns how many jobs were run.
+    ///
+    /// A job whose handler fails is logged and not retried.
+    ///
+    /// # Errors
+    /// - Any `MongoDB` error occurs while fetching or claiming jobs.
+    pub async fn tick(&self) -> Result<usize> {
+        if self.handlers.is_empty() {
+            return Ok(0);
+        }
+        let now = self.clock.now();
+        let kinds = self.handlers.keys().map(String::as_str).collect::<Vec<_>>();
+        let mut ran = 0;
+        for mut job in ScheduledJob::due(&kinds, now).await? {
+            if !job.claim(now).await? {
+                continue; // Someone else got to it first.
+            }
+            let Some(handler) = self.handlers.get(job.kind()) else {
+                continue;
+            };
+            let name = job.name().to_owned();
+            if let Err(e) = handler(job).await {
+                error!("Scheduled job {} failed: {}", name, e);
+            }
+            ran += 1;
+        }
+        Ok(ran)
+    }
+
+    /// Spawns a task that ticks forever.
+    pub fn start(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.tick().await {
+                    warn!("Scheduler tick failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Registers the handlers of every kind of job and starts the scheduler.
+///
+/// This is called from `ready`, which may fire more than once over the lifetime of the bot, so it
+/// only does anything the first time.
+pub fn start(ctx: &Context) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut scheduler = Scheduler::new(SystemClock);
+
+    let http = ctx.http.clone();
+    scheduler.register(role_income::JOB_KIND, move |job| {
+        let http = http.clone();
+        async move { role_income::run_job(&http, job).await }
+    });
+    scheduler.register(interest::JOB_KIND, interest::run_job);
+
+    info!("Starting scheduler with {} job kinds.", scheduler.handlers.len());
+    scheduler.start();
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+
+    use chrono::Duration;
+    use mongodb::bson::doc;
+
+    use crate::db::models::scheduled_job::Schedule;
+
+    use super::{ clock::MockClock, * };
+
+    #[tokio::test]
+    async fn test_jobs_run_once() {
+        crate::init_env().await;
+        let clock = MockClock::default();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut schedulers = Vec::new();
+        for _ in 0..2 {
+            let mut scheduler = Scheduler::new(clock.clone());
+            let counter = counter.clone();
+            scheduler.register("test", move |_| {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            });
+            schedulers.push(scheduler);
+        }
+
+        ScheduledJob::upsert(
+            "test:once".to_owned(),
+            "test",
+            doc! {},
+            Schedule::Once,
+            clock.now() + Duration::minutes(5),
+            None
+        ).await.unwrap();
+
+        assert_eq!(schedulers[0].tick().await.unwrap(), 0); // Not due yet.
+        clock.advance(Duration::minutes(5));
+        let (a, b) = tokio::join!(schedulers[0].tick(), schedulers[1].tick());
+        assert_eq!(a.unwrap() + b.unwrap(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert!(ScheduledJob::try_from_name("test:once").await.unwrap().is_none());
+
+        ScheduledJob::upsert(
+            "test:interval".to_owned(),
+            "test",
+            doc! {},
+            Schedule::Interval { seconds: 60 },
+            clock.now(),
+            None
+        ).await.unwrap();
+        assert_eq!(schedulers[0].tick().await.unwrap(), 1);
+        assert_eq!(schedulers[1].tick().await.unwrap(), 0);
+        clock.advance(Duration::seconds(60));
+        assert_eq!(schedulers[1].tick().await.unwrap(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+
+        ScheduledJob::cancel("test:interval", None).await.unwrap();
+
+        ScheduledJob::upsert(
+            "test:cron".to_owned(),
+            "test",
+            doc! {},
+            Schedule::Cron { expression: "0 12 * * *".to_owned() },
+            clock.now(),
+            None
+        ).await.unwrap();
+        assert_eq!(schedulers[0].tick().await.unwrap(), 1);
+        let job = ScheduledJob::try_from_name("test:cron").await.unwrap().unwrap();
+        assert_eq!(schedulers[1].tick().await.unwrap(), 0);
+        clock.set(job.next_run());
+        assert_eq!(schedulers[1].tick().await.unwrap(), 1);
+        assert_eq!(counter.load(Ordering::SeqCst), 5);
+
+        ScheduledJob::cancel("test:cron", None).await.unwrap();
+    }
+}
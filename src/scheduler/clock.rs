@@ -0,0 +1,58 @@
+//! Clocks for the scheduler. The scheduler never asks the system for the time directly so that
+//! tests can control time with a `MockClock`.
+
+use std::sync::{ Arc, Mutex };
+
+use chrono::{ DateTime, Duration, Utc };
+
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+///
+/// The mutex is a std one because it is never held across an await.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Arc::new(Mutex::new(now)) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += by;
+    }
+}
+
+impl Default for MockClock {
+    /// Starts the clock at the current time rounded down to the second, since that is all
+    /// the precision the database stores.
+    fn default() -> Self {
+        let now = Utc::now().timestamp();
+        Self::new(DateTime::from_timestamp(now, 0).unwrap_or_default())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,101 @@
+//! Helpers to work out where a channel sits in a guild using the serenity cache, so that settings
+//! made for a category or a channel also cover what is inside of it.
+
+use serenity::{
+    all::{ ChannelId, ChannelType, GuildId },
+    cache::Cache,
+    model::guild::Guild,
+};
+
+/// How many channels deep the lineage of a channel goes at most, which is a thread in a channel
+/// in a category.
+const MAX_DEPTH: usize = 3;
+
+/// Gets a channel followed by the channels it is inside of, like a thread, then the channel of
+/// the thread, then the category of that channel.
+///
+/// If the guild or a channel is not in the cache, the lineage stops there, so it always has at
+/// least the channel itself.
+#[allow(clippy::must_use_candidate)]
+pub fn lineage(cache: &Cache, guild_id: GuildId, channel_id: ChannelId) -> Vec<ChannelId> {
+    let Some(guild) = cache.guild(guild_id) else {
+        return vec![channel_id];
+    };
+    let lineage = lineage_in(&guild, channel_id);
+    drop(guild);
+    lineage
+}
+
+/// Like `lineage`, but for a guild that has already been taken from the cache.
+#[allow(clippy::must_use_candidate)]
+pub fn lineage_in(guild: &Guild, channel_id: ChannelId) -> Vec<ChannelId> {
+    lineage_with(channel_id, |id| {
+        guild.channels
+            .get(&id)
+            .or_else(|| guild.threads.iter().find(|t| t.id == id))
+            .and_then(|c| c.parent_id)
+    })
+}
+
+fn lineage_with(
+    channel_id: ChannelId,
+    parent_of: impl Fn(ChannelId) -> Option<ChannelId>
+) -> Vec<ChannelId> {
+    let mut lineage = vec![channel_id];
+    let mut current = channel_id;
+    while lineage.len() < MAX_DEPTH {
+        let Some(parent) = parent_of(current) else {
+            break;
+        };
+        lineage.push(parent);
+        current = parent;
+    }
+    lineage
+}
+
+/// Gets a readable name for what kind of channel a channel is, such as `category` or `thread`.
+#[allow(clippy::must_use_candidate)]
+pub fn kind_name(cache: &Cache, guild_id: GuildId, channel_id: ChannelId) -> &'static str {
+    let Some(guild) = cache.guild(guild_id) else {
+        return "unknown channel";
+    };
+    let kind = guild.channels
+        .get(&channel_id)
+        .or_else(|| guild.threads.iter().find(|t| t.id == channel_id))
+        .map(|c| c.kind);
+    drop(guild);
+    match kind {
+        Some(ChannelType::Category) => "category",
+        Some(ChannelType::Text) => "text channel",
+        Some(ChannelType::News) => "announcement channel",
+        Some(ChannelType::Voice) => "voice channel",
+        Some(ChannelType::Stage) => "stage channel",
+        Some(ChannelType::Forum) => "forum",
+        Some(ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread) =>
+            "thread",
+        Some(_) => "channel",
+        None => "unknown channel",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lineage_with() {
+        // 1 is a thread in the forum 2, which is in the category 3.
+        let parent_of = |id: ChannelId| {
+            match id.get() {
+                1 => Some(ChannelId::new(2)),
+                2 => Some(ChannelId::new(3)),
+                3 => Some(ChannelId::new(4)), // Can not happen, but should not be followed.
+                _ => None,
+            }
+        };
+        let ids = |ids: &[u64]| ids.iter().copied().map(ChannelId::new).collect::<Vec<_>>();
+        assert_eq!(lineage_with(ChannelId::new(1), parent_of), ids(&[1, 2, 3]));
+        assert_eq!(lineage_with(ChannelId::new(2), parent_of), ids(&[2, 3, 4]));
+        assert_eq!(lineage_with(ChannelId::new(5), parent_of), ids(&[5]));
+    }
+}
@@ -0,0 +1,161 @@
+//! A small reader and writer for CSV files, like the ones that other economy bots export and the
+//! ones staff take into spreadsheets.
+//!
+//! It follows RFC 4180: fields are separated by commas, records by `\n` or `\r\n`, and a field in
+//! double quotes may contain commas, line breaks and quotes written twice. That covers every export
+//! seen so far, so pulling in a whole CSV crate was not worth it.
+
+use thiserror::Error;
+
+/// A record of the file, along with the line it starts on for error messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CsvError {
+    #[error("Line {0}: a quoted field is never closed.")]
+    UnclosedQuote(usize),
+    #[error("Line {0}: a quoted field is followed by something other than a comma.")]
+    TextAfterQuote(usize),
+}
+
+/// Splits the text of a CSV file into records. Blank lines are skipped, and so is a byte order
+/// mark at the start.
+///
+/// # Errors
+/// - A quoted field is never closed, or has text between its closing quote and the next comma.
+pub fn parse(text: &str) -> Result<Vec<Record>, CsvError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start_line = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                None => {
+                    fields.push(std::mem::take(&mut field));
+                    break;
+                }
+                Some('"') if field.is_empty() && !quoted => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            None => {
+                                return Err(CsvError::UnclosedQuote(start_line));
+                            }
+                            Some('"') if chars.peek() == Some(&'"') => {
+                                chars.next();
+                                field.push('"');
+                            }
+                            Some('"') => {
+                                break;
+                            }
+                            Some(c) => {
+                                if c == '\n' {
+                                    line += 1;
+                                }
+                                field.push(c);
+                            }
+                        }
+                    }
+                    if !matches!(chars.peek(), None | Some(',' | '\r' | '\n')) {
+                        return Err(CsvError::TextAfterQuote(line));
+                    }
+                }
+                Some(',') => {
+                    fields.push(std::mem::take(&mut field));
+                    quoted = false;
+                }
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('\n') => {
+                    line += 1;
+                    fields.push(std::mem::take(&mut field));
+                    break;
+                }
+                Some(c) => field.push(c),
+            }
+        }
+        if fields.len() > 1 || fields.first().is_some_and(|f| !f.trim().is_empty()) {
+            records.push(Record { line: start_line, fields });
+        }
+    }
+    Ok(records)
+}
+
+/// Writes a record to the end of a CSV file, quoting the fields that need it.
+pub fn write_record<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\r', '\n']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fields(records: &[Record]) -> Vec<Vec<&str>> {
+        records
+            .iter()
+            .map(|r|
+                r.fields
+                    .iter()
+                    .map(String::as_str)
+                    .collect()
+            )
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let records = parse(
+            "\u{feff}user_ID,cash,bank,total\r\n1,\"1,000\",5,\"say \"\"hi\"\"\"\r\n\r\n2,,3,\n"
+        ).unwrap();
+        assert_eq!(
+            fields(&records),
+            vec![
+                vec!["user_ID", "cash", "bank", "total"],
+                vec!["1", "1,000", "5", "say \"hi\""],
+                vec!["2", "", "3", ""]
+            ]
+        );
+        assert_eq!(records[2].line, 4);
+
+        let records = parse("a,\"line\nbreak\"\nb,c").unwrap();
+        assert_eq!(fields(&records), vec![vec!["a", "line\nbreak"], vec!["b", "c"]]);
+        assert_eq!(records[1].line, 3);
+    }
+
+    #[test]
+    fn test_write_record() {
+        let mut out = String::new();
+        write_record(&mut out, &["user_id", "name"]);
+        write_record(&mut out, &["1", "Gold, \"shiny\"\nbars"]);
+        assert_eq!(out, "user_id,name\r\n1,\"Gold, \"\"shiny\"\"\nbars\"\r\n");
+        assert_eq!(fields(&parse(&out).unwrap())[1], vec!["1", "Gold, \"shiny\"\nbars"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("a,\"b\nc,d"), Err(CsvError::UnclosedQuote(1)));
+        assert_eq!(parse("a\n\"b\"c,d"), Err(CsvError::TextAfterQuote(2)));
+    }
+}
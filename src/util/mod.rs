@@ -1,3 +1,5 @@
-pub mod currency;
+pub mod channel;
+pub mod csv;
+pub mod money;
 pub mod paginator;
 pub mod user;
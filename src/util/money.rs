@@ -0,0 +1,342 @@
+//! This module contains the `Money` type, which is what every amount of currency is represented as.
+//!
+//! Amounts used to be stored as f64 and cleaned up by truncating them to 2 decimal places after
+//! every operation, but floats can not represent most decimal fractions exactly, so the errors piled
+//! up the more operations were chained together. `Money` instead stores a whole number of minor
+//! units, of which there are `10^MONEY_SCALE` in one whole unit of currency, so adding, subtracting
+//! and multiplying by whole numbers is always exact.
+//!
+//! Each currency has a precision of its own, which is how many of those decimal places it actually
+//! uses. Amounts coming in from the outside are truncated to that precision.
+//!
+//! In the database amounts are stored as 64-bit integers of minor units. Documents written before
+//! this existed have them as doubles of whole units, which is why deserializing accepts both and
+//! why `migrate_legacy_amounts` exists.
+
+use std::{ fmt, str::FromStr };
+
+use anyhow::{ anyhow, bail, Result };
+use mongodb::bson::{ doc, Bson, Document };
+use serde::{ de::Visitor, Deserialize, Deserializer, Serialize, Serializer };
+
+use crate::db::CLIENT;
+
+/// The amount of decimal places that every amount is stored with.
+pub const MONEY_SCALE: u8 = 4;
+
+/// The amount of minor units in one whole unit of currency.
+const ONE: i64 = 10_i64.pow(MONEY_SCALE as u32);
+
+/// An exact amount of currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(i64::MAX);
+
+    /// Makes an amount out of a raw count of minor units.
+    pub const fn from_minor(minor: i64) -> Self {
+        Self(minor)
+    }
+
+    /// Returns the raw count of minor units.
+    pub const fn as_minor(self) -> i64 {
+        self.0
+    }
+
+    /// Makes an amount out of a whole number of units of currency.
+    pub const fn from_whole(whole: i64) -> Option<Self> {
+        match whole.checked_mul(ONE) {
+            Some(minor) => Some(Self(minor)),
+            None => None,
+        }
+    }
+
+    /// Converts a float to an amount, truncating it to the given precision.
+    ///
+    /// The float is first rounded to the nearest minor unit so that something like `0.29`,
+    /// which is actually `0.28999...` as a float, does not get truncated down to `0.28`.
+    ///
+    /// # Errors
+    /// - If the float is NaN or infinite.
+    /// - If the float is too large to be represented.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_f64(amount: f64, precision: u8) -> Result<Self> {
+        if amount.is_nan() {
+            bail!("Amount cannot be NaN.");
+        }
+        if amount.is_infinite() {
+            bail!("Amount cannot be infinite.");
+        }
+        let minor = (amount * (ONE as f64)).round();
+        if minor >= (i64::MAX as f64) || minor <= (i64::MIN as f64) {
+            bail!("Amount is too large.");
+        }
+        Ok(Self(minor as i64).truncate(precision))
+    }
+
+    /// Converts the amount to a float. Only meant for things like exchange rates and displaying,
+    /// never for doing arithmetic with the result and turning it back.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_f64(self) -> f64 {
+        (self.0 as f64) / (ONE as f64)
+    }
+
+    /// Truncates the amount towards zero so that it has at most `precision` decimal places.
+    #[must_use]
+    pub const fn truncate(self, precision: u8) -> Self {
+        if precision >= MONEY_SCALE {
+            return self;
+        }
+        let step = 10_i64.pow((MONEY_SCALE - precision) as u32);
+        Self(self.0 - (self.0 % step))
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        match self.0.checked_add(other.0) {
+            Some(minor) => Some(Self(minor)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.0.checked_sub(other.0) {
+            Some(minor) => Some(Self(minor)),
+            None => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    /// Multiplies the amount by a whole number, such as how many of an item are being bought.
+    pub const fn checked_mul(self, times: i64) -> Option<Self> {
+        match self.0.checked_mul(times) {
+            Some(minor) => Some(Self(minor)),
+            None => None,
+        }
+    }
+
+    /// Multiplies the amount by a rate, such as an exchange rate, and truncates the result
+    /// to the given precision.
+    ///
+    /// # Errors
+    /// - If the rate is NaN, infinite or negative.
+    /// - If the result is too large to be represented.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn checked_mul_rate(self, rate: f64, precision: u8) -> Result<Self> {
+        if rate.is_nan() || rate.is_infinite() || rate < 0.0 {
+            bail!("Invalid rate.");
+        }
+        let minor = ((self.0 as f64) * rate).trunc();
+        if minor >= (i64::MAX as f64) || minor <= (i64::MIN as f64) {
+            bail!("Amount is too large.");
+        }
+        Ok(Self(minor as i64).truncate(precision))
+    }
+}
+
+impl fmt::Display for Money {
+    /// Displays the amount with as many decimal places as it needs and no more,
+    /// so `12.5000` is shown as `12.5` and `3.0000` as `3`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let whole = abs / ONE.unsigned_abs();
+        let frac = abs % ONE.unsigned_abs();
+        if frac == 0 {
+            return write!(f, "{sign}{whole}");
+        }
+        let frac = format!("{frac:0width$}", width = MONEY_SCALE as usize);
+        write!(f, "{sign}{whole}.{}", frac.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for Money {
+    type Err = anyhow::Error;
+
+    /// Parses a decimal number exactly, without ever going through a float. Any decimal places
+    /// beyond `MONEY_SCALE` are truncated.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (negative, s) = s.strip_prefix('-').map_or((false, s), |s| (true, s));
+        let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+        if
+            (whole.is_empty() && frac.is_empty()) ||
+            !whole.chars().all(|c| c.is_ascii_digit()) ||
+            !frac.chars().all(|c| c.is_ascii_digit())
+        {
+            bail!("{s} is not a valid amount.");
+        }
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse()? };
+        let mut frac = frac.chars().take(MONEY_SCALE as usize).collect::<String>();
+        while frac.len() < (MONEY_SCALE as usize) {
+            frac.push('0');
+        }
+        let frac: i64 = frac.parse()?;
+        let minor = whole
+            .checked_mul(ONE)
+            .and_then(|w| w.checked_add(frac))
+            .ok_or_else(|| anyhow!("{s} is too large."))?;
+        Ok(Self(if negative { -minor } else { minor }))
+    }
+}
+
+impl From<Money> for Bson {
+    fn from(value: Money) -> Self {
+        Self::Int64(value.0)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+struct MoneyVisitor;
+
+impl Visitor<'_> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an integer amount of minor units or a legacy float amount")
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Money(v))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        i64::try_from(v)
+            .map(Money)
+            .map_err(|_| E::custom("amount too large"))
+    }
+
+    /// Documents from before amounts were stored as minor units.
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Money::from_f64(v, MONEY_SCALE).map_err(E::custom)
+    }
+}
+
+/// Every field that holds an amount of currency, by collection.
+const MONEY_FIELDS: [(&str, &str); 5] = [
+    ("balances", "Amount"),
+    ("currencies", "EarnMin"),
+    ("currencies", "EarnMax"),
+    ("items", "Value"),
+    ("storeEntries", "Value"),
+];
+
+/// Converts every amount that is still stored as a float of whole units into an integer of
+/// minor units. Running it more than once is harmless as it only touches the floats.
+///
+/// # Errors
+/// - Any `MongoDB` error occurs.
+pub async fn migrate_legacy_amounts() -> Result<()> {
+    let db = CLIENT.get().await.database("conebot");
+    for (coll, field) in MONEY_FIELDS {
+        let coll = db.collection::<Document>(coll);
+        let filterdoc = doc! {
+            field: { "$type": "double" },
+        };
+        let updatedoc =
+            vec![
+                doc! {
+                "$set": {
+                    field: {
+                        "$toLong": {
+                            "$round": [{ "$multiply": [format!("${field}"), ONE] }, 0],
+                        },
+                    },
+                },
+            }
+            ];
+        coll.update_many(filterdoc, updatedoc, None).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(Money::from_f64(0.29, 2).unwrap(), Money::from_minor(2900));
+        assert_eq!(Money::from_f64(1.999, 2).unwrap(), Money::from_minor(19900));
+        assert_eq!(Money::from_f64(-1.999, 2).unwrap(), Money::from_minor(-19900));
+        assert_eq!(Money::from_f64(12.3456, 4).unwrap(), Money::from_minor(123_456));
+        assert_eq!(Money::from_f64(12.3456, 0).unwrap(), Money::from_minor(120_000));
+        assert!(Money::from_f64(f64::NAN, 2).is_err());
+        assert!(Money::from_f64(f64::INFINITY, 2).is_err());
+        assert!(Money::from_f64(1e300, 2).is_err());
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for s in ["0", "3", "12.5", "0.01", "-7.25", "123456.7891"] {
+            assert_eq!(s.parse::<Money>().unwrap().to_string(), s);
+        }
+        assert_eq!("1.23456".parse::<Money>().unwrap(), Money::from_minor(12345));
+        assert_eq!(".5".parse::<Money>().unwrap(), Money::from_minor(5000));
+        assert!("abc".parse::<Money>().is_err());
+        assert!("1.2.3".parse::<Money>().is_err());
+        assert!("".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        let cent = "0.01".parse::<Money>().unwrap();
+        let mut total = Money::ZERO;
+        for _ in 0..1000 {
+            total = total.checked_add(cent).unwrap();
+        }
+        assert_eq!(total, Money::from_whole(10).unwrap());
+        assert_eq!(cent.checked_mul(1000).unwrap(), total);
+        assert_eq!(total.checked_sub(total).unwrap(), Money::ZERO);
+        assert!(Money::MAX.checked_add(cent).is_none());
+        assert!(Money::MAX.checked_mul(2).is_none());
+    }
+
+    #[test]
+    fn test_mul_rate() {
+        let amount = Money::from_whole(10).unwrap();
+        assert_eq!(amount.checked_mul_rate(0.333, 2).unwrap(), "3.33".parse().unwrap());
+        assert_eq!(amount.checked_mul_rate(2.5, 0).unwrap(), Money::from_whole(25).unwrap());
+        assert!(amount.checked_mul_rate(f64::NAN, 2).is_err());
+        assert!(amount.checked_mul_rate(-1.0, 2).is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Wrapper {
+            amount: Money,
+        }
+        let new = mongodb::bson::to_document(&Wrapper { amount: Money::from_minor(12_500) }).unwrap();
+        assert_eq!(new.get("amount"), Some(&Bson::Int64(12_500)));
+        let legacy = doc! { "amount": 1.25 };
+        let legacy: Wrapper = mongodb::bson::from_document(legacy).unwrap();
+        assert_eq!(legacy.amount, Money::from_minor(12_500));
+    }
+}